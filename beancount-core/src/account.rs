@@ -1,7 +1,10 @@
-use typed_builder::TypedBuilder;
+use std::fmt;
+use std::str::FromStr;
 
+use thiserror::Error;
+use typed_builder::TypedBuilder;
 
-use super::account_types::AccountType;
+use super::account_types::{AccountType, RootNames};
 
 /// Represents an account.
 ///
@@ -19,6 +22,21 @@ use super::account_types::AccountType;
 /// Expenses:Food:Groceries
 /// ```
 ///
+/// Accounts can be parsed from and displayed as such names, using the default root account names.
+/// An account without parts, such as `Assets`, is the root account of its type.
+///
+/// ```rust
+/// use beancount_core::{Account, AccountType};
+///
+/// let account: Account = "Assets:US:BofA:Checking".parse().unwrap();
+/// assert_eq!(account.ty, AccountType::Assets);
+/// assert_eq!(account.parts, ["US", "BofA", "Checking"]);
+/// assert_eq!(account.to_string(), "Assets:US:BofA:Checking");
+/// assert_eq!(account.leaf(), "Checking");
+/// assert_eq!(account.parent().unwrap().to_string(), "Assets:US:BofA");
+/// assert!(account.is_descendant_of(&"Assets:US".parse().unwrap()));
+/// ```
+///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.17ry42rqbuiu>
 #[derive(Clone, Debug, Eq, PartialEq, Hash, TypedBuilder)]
 pub struct Account {
@@ -28,3 +46,208 @@ pub struct Account {
     /// Optional parts of the account following the account type.
     pub parts: Vec<String>,
 }
+
+/// An error returned when parsing an invalid account name.
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+#[error("invalid account '{input}'")]
+pub struct ParseAccountError {
+    /// The string which could not be parsed.
+    pub input: String,
+}
+
+impl Account {
+    /// Parses an account name whose root account is named according to `root_names`.
+    ///
+    /// ```rust
+    /// use beancount_core::account_types::RootNames;
+    /// use beancount_core::{Account, AccountType};
+    ///
+    /// let mut names = RootNames::default();
+    /// names.set(AccountType::Assets, "Activa");
+    /// let account = Account::parse_with_root_names("Activa:Kas", &names).unwrap();
+    /// assert_eq!(account.ty, AccountType::Assets);
+    /// assert_eq!(account.display_with_root_names(&names), "Activa:Kas");
+    /// assert!(Account::parse_with_root_names("Assets:Kas", &names).is_err());
+    /// ```
+    pub fn parse_with_root_names(
+        s: &str,
+        root_names: &RootNames,
+    ) -> Result<Account, ParseAccountError> {
+        let err = || ParseAccountError {
+            input: s.to_owned(),
+        };
+        let mut components = s.split(':');
+        let root = components.next().ok_or_else(err)?;
+        let ty = root_names.account_type(root).ok_or_else(err)?;
+        let parts = components
+            .map(|part| {
+                if is_valid_part(part) {
+                    Ok(part.to_owned())
+                } else {
+                    Err(err())
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Account { ty, parts })
+    }
+
+    /// The full name of the account, with its root account named according to `root_names`.
+    pub fn display_with_root_names(&self, root_names: &RootNames) -> String {
+        let mut name = root_names.name(self.ty).to_owned();
+        for part in &self.parts {
+            name.push(':');
+            name.push_str(part);
+        }
+        name
+    }
+
+    /// The root account of the same type, e.g. `Assets` for `Assets:Cash`.
+    pub fn root(&self) -> Account {
+        Account {
+            ty: self.ty,
+            parts: Vec::new(),
+        }
+    }
+
+    /// Whether this account is a root account, i.e. it has no parts.
+    pub fn is_root(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// The parent of this account, or `None` for a root account.
+    pub fn parent(&self) -> Option<Account> {
+        let (_, parents) = self.parts.split_last()?;
+        Some(Account {
+            ty: self.ty,
+            parts: parents.to_vec(),
+        })
+    }
+
+    /// The ancestors of this account, starting with its parent and ending with its root account.
+    pub fn ancestors(&self) -> impl Iterator<Item = Account> + '_ {
+        (0..self.parts.len()).rev().map(move |len| Account {
+            ty: self.ty,
+            parts: self.parts[..len].to_vec(),
+        })
+    }
+
+    /// The last component of the account name, e.g. `Checking` for `Assets:Bank:Checking`. This
+    /// is the default root account name for a root account.
+    pub fn leaf(&self) -> &str {
+        self.parts
+            .last()
+            .map_or(self.ty.default_name(), String::as_str)
+    }
+
+    /// The number of components of the account name, e.g. 1 for `Assets` and 3 for
+    /// `Assets:Bank:Checking`.
+    pub fn depth(&self) -> usize {
+        self.parts.len() + 1
+    }
+
+    /// Whether this account is a strict descendant of `ancestor`, i.e. a child account, a child of
+    /// a child account and so on.
+    pub fn is_descendant_of(&self, ancestor: &Account) -> bool {
+        self.parts.len() > ancestor.parts.len() && self.is_same_or_descendant_of(ancestor)
+    }
+
+    /// Whether this account is `ancestor` itself or one of its descendants.
+    pub fn is_same_or_descendant_of(&self, ancestor: &Account) -> bool {
+        self.ty == ancestor.ty && self.parts.starts_with(&ancestor.parts)
+    }
+
+    /// The sub-account named by appending `name` to this account. `name` may consist of several
+    /// colon-separated components.
+    ///
+    /// ```rust
+    /// use beancount_core::Account;
+    ///
+    /// let bank: Account = "Assets:Bank".parse().unwrap();
+    /// assert_eq!(bank.join("Checking:EUR").to_string(), "Assets:Bank:Checking:EUR");
+    /// ```
+    pub fn join(&self, name: &str) -> Account {
+        let mut parts = self.parts.clone();
+        parts.extend(name.split(':').map(str::to_owned));
+        Account { ty: self.ty, parts }
+    }
+}
+
+pub(crate) fn is_valid_part(part: &str) -> bool {
+    let mut chars = part.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_uppercase() || c.is_numeric())
+        && chars.all(|c| c.is_alphanumeric() || c == '-')
+}
+
+impl FromStr for Account {
+    type Err = ParseAccountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Account::parse_with_root_names(s, &RootNames::default())
+    }
+}
+
+impl fmt::Display for Account {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.ty.default_name())?;
+        for part in &self.parts {
+            write!(f, ":{}", part)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(s: &str) -> Account {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        assert_eq!(account("Assets").parts, Vec::<String>::new());
+        assert_eq!(
+            account("Expenses:Café:2021").to_string(),
+            "Expenses:Café:2021"
+        );
+        assert_eq!(account("Equity:Retained-Earnings").ty, AccountType::Equity);
+        for invalid in [
+            "",
+            "Asset:Cash",
+            "Assets:cash",
+            "Assets::Cash",
+            "Assets:Cash:",
+            "Assets:A B",
+        ] {
+            assert!(invalid.parse::<Account>().is_err(), "{}", invalid);
+        }
+        assert_eq!(
+            "Assets:cash".parse::<Account>().unwrap_err().to_string(),
+            "invalid account 'Assets:cash'"
+        );
+    }
+
+    #[test]
+    fn hierarchy() {
+        let checking = account("Assets:Bank:Checking");
+        let ancestors: Vec<_> = checking.ancestors().map(|a| a.to_string()).collect();
+        assert_eq!(ancestors, ["Assets:Bank", "Assets"]);
+        assert_eq!(checking.root(), account("Assets"));
+        assert_eq!(checking.depth(), 3);
+        assert_eq!(account("Assets").parent(), None);
+        assert_eq!(account("Assets").leaf(), "Assets");
+        assert!(account("Assets").is_root());
+
+        let bank = account("Assets:Bank");
+        assert!(checking.is_descendant_of(&bank));
+        assert!(checking.is_descendant_of(&account("Assets")));
+        assert!(!checking.is_descendant_of(&checking));
+        assert!(checking.is_same_or_descendant_of(&checking));
+        assert!(!account("Assets:Banking").is_descendant_of(&bank));
+        assert!(!account("Liabilities:Bank").is_same_or_descendant_of(&bank));
+        assert_eq!(bank.join("Checking"), checking);
+    }
+}
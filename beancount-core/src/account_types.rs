@@ -11,6 +11,15 @@ pub enum AccountType {
 }
 
 impl AccountType {
+    /// All account types, in the order beancount lists them.
+    pub const ALL: [AccountType; 5] = [
+        AccountType::Assets,
+        AccountType::Liabilities,
+        AccountType::Equity,
+        AccountType::Income,
+        AccountType::Expenses,
+    ];
+
     /// Get the default name for this account type.
     ///
     /// # Example
@@ -33,3 +42,53 @@ impl AccountType {
         }
     }
 }
+
+/// The names of the root accounts, which can be changed with the `name_*` options:
+///
+/// ```text
+/// option "name_assets" "Activa"
+/// ```
+///
+/// # Example
+/// ```rust
+/// use beancount_core::account_types::RootNames;
+/// use beancount_core::AccountType;
+///
+/// let mut names = RootNames::default();
+/// assert_eq!(names.name(AccountType::Assets), "Assets");
+/// names.set(AccountType::Assets, "Activa");
+/// assert_eq!(names.account_type("Activa"), Some(AccountType::Assets));
+/// assert_eq!(names.account_type("Assets"), None);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct RootNames {
+    names: [String; 5],
+}
+
+impl RootNames {
+    /// The root account name of the given account type.
+    pub fn name(&self, ty: AccountType) -> &str {
+        &self.names[ty as usize]
+    }
+
+    /// Renames the root account of the given account type.
+    pub fn set(&mut self, ty: AccountType, name: impl Into<String>) {
+        self.names[ty as usize] = name.into();
+    }
+
+    /// The account type whose root account has the given name.
+    pub fn account_type(&self, name: &str) -> Option<AccountType> {
+        AccountType::ALL
+            .iter()
+            .copied()
+            .find(|ty| self.name(*ty) == name)
+    }
+}
+
+impl Default for RootNames {
+    fn default() -> Self {
+        RootNames {
+            names: AccountType::ALL.map(|ty| ty.default_name().to_string()),
+        }
+    }
+}
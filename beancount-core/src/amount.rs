@@ -7,6 +7,7 @@ use super::Currency;
 
 /// A number of units of a certain commodity.
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Amount {
     /// The value of the amount.
     pub num: Decimal,
@@ -27,6 +28,7 @@ impl cmp::PartialOrd for Amount {
 
 /// An amount that may have missing units and/or commodity.
 #[derive(Clone, Debug, Eq, PartialEq, Hash, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IncompleteAmount {
     /// The (optional) value of the amount.
     #[builder(default)]
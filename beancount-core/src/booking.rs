@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+use super::account::Account;
+use super::amount::Amount;
+use super::directives::{sort_directives, Booking, Directive, Transaction};
+use super::interpolate::{complete_transaction, InterpolationErrorKind};
+use super::inventory::Inventory;
+use super::position::{Cost, CostSpec, Position};
+use super::posting::Posting;
+use super::Date;
+
+/// An error encountered while booking a posting.
+#[derive(Clone, Debug, PartialEq, Error)]
+#[error("{kind}")]
+pub struct BookingError {
+    /// The type of error.
+    pub kind: BookingErrorKind,
+
+    /// The transaction containing the posting that could not be booked.
+    pub transaction: Transaction,
+
+    /// Index of the offending posting within the transaction, if the error concerns a single
+    /// posting.
+    pub posting: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Error)]
+pub enum BookingErrorKind {
+    /// No lot held in the account matches the cost spec of the reduction.
+    #[error("no position matches the reduction")]
+    NoMatch,
+
+    /// Several lots match the cost spec of the reduction and the booking method does not allow
+    /// picking one of them.
+    #[error("ambiguous reduction: {} lots match", matches.len())]
+    Ambiguous { matches: Vec<Position> },
+
+    /// The matching lots do not hold enough units for the reduction.
+    #[error("not enough units to reduce: only {available} available")]
+    InsufficientUnits { available: Decimal },
+
+    /// The transaction could not be completed after booking its reductions.
+    #[error(transparent)]
+    Interpolation(#[from] InterpolationErrorKind),
+}
+
+/// Book the postings held at cost of all transactions against the lots of their accounts.
+///
+/// Postings which add units to an account at cost create new lots (augmentations), while postings
+/// which remove units have to be matched against the lots already held (reductions). The cost
+/// spec of a reduction may be partial, e.g. `{}`, `{2020-01-01}` or `{"label"}`, in which case it
+/// is matched against every lot it is compatible with, and the booking method of the account
+/// decides which of those lots are reduced.
+///
+/// Every posting held at cost is replaced by one or more postings whose cost spec is fully
+/// specified (see [CostSpec::to_cost](../position/struct.CostSpec.html#method.to_cost)): an
+/// augmentation gets its per-unit cost and the date of the transaction if no date was given, and
+/// a reduction is split into one posting per lot it reduces. Once its reductions are booked, the
+/// transaction is completed with
+/// [complete_transaction](../interpolate/fn.complete_transaction.html), so that augmentations
+/// whose cost or units were elided can be added to the inventory as well.
+///
+/// The booking method of an account is taken from its `open` directive, falling back to
+/// `default_method`. Transactions that cannot be booked are dropped from the result and reported
+/// as errors. The returned directives are sorted with
+/// [sort_directives](../directives/fn.sort_directives.html).
+///
+/// <https://beancount.github.io/docs/how_inventories_work.html>
+pub fn book(
+    mut directives: Vec<Directive>,
+    default_method: Booking,
+) -> (Vec<Directive>, Vec<BookingError>) {
+    sort_directives(&mut directives);
+
+    let mut methods: HashMap<Account, Booking> = HashMap::new();
+    let mut balances: HashMap<Account, Inventory> = HashMap::new();
+    let mut errors = Vec::new();
+    let mut booked = Vec::with_capacity(directives.len());
+
+    for directive in directives {
+        match directive {
+            Directive::Open(ref open) => {
+                if let Some(method) = open.booking {
+                    methods.insert(open.account.clone(), method);
+                }
+                booked.push(directive);
+            }
+            Directive::Transaction(txn) => {
+                match book_transaction(&txn, &methods, default_method, &mut balances) {
+                    Ok(postings) => {
+                        booked.push(Directive::Transaction(Transaction { postings, ..txn }))
+                    }
+                    Err((kind, posting)) => errors.push(BookingError {
+                        kind,
+                        transaction: txn,
+                        posting,
+                    }),
+                }
+            }
+            _ => booked.push(directive),
+        }
+    }
+
+    (booked, errors)
+}
+
+/// Book the postings of a single transaction and complete it. The inventories of the accounts are
+/// only updated if the whole transaction could be booked.
+fn book_transaction(
+    txn: &Transaction,
+    methods: &HashMap<Account, Booking>,
+    default_method: Booking,
+    balances: &mut HashMap<Account, Inventory>,
+) -> Result<Vec<Posting>, (BookingErrorKind, Option<usize>)> {
+    // Reductions are matched against scratch copies of the inventories, so that several postings
+    // to the same account within the transaction see each other.
+    let mut scratch: HashMap<&Account, Inventory> = HashMap::new();
+    let mut postings = Vec::with_capacity(txn.postings.len());
+
+    for (index, posting) in txn.postings.iter().enumerate() {
+        let inventory = scratch
+            .entry(&posting.account)
+            .or_insert_with(|| balances.get(&posting.account).cloned().unwrap_or_default());
+        let method = methods
+            .get(&posting.account)
+            .copied()
+            .unwrap_or(default_method);
+        let booked =
+            book_posting(posting, txn, method, inventory).map_err(|kind| (kind, Some(index)))?;
+        postings.extend(booked);
+    }
+
+    let completed = complete_transaction(&Transaction {
+        postings,
+        ..txn.clone()
+    })
+    .map_err(|kind| (kind.into(), None))?;
+
+    for posting in &completed.postings {
+        if let Some(position) = posting.position() {
+            balances
+                .entry(posting.account.clone())
+                .or_default()
+                .add_position(position);
+        }
+    }
+    Ok(completed.postings)
+}
+
+/// Book a single posting against the inventory of its account, updating the inventory.
+fn book_posting(
+    posting: &Posting,
+    txn: &Transaction,
+    method: Booking,
+    inventory: &mut Inventory,
+) -> Result<Vec<Posting>, BookingErrorKind> {
+    // Postings with missing units are left to interpolation.
+    let units = match (&posting.units.num, &posting.units.currency) {
+        (Some(num), Some(currency)) => Amount {
+            num: *num,
+            currency: currency.clone(),
+        },
+        _ => return Ok(vec![posting.clone()]),
+    };
+    let spec = match &posting.cost {
+        Some(spec) => spec,
+        None => {
+            inventory.add_amount(units, None);
+            return Ok(vec![posting.clone()]);
+        }
+    };
+
+    if method == Booking::None || !inventory.is_reduced_by(&units) {
+        let spec = augmentation_cost(spec, &units, txn);
+        if let Some(cost) = spec.to_cost() {
+            inventory.add_amount(units, Some(cost));
+        }
+        return Ok(vec![Posting {
+            cost: Some(spec),
+            ..posting.clone()
+        }]);
+    }
+
+    let method = if spec.merge_cost {
+        Booking::Average
+    } else {
+        method
+    };
+    let mut matches: Vec<Position> = inventory
+        .iter()
+        .filter(|pos| {
+            pos.units.currency == units.currency
+                && pos.units.num.is_sign_negative() != units.num.is_sign_negative()
+                && pos
+                    .cost
+                    .as_ref()
+                    .is_some_and(|cost| cost_matches(spec, &units, cost))
+        })
+        .cloned()
+        .collect();
+    if matches.is_empty() {
+        return Err(BookingErrorKind::NoMatch);
+    }
+
+    let wanted = units.num.abs();
+    let available: Decimal = matches.iter().map(|pos| pos.units.num.abs()).sum();
+    if available < wanted {
+        return Err(BookingErrorKind::InsufficientUnits { available });
+    }
+
+    match method {
+        Booking::Strict | Booking::StrictWithSize if matches.len() > 1 => {
+            let exact = matches
+                .iter()
+                .filter(|pos| pos.units.num.abs() == wanted)
+                .min_by_key(|pos| lot_date(pos))
+                .cloned();
+            match exact {
+                Some(lot) if method == Booking::StrictWithSize => matches = vec![lot],
+                // Reducing the whole inventory of matching lots is never ambiguous.
+                _ if available == wanted => {}
+                _ => return Err(BookingErrorKind::Ambiguous { matches }),
+            }
+        }
+        Booking::Fifo => matches.sort_by(|a, b| lot_date(a).cmp(&lot_date(b))),
+        Booking::Lifo => matches.sort_by(|a, b| lot_date(b).cmp(&lot_date(a))),
+        Booking::Average => {
+            let merged = matches.iter().cloned().collect::<Inventory>().average();
+            for lot in matches {
+                inventory.add_position(-lot);
+            }
+            inventory.add_inventory(&merged);
+            matches = merged.into_iter().collect();
+        }
+        _ => {}
+    }
+
+    let mut remaining = wanted;
+    let mut postings = Vec::new();
+    for lot in matches {
+        if remaining.is_zero() {
+            break;
+        }
+        let cost = match lot.cost {
+            Some(cost) => cost,
+            None => continue,
+        };
+        let take = remaining.min(lot.units.num.abs());
+        remaining -= take;
+        let reduced = Amount {
+            num: if units.num.is_sign_negative() {
+                -take
+            } else {
+                take
+            },
+            currency: units.currency.clone(),
+        };
+        inventory.add_amount(reduced.clone(), Some(cost.clone()));
+        postings.push(Posting {
+            units: reduced.into(),
+            cost: Some(cost.into()),
+            ..posting.clone()
+        });
+    }
+    Ok(postings)
+}
+
+/// Complete the cost spec of an augmentation as far as possible: compute the per-unit cost from
+/// the per-unit and/or total numbers, and use the date of the transaction if none was given.
+fn augmentation_cost(spec: &CostSpec, units: &Amount, txn: &Transaction) -> CostSpec {
+    let quantity = units.num.abs();
+    let number_per = match (spec.number_per, spec.number_total) {
+        (Some(per), None) => Some(per),
+        (per, Some(total)) if !quantity.is_zero() => {
+            Some(per.unwrap_or_default() + total / quantity)
+        }
+        _ => None,
+    };
+    CostSpec {
+        number_per,
+        number_total: if number_per.is_some() {
+            None
+        } else {
+            spec.number_total
+        },
+        currency: spec.currency.clone(),
+        date: Some(spec.date.unwrap_or(txn.date)),
+        label: spec.label.clone(),
+        merge_cost: false,
+    }
+}
+
+/// Whether a lot held at `cost` is compatible with the (possibly partial) cost spec of a
+/// reduction of `units`.
+fn cost_matches(spec: &CostSpec, units: &Amount, cost: &Cost) -> bool {
+    let number_per = match (spec.number_per, spec.number_total) {
+        (per, Some(total)) if !units.num.is_zero() => {
+            Some(per.unwrap_or_default() + total / units.num.abs())
+        }
+        (per, _) => per,
+    };
+    number_per.is_none_or(|n| n == cost.number)
+        && spec.currency.as_ref().is_none_or(|c| *c == cost.currency)
+        && spec.date.as_ref().is_none_or(|d| *d == cost.date)
+        && spec
+            .label
+            .as_ref()
+            .is_none_or(|l| cost.label.as_ref() == Some(l))
+}
+
+fn lot_date(pos: &Position) -> Option<&Date> {
+    pos.cost.as_ref().map(|cost| &cost.date)
+}
@@ -0,0 +1,112 @@
+//! Conversions of positions to units, cost and market value.
+//!
+//! <https://github.com/beancount/beancount/blob/master/beancount/core/convert.py>
+
+use super::amount::Amount;
+use super::inventory::Inventory;
+use super::position::Position;
+use super::prices::PriceMap;
+use super::Date;
+
+/// The result of converting positions using a price map.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Conversion {
+    /// The sum of the converted amounts. Positions which could not be priced contribute their
+    /// units unchanged.
+    pub inventory: Inventory,
+
+    /// The positions for which no price was available.
+    pub unpriced: Vec<Position>,
+}
+
+/// Sums the units of the positions, disregarding their cost.
+pub fn units<'a>(positions: impl IntoIterator<Item = &'a Position>) -> Inventory {
+    sum(positions.into_iter().map(|pos| pos.units.clone()))
+}
+
+/// Sums the cost basis of the positions: the total cost of positions held at cost and the units
+/// of the others.
+pub fn cost<'a>(positions: impl IntoIterator<Item = &'a Position>) -> Inventory {
+    sum(positions.into_iter().map(Position::weight))
+}
+
+/// Sums the market value of the positions in the currency of their cost, as of `date`, or using
+/// the latest prices if `date` is `None`. Positions which are not held at cost are valued at
+/// their units.
+pub fn market_value<'a>(
+    positions: impl IntoIterator<Item = &'a Position>,
+    prices: &PriceMap,
+    date: Option<Date>,
+) -> Conversion {
+    convert_each(positions, |pos| position_value(pos, prices, date))
+}
+
+/// Sums the value of the positions in `currency`, as of `date`, or using the latest prices if
+/// `date` is `None`. Positions already in `currency` are kept as they are.
+pub fn convert<'a>(
+    positions: impl IntoIterator<Item = &'a Position>,
+    currency: &str,
+    prices: &PriceMap,
+    date: Option<Date>,
+) -> Conversion {
+    convert_each(positions, |pos| {
+        convert_amount(&pos.units, currency, prices, date)
+    })
+}
+
+/// The market value of a position in the currency of its cost, or its units if it is not held
+/// at cost. Returns `None` if no price is available.
+pub fn position_value(
+    position: &Position,
+    prices: &PriceMap,
+    date: Option<Date>,
+) -> Option<Amount> {
+    match &position.cost {
+        Some(cost) => convert_amount(&position.units, &cost.currency, prices, date),
+        None => Some(position.units.clone()),
+    }
+}
+
+/// Converts an amount to `currency` at the price as of `date`, or at the latest price if `date`
+/// is `None`. Returns `None` if no price is available or if the converted number overflows.
+pub fn convert_amount(
+    amount: &Amount,
+    currency: &str,
+    prices: &PriceMap,
+    date: Option<Date>,
+) -> Option<Amount> {
+    if amount.currency == currency {
+        return Some(amount.clone());
+    }
+    let (_, rate) = match date {
+        Some(date) => prices.get_price(&amount.currency, currency, date),
+        None => prices.get_latest_price(&amount.currency, currency),
+    }?;
+    Some(Amount {
+        num: amount.num.checked_mul(rate)?,
+        currency: currency.to_string(),
+    })
+}
+
+fn convert_each<'a, F>(positions: impl IntoIterator<Item = &'a Position>, mut f: F) -> Conversion
+where
+    F: FnMut(&Position) -> Option<Amount>,
+{
+    let mut conversion = Conversion::default();
+    for pos in positions {
+        let amount = f(pos).unwrap_or_else(|| {
+            conversion.unpriced.push(pos.clone());
+            pos.units.clone()
+        });
+        conversion.inventory.add_amount(amount, None);
+    }
+    conversion
+}
+
+fn sum(amounts: impl Iterator<Item = Amount>) -> Inventory {
+    let mut inventory = Inventory::new();
+    for amount in amounts {
+        inventory.add_amount(amount, None);
+    }
+    inventory
+}
@@ -1,66 +1,412 @@
-use std::borrow::Cow;
-use std::{fmt, fmt::Display};
+use std::fmt;
+use std::str::FromStr;
 
 #[cfg(feature = "chrono")]
-use chrono::NaiveDate;
+use chrono::{Datelike, NaiveDate};
+use thiserror::Error;
 
-/// Represents a beancount date. It can be created using the `from_*_unchecked` methods.
-/// Alternatively, with the `chrono` feature enabled, it can be converted from a `NaiveDate`.
+/// Represents a beancount date: a day of the proleptic Gregorian calendar.
+///
+/// Dates are parsed from the `YYYY-MM-DD` format, where `/` is accepted as a separator as well,
+/// and are always displayed in the ISO 8601 `YYYY-MM-DD` format, with a leading `-` for negative
+/// years. Alternatively, with the `chrono` feature enabled, it can be converted from a
+/// `NaiveDate`, and to one if it is within the range supported by `chrono`.
 ///
 /// # Example
 /// ```rust
-/// use beancount_core::Date;
+/// use beancount_core::{Date, Weekday};
 ///
-/// // Create a Date from a String
-/// let past: Date = Date::from_str_unchecked("2020-01-01");
-/// let later: Date = Date::from_str_unchecked("43020-01-01");
+/// let past: Date = "2020-01-01".parse().unwrap();
+/// let later: Date = "43020/01/01".parse().unwrap();
 /// assert!(later > past);
+/// assert_eq!(later.to_string(), "43020-01-01");
+/// assert!("2021-02-29".parse::<Date>().is_err());
+///
+/// let date = Date::from_ymd(2020, 2, 27).unwrap();
+/// assert_eq!(date.add_days(3).unwrap().to_string(), "2020-03-01");
+/// assert_eq!(date.end_of_month().to_string(), "2020-02-29");
+/// assert_eq!(date.weekday(), Weekday::Thursday);
 ///
 /// // Create a Date from a chrono type.
 /// #[cfg(feature = "chrono")]
-/// let today: Date = chrono::Local::today().naive_local().into();
+/// let today: Date = chrono::Local::now().date_naive().into();
 /// ```
-#[derive(Eq, PartialEq, Debug, Clone, Ord, PartialOrd, Hash)]
-pub struct Date(String);
+#[derive(Eq, PartialEq, Clone, Copy, Ord, PartialOrd, Hash)]
+pub struct Date {
+    year: i32,
+    month: u8,
+    day: u8,
+}
+
+/// A day of the week.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+/// An error returned when parsing an invalid date.
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+#[error("invalid date '{input}'")]
+pub struct ParseDateError {
+    /// The string which could not be parsed.
+    pub input: String,
+}
+
+/// An error returned when converting a date outside of the range supported by `chrono`.
+#[cfg(feature = "chrono")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Error)]
+#[error("date {date} is out of range")]
+pub struct DateOutOfRangeError {
+    /// The date which could not be converted.
+    pub date: Date,
+}
 
 impl Date {
-    pub fn from_str_unchecked(s: &str) -> Date {
-        Date(s.into())
+    /// The date with the given year, month (1 to 12) and day (1 to 31), if it exists.
+    pub fn from_ymd(year: i32, month: u32, day: u32) -> Option<Date> {
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(Date {
+            year,
+            month: month as u8,
+            day: day as u8,
+        })
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    /// The month, from 1 to 12.
+    pub fn month(&self) -> u32 {
+        self.month.into()
+    }
+
+    /// The day of the month, from 1 to 31.
+    pub fn day(&self) -> u32 {
+        self.day.into()
+    }
+
+    /// The quarter of the year, from 1 to 4.
+    pub fn quarter(&self) -> u32 {
+        (self.month() - 1) / 3 + 1
+    }
+
+    pub fn weekday(&self) -> Weekday {
+        // 1970-01-01 was a Thursday.
+        match (self.days_since_epoch() + 3).rem_euclid(7) {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        }
+    }
+
+    /// Whether the year of the date is a leap year.
+    pub fn is_leap_year(&self) -> bool {
+        is_leap_year(self.year)
+    }
+
+    /// The date `days` days after this one, or before it if `days` is negative. Returns `None` if
+    /// the resulting year is out of range.
+    pub fn add_days(self, days: i64) -> Option<Date> {
+        Date::from_days_since_epoch(self.days_since_epoch().checked_add(days)?)
+    }
+
+    /// The date `months` months after this one, or before it if `months` is negative. The day is
+    /// clamped to the last day of the resulting month, e.g. one month after `2021-01-31` is
+    /// `2021-02-28`. Returns `None` if the resulting year is out of range.
+    pub fn add_months(self, months: i32) -> Option<Date> {
+        let index = i64::from(self.year) * 12 + i64::from(self.month) - 1 + i64::from(months);
+        let year = i32::try_from(index.div_euclid(12)).ok()?;
+        let month = index.rem_euclid(12) as u32 + 1;
+        let day = self.day().min(days_in_month(year, month));
+        Some(Date {
+            year,
+            month: month as u8,
+            day: day as u8,
+        })
+    }
+
+    /// The number of days from `earlier` to this date, negative if `earlier` is after this date.
+    pub fn days_since(self, earlier: Date) -> i64 {
+        self.days_since_epoch() - earlier.days_since_epoch()
+    }
+
+    pub fn start_of_month(self) -> Date {
+        Date { day: 1, ..self }
+    }
+
+    pub fn end_of_month(self) -> Date {
+        let day = days_in_month(self.year, self.month()) as u8;
+        Date { day, ..self }
+    }
+
+    pub fn start_of_quarter(self) -> Date {
+        let month = (self.quarter() * 3 - 2) as u8;
+        Date {
+            month,
+            day: 1,
+            ..self
+        }
     }
 
-    pub fn from_string_unchecked(s: String) -> Date {
-        Date(s)
+    pub fn end_of_quarter(self) -> Date {
+        let month = (self.quarter() * 3) as u8;
+        Date { month, ..self }.end_of_month()
     }
 
-    pub fn from_cow_unchecked(s: Cow<'_, str>) -> Date {
-        Date(s.into_owned())
+    pub fn start_of_year(self) -> Date {
+        Date {
+            month: 1,
+            day: 1,
+            ..self
+        }
     }
+
+    pub fn end_of_year(self) -> Date {
+        Date {
+            month: 12,
+            day: 31,
+            ..self
+        }
+    }
+
+    /// Days since 1970-01-01, see <http://howardhinnant.github.io/date_algorithms.html>.
+    fn days_since_epoch(&self) -> i64 {
+        let (month, day) = (i64::from(self.month), i64::from(self.day));
+        let year = i64::from(self.year) - i64::from(month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    fn from_days_since_epoch(days: i64) -> Option<Date> {
+        let days = days.checked_add(719_468)?;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = (shifted_month + 2) % 12 + 1;
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Some(Date {
+            year: i32::try_from(year).ok()?,
+            month: month as u8,
+            day: day as u8,
+        })
+    }
+}
+
+fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
 }
 
-impl From<Date> for Cow<'_, str> {
-    fn from(d: Date) -> Self {
-        d.0.into()
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
     }
 }
 
-impl Display for Date {
+impl FromStr for Date {
+    type Err = ParseDateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseDateError {
+            input: s.to_owned(),
+        };
+        let (negative, unsigned) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let mut parts = unsigned.split(['-', '/']);
+        let mut part = |min_len: usize, max_len: usize| {
+            parts
+                .next()
+                .filter(|p| (min_len..=max_len).contains(&p.len()))
+                .filter(|p| p.bytes().all(|b| b.is_ascii_digit()))
+                .ok_or_else(err)
+        };
+        let year: i64 = part(4, 10)?.parse().map_err(|_| err())?;
+        let year = i32::try_from(if negative { -year } else { year }).map_err(|_| err())?;
+        let month = part(2, 2)?.parse().map_err(|_| err())?;
+        let day = part(2, 2)?.parse().map_err(|_| err())?;
+        if parts.next().is_some() {
+            return Err(err());
+        }
+        Date::from_ymd(year, month, day).ok_or_else(err)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.year < 0 {
+            write!(f, "-")?;
+        }
+        write!(
+            f,
+            "{:04}-{:02}-{:02}",
+            self.year.unsigned_abs(),
+            self.month,
+            self.day
+        )
+    }
+}
+
+impl fmt::Debug for Date {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.0.fmt(f)
+        write!(f, "Date({})", self)
     }
 }
 
 #[cfg(feature = "chrono")]
 impl From<NaiveDate> for Date {
     fn from(d: NaiveDate) -> Self {
-        Date::from_string_unchecked(d.format("%Y-%m-%d").to_string())
+        Date {
+            year: d.year(),
+            month: d.month() as u8,
+            day: d.day() as u8,
+        }
     }
 }
 
 #[cfg(feature = "chrono")]
-#[test]
-fn test_date_from_chrono() {
-    assert_eq!(
-        Date::from(chrono::NaiveDate::from_ymd(2020, 05, 05)),
-        Date::from_str_unchecked("2020-05-05")
-    );
+impl TryFrom<Date> for NaiveDate {
+    type Error = DateOutOfRangeError;
+
+    fn try_from(d: Date) -> Result<Self, Self::Error> {
+        NaiveDate::from_ymd_opt(d.year(), d.month(), d.day()).ok_or(DateOutOfRangeError { date: d })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> Date {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        assert_eq!(date("2021/01/05"), date("2021-01-05"));
+        assert_eq!(date("2021/01/05").to_string(), "2021-01-05");
+        assert_eq!(Date::from_ymd(2020, 2, 29), Some(date("2020-02-29")));
+        for year in [-5, -2021, 12_345, i32::MAX, i32::MIN] {
+            let d = Date::from_ymd(year, 3, 1).unwrap();
+            assert_eq!(d.to_string().parse::<Date>(), Ok(d), "{}", d);
+        }
+        assert_eq!(Date::from_ymd(-5, 1, 1).unwrap().to_string(), "-0005-01-01");
+        for invalid in [
+            "2021-02-29",
+            "1900-02-29",
+            "2021-04-31",
+            "2021-13-01",
+            "2021-00-10",
+            "2021-01-00",
+            "2021-1-05",
+            "21-01-05",
+            "2021-01-05-01",
+            "2021-01",
+            "2021-+1-05",
+            "+2021-01-05",
+            "--2021-01-05",
+            "2147483648-01-01",
+            "-2147483649-01-01",
+        ] {
+            assert!(invalid.parse::<Date>().is_err(), "{}", invalid);
+        }
+        assert_eq!(
+            "2021-02-30".parse::<Date>().unwrap_err().to_string(),
+            "invalid date '2021-02-30'"
+        );
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(date("2020-12-31").add_days(1), Some(date("2021-01-01")));
+        assert_eq!(date("2020-03-01").add_days(-1), Some(date("2020-02-29")));
+        assert_eq!(date("2000-01-01").add_days(366), Some(date("2001-01-01")));
+        assert_eq!(date("1969-12-31").add_days(1), Some(date("1970-01-01")));
+        let last = Date::from_ymd(i32::MAX, 12, 31).unwrap();
+        assert_eq!(last.add_days(-1), Date::from_ymd(i32::MAX, 12, 30));
+        assert_eq!(last.add_days(1), None);
+        assert_eq!(Date::from_ymd(i32::MIN, 1, 1).unwrap().add_days(-1), None);
+        assert_eq!(date("2021-01-01").add_days(i64::MAX), None);
+        assert_eq!(date("2021-01-01").add_days(i64::MIN), None);
+        assert_eq!(date("2021-01-01").days_since(date("2020-01-01")), 366);
+        assert_eq!(date("2020-01-01").days_since(date("2021-01-01")), -366);
+
+        assert_eq!(date("2021-01-31").add_months(1), Some(date("2021-02-28")));
+        assert_eq!(date("2020-01-31").add_months(1), Some(date("2020-02-29")));
+        assert_eq!(date("2021-03-15").add_months(-3), Some(date("2020-12-15")));
+        assert_eq!(date("2021-03-15").add_months(24), Some(date("2023-03-15")));
+        let last = Date::from_ymd(i32::MAX, 12, 31).unwrap();
+        assert_eq!(last.add_months(-1), Date::from_ymd(i32::MAX, 11, 30));
+        assert_eq!(last.add_months(1), None);
+        let first = Date::from_ymd(i32::MIN, 1, 1).unwrap();
+        assert_eq!(first.add_months(-1), None);
+        assert_eq!(
+            date("2021-03-15").add_months(i32::MIN),
+            Date::from_ymd(-178_954_950, 7, 15)
+        );
+
+        let mut day = date("1899-12-25");
+        for _ in 0..100_000 {
+            let next = day.add_days(1).unwrap();
+            assert_eq!(next.days_since(day), 1);
+            assert!(next > day);
+            day = next;
+        }
+    }
+
+    #[test]
+    fn calendar() {
+        assert_eq!(date("2021-01-05").weekday(), Weekday::Tuesday);
+        assert_eq!(date("1970-01-01").weekday(), Weekday::Thursday);
+        assert_eq!(date("1969-12-28").weekday(), Weekday::Sunday);
+        assert!(date("2000-06-01").is_leap_year());
+        assert!(!date("2100-06-01").is_leap_year());
+
+        let d = date("2021-08-17");
+        assert_eq!(d.quarter(), 3);
+        assert_eq!(d.start_of_month(), date("2021-08-01"));
+        assert_eq!(d.end_of_month(), date("2021-08-31"));
+        assert_eq!(d.start_of_quarter(), date("2021-07-01"));
+        assert_eq!(d.end_of_quarter(), date("2021-09-30"));
+        assert_eq!(d.start_of_year(), date("2021-01-01"));
+        assert_eq!(d.end_of_year(), date("2021-12-31"));
+        assert_eq!(date("2024-02-10").end_of_quarter(), date("2024-03-31"));
+        assert_eq!(date("2024-02-10").end_of_month(), date("2024-02-29"));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_date_from_chrono() {
+        let naive = chrono::NaiveDate::from_ymd_opt(2020, 5, 5).unwrap();
+        assert_eq!(Date::from(naive), date("2020-05-05"));
+        assert_eq!(NaiveDate::try_from(date("2020-05-05")), Ok(naive));
+        let far = date("300000-01-01");
+        assert_eq!(
+            NaiveDate::try_from(far),
+            Err(DateOutOfRangeError { date: far })
+        );
+    }
 }
@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::collections::HashSet;
 use std::convert::TryFrom;
 
@@ -7,14 +8,18 @@ use typed_builder::TypedBuilder;
 use super::account::Account;
 use super::amount::Amount;
 use super::flags::Flag;
-use super::metadata::{Link, Meta, Tag};
+use super::location::Location;
+use super::metadata::{Link, Meta, MetaValue, Tag};
 use super::posting::Posting;
 use super::{Currency, Date};
 
 /// The set of booking methods for positions on accounts.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "SCREAMING_SNAKE_CASE"))]
 pub enum Booking {
     /// Reject ambiguous matches with an error.
+    #[default]
     Strict,
 
     /// Strict booking method, but disambiguate further with sizes. Reject ambiguous matches with
@@ -52,6 +57,8 @@ impl TryFrom<&str> for Booking {
 
 /// Enum of all directive types.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
 pub enum Directive {
     Open(Open),
     Close(Close),
@@ -71,6 +78,95 @@ pub enum Directive {
     Unsupported,
 }
 
+impl Directive {
+    /// The date of the directive, if it is a dated directive.
+    pub fn date(&self) -> Option<&Date> {
+        use Directive::*;
+        match self {
+            Open(d) => Some(&d.date),
+            Close(d) => Some(&d.date),
+            Balance(d) => Some(&d.date),
+            Commodity(d) => Some(&d.date),
+            Custom(d) => Some(&d.date),
+            Document(d) => Some(&d.date),
+            Event(d) => Some(&d.date),
+            Note(d) => Some(&d.date),
+            Pad(d) => Some(&d.date),
+            Price(d) => Some(&d.date),
+            Query(d) => Some(&d.date),
+            Transaction(d) => Some(&d.date),
+            Option(_) | Include(_) | Plugin(_) | Unsupported => None,
+        }
+    }
+
+    /// The location of the directive in its source file, if it was parsed from one.
+    pub fn location(&self) -> Option<&Location> {
+        use Directive::*;
+        match self {
+            Open(d) => d.location.as_ref(),
+            Close(d) => d.location.as_ref(),
+            Balance(d) => d.location.as_ref(),
+            Commodity(d) => d.location.as_ref(),
+            Custom(d) => d.location.as_ref(),
+            Document(d) => d.location.as_ref(),
+            Event(d) => d.location.as_ref(),
+            Include(d) => d.location.as_ref(),
+            Note(d) => d.location.as_ref(),
+            Option(d) => d.location.as_ref(),
+            Pad(d) => d.location.as_ref(),
+            Plugin(d) => d.location.as_ref(),
+            Price(d) => d.location.as_ref(),
+            Query(d) => d.location.as_ref(),
+            Transaction(d) => d.location.as_ref(),
+            Unsupported => None,
+        }
+    }
+
+    /// The accounts the directive refers to: the accounts of the postings of a transaction, both
+    /// accounts of a `pad` directive, and the account of an `open`, `close`, `balance`, `note` or
+    /// `document` directive.
+    pub fn accounts(&self) -> Vec<&Account> {
+        use Directive::*;
+        match self {
+            Open(d) => vec![&d.account],
+            Close(d) => vec![&d.account],
+            Balance(d) => vec![&d.account],
+            Document(d) => vec![&d.account],
+            Note(d) => vec![&d.account],
+            Pad(d) => vec![&d.pad_to_account, &d.pad_from_account],
+            Transaction(d) => d.postings.iter().map(|p| &p.account).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Relative order of directives sharing the same date: accounts are opened before anything
+    /// else happens on that date, balances are asserted at the start of the day, and documents
+    /// and closings come last.
+    fn type_order(&self) -> i8 {
+        match self {
+            Directive::Open(_) => -2,
+            Directive::Balance(_) => -1,
+            Directive::Document(_) => 1,
+            Directive::Close(_) => 2,
+            _ => 0,
+        }
+    }
+}
+
+/// Sort directives in the order Beancount processes them: undated directives first, then by date,
+/// and directives on the same date by type. The sort is stable, so the file order is kept
+/// otherwise.
+pub fn sort_directives(directives: &mut [Directive]) {
+    directives.sort_by(compare_directives);
+}
+
+/// The ordering used by [sort_directives](fn.sort_directives.html).
+pub fn compare_directives(a: &Directive, b: &Directive) -> Ordering {
+    a.date()
+        .cmp(&b.date())
+        .then_with(|| a.type_order().cmp(&b.type_order()))
+}
+
 /// Represents a `balance` directive, which is a way for you to input your statement balance into
 /// the flow of transactions.
 ///
@@ -95,6 +191,7 @@ pub enum Directive {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.l0pvgeniwvq8>
 #[derive(Clone, Debug, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Balance {
     /// Date of the balance.
     pub date: Date,
@@ -116,6 +213,10 @@ pub struct Balance {
     /// Source string from the parsed input
     #[builder(default)]
     pub source: Option<String>,
+
+    /// Location of the directive in its source file.
+    #[builder(default)]
+    pub location: Option<Location>,
 }
 
 /// Represents a Beancount `option`, which are configuration points global to the file.
@@ -135,6 +236,7 @@ pub struct Balance {
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.e2iyrfrmstl>
 
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BcOption {
     /// Name of the option.
     pub name: String,
@@ -145,6 +247,10 @@ pub struct BcOption {
     /// Source string from the parsed input
     #[builder(default)]
     pub source: Option<String>,
+
+    /// Location of the directive in its source file.
+    #[builder(default)]
+    pub location: Option<Location>,
 }
 
 impl BcOption {
@@ -186,6 +292,7 @@ impl BcOption {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.wf248e8stnac>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Close {
     /// Date the account was closed.
     pub date: Date,
@@ -200,6 +307,10 @@ pub struct Close {
     /// Source string from the parsed input
     #[builder(default)]
     pub source: Option<String>,
+
+    /// Location of the directive in its source file.
+    #[builder(default)]
+    pub location: Option<Location>,
 }
 
 /// Represents a `commodity` directive.  This directive allows you to declare commodities,
@@ -228,6 +339,7 @@ pub struct Close {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.a3si01ejc035>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Commodity {
     /// Date the commodity was declared.
     pub date: Date,
@@ -242,6 +354,10 @@ pub struct Commodity {
     /// Source string from the parsed input
     #[builder(default)]
     pub source: Option<String>,
+
+    /// Location of the directive in its source file.
+    #[builder(default)]
+    pub location: Option<Location>,
 }
 
 /// Represents a `custom` directive, which is a generic directive provided to allow clients to
@@ -270,6 +386,7 @@ pub struct Commodity {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.20klpeqb6ajy>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Custom {
     /// Date associated with the custom directive.
     pub date: Date,
@@ -277,8 +394,9 @@ pub struct Custom {
     /// Custom directive name.
     pub name: String,
 
-    /// Arbitrary number of custom directive arguments.
-    pub args: Vec<String>,
+    /// Arbitrary number of custom directive arguments: strings, dates, booleans, amounts, numbers
+    /// and accounts.
+    pub args: Vec<MetaValue>,
 
     /// Metadata attached to the custom directive.
     #[builder(default)]
@@ -287,6 +405,10 @@ pub struct Custom {
     /// Source string from the parsed input
     #[builder(default)]
     pub source: Option<String>,
+
+    /// Location of the directive in its source file.
+    #[builder(default)]
+    pub location: Option<Location>,
 }
 
 /// Represents a `document` directive.  A `document` directive can be used to attach an external
@@ -306,6 +428,7 @@ pub struct Custom {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.w1ins9jk4mq3>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Document {
     /// Date the document was linked.
     pub date: Date,
@@ -318,10 +441,18 @@ pub struct Document {
 
     /// Tags associated with the document.
     #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serialization::sorted")
+    )]
     pub tags: HashSet<Tag>,
 
     /// Links associated with the document.
     #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serialization::sorted")
+    )]
     pub links: HashSet<Link>,
 
     /// Metadata attached to the document directive.
@@ -331,6 +462,10 @@ pub struct Document {
     /// Source string from the parsed input
     #[builder(default)]
     pub source: Option<String>,
+
+    /// Location of the directive in its source file.
+    #[builder(default)]
+    pub location: Option<Location>,
 }
 
 /// Represents an `event` directive.  `event` directives are used to track the value of some
@@ -350,6 +485,7 @@ pub struct Document {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.tm5fxddlik5x>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Event {
     /// Date the event occurred.
     pub date: Date,
@@ -367,6 +503,10 @@ pub struct Event {
     /// Source string from the parsed input
     #[builder(default)]
     pub source: Option<String>,
+
+    /// Location of the directive in its source file.
+    #[builder(default)]
+    pub location: Option<Location>,
 }
 
 /// Represents an `include` directive.  The `include` directive, as it sounds, includes another
@@ -386,6 +526,7 @@ pub struct Event {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.86lelow4097r>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Include {
     /// Fully qualified filename, including any necessary path segments.
     pub filename: String,
@@ -393,6 +534,10 @@ pub struct Include {
     /// Source string from the parsed input
     #[builder(default)]
     pub source: Option<String>,
+
+    /// Location of the directive in its source file.
+    #[builder(default)]
+    pub location: Option<Location>,
 }
 
 /// Represents a `note` directive.  A `note` directive is simply used to attach a dated comment to
@@ -412,6 +557,7 @@ pub struct Include {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.c4cyaa6o6rqm>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Note {
     /// Date of the note.
     pub date: Date,
@@ -429,6 +575,10 @@ pub struct Note {
     /// Source string from the parsed input
     #[builder(default)]
     pub source: Option<String>,
+
+    /// Location of the directive in its source file.
+    #[builder(default)]
+    pub location: Option<Location>,
 }
 
 /// Represents a `open` directive.  This directive signifies the opening of an account.
@@ -446,6 +596,7 @@ pub struct Note {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.omdgvaikswd0>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Open {
     /// Date the account was opened.
     pub date: Date,
@@ -470,6 +621,10 @@ pub struct Open {
     /// Source string from the parsed input
     #[builder(default)]
     pub source: Option<String>,
+
+    /// Location of the directive in its source file.
+    #[builder(default)]
+    pub location: Option<Location>,
 }
 
 /// Represents a `pad` directive.  A `pad` directive automatically inserts a transaction that will
@@ -493,6 +648,7 @@ pub struct Open {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.aw8ic3d8k8rq>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pad {
     /// Date of the pad.
     pub date: Date,
@@ -510,14 +666,18 @@ pub struct Pad {
     /// Source string from the parsed input
     #[builder(default)]
     pub source: Option<String>,
+
+    /// Location of the directive in its source file.
+    #[builder(default)]
+    pub location: Option<Location>,
 }
 
 /// Represents a `plugin` directive.
 ///
-/// In the Python version of Beancount, this would allow you to
-/// specify an actual arbitrary Python program to programmatically transform directives as they are
-/// parsed.  That is clearly not (easily) possible in this Rust implementation, but we will still
-/// give you back any 'plugin' directives we found in the form of this struct.
+/// In the Python version of Beancount, this allows you to specify an actual arbitrary Python
+/// program to programmatically transform directives as they are parsed. In this Rust
+/// implementation, the module name instead refers to a [Plugin](../plugins/trait.Plugin.html)
+/// registered in a [PluginRegistry](../plugins/struct.PluginRegistry.html), which runs it.
 ///
 /// The general format of the `plugin` directive is:
 ///
@@ -533,6 +693,7 @@ pub struct Pad {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.lxgs9ewvbt8k>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Plugin {
     /// Full module name of the plugin.
     pub module: String,
@@ -544,6 +705,10 @@ pub struct Plugin {
     /// Source string from the parsed input
     #[builder(default)]
     pub source: Option<String>,
+
+    /// Location of the directive in its source file.
+    #[builder(default)]
+    pub location: Option<Location>,
 }
 
 /// Represents a `price` directive, which establishes the rate of exchange between one commodity and
@@ -574,6 +739,7 @@ pub struct Plugin {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.f78ym1dxtemh>
 #[derive(Clone, Debug, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Price {
     /// Date of the price specification.
     pub date: Date,
@@ -591,6 +757,10 @@ pub struct Price {
     /// Source string from the parsed input
     #[builder(default)]
     pub source: Option<String>,
+
+    /// Location of the directive in its source file.
+    #[builder(default)]
+    pub location: Option<Location>,
 }
 
 /// Represents a `query` directive.  `query` directives allow you to insert a query in the usual
@@ -614,6 +784,7 @@ pub struct Price {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.nw8fgvy4ub1w>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Query {
     /// Date on which the query should be run.
     pub date: Date,
@@ -631,6 +802,10 @@ pub struct Query {
     /// Source string from the parsed input
     #[builder(default)]
     pub source: Option<String>,
+
+    /// Location of the directive in its source file.
+    #[builder(default)]
+    pub location: Option<Location>,
 }
 
 /// Represents a `txn` (or `*` or `!`) directive.
@@ -679,6 +854,7 @@ pub struct Query {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.up4dj751q84w>
 #[derive(Clone, Debug, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transaction {
     pub date: Date,
 
@@ -698,10 +874,18 @@ pub struct Transaction {
 
     /// Tags associated with the transaction.
     #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serialization::sorted")
+    )]
     pub tags: HashSet<Tag>,
 
     /// Links associated with the transactions.
     #[builder(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serialization::sorted")
+    )]
     pub links: HashSet<Link>,
 
     /// Postings belonging to this transaction.
@@ -714,4 +898,8 @@ pub struct Transaction {
 
     #[builder(default)]
     pub source: Option<String>,
+
+    /// Location of the directive in its source file.
+    #[builder(default)]
+    pub location: Option<Location>,
 }
@@ -8,6 +8,7 @@ use std::fmt;
 /// assert_eq!(Flag::default(), Flag::Okay);
 /// assert_eq!(Flag::from("*"), Flag::Okay);
 /// assert_eq!(Flag::from("!"), Flag::Warning);
+/// assert_eq!(Flag::from("P"), Flag::Padding);
 /// assert_eq!(Flag::from(":)"), Flag::Other(":)".into()));
 /// ```
 // TODO: Make sure that the variant Other("*") can't be created, since Other("*") != Okay
@@ -17,6 +18,8 @@ pub enum Flag {
     #[default]
     Okay,
     Warning,
+    /// `P`: a transaction inserted to satisfy a `pad` directive.
+    Padding,
     Other(String),
 }
 
@@ -26,6 +29,7 @@ impl From<&str> for Flag {
         match s {
             "*" | "txn" => Flag::Okay,
             "!" => Flag::Warning,
+            "P" => Flag::Padding,
             _ => Flag::Other(s.to_owned()),
         }
     }
@@ -36,6 +40,7 @@ impl fmt::Display for Flag {
         match self {
             Flag::Okay => write!(f, "*"),
             Flag::Warning => write!(f, "!"),
+            Flag::Padding => write!(f, "P"),
             Flag::Other(s) => write!(f, "{}", s),
         }
     }
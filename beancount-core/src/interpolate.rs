@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+use super::amount::{Amount, IncompleteAmount};
+use super::directives::{Directive, Transaction};
+use super::inventory::Inventory;
+use super::options::Options;
+use super::posting::{Posting, PriceSpec};
+use super::Currency;
+
+/// An error encountered while completing a transaction.
+#[derive(Clone, Debug, PartialEq, Error)]
+#[error("{kind}")]
+pub struct InterpolationError {
+    /// The type of error.
+    pub kind: InterpolationErrorKind,
+
+    /// The transaction that could not be completed.
+    pub transaction: Transaction,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+pub enum InterpolationErrorKind {
+    /// A currency of a posting could not be inferred from the other postings.
+    #[error("could not infer the currency of posting {posting}")]
+    UnresolvedCurrency { posting: usize },
+
+    /// More than one number is missing, so the transaction is under-determined.
+    #[error("too many missing numbers: {count}")]
+    TooManyMissingNumbers { count: usize },
+
+    /// More than one posting has no amount at all.
+    #[error("only one posting may have its amount elided")]
+    MultipleAutoPostings,
+
+    /// The missing number of posting cannot be solved for, e.g. because it would require a
+    /// division by zero.
+    #[error("cannot interpolate the missing number of posting {posting}")]
+    Unsolvable { posting: usize },
+}
+
+/// How tolerances are inferred, as configured by the `inferred_tolerance_default` and
+/// `inferred_tolerance_multiplier` options.
+///
+/// ```text
+/// option "inferred_tolerance_default" "USD:0.003"
+/// option "inferred_tolerance_default" "*:0.001"
+/// option "inferred_tolerance_multiplier" "0.6"
+/// ```
+///
+/// <https://beancount.github.io/docs/precision_tolerances.html>
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ToleranceOptions {
+    /// Tolerance used for currencies whose tolerance cannot be inferred from the numbers in a
+    /// transaction, e.g. because they are integers. The key `*` applies to all currencies not
+    /// listed explicitly.
+    pub default: HashMap<Currency, Decimal>,
+
+    /// Multiplier applied to the last significant digit of a number to get its tolerance.
+    pub multiplier: Decimal,
+}
+
+impl Default for ToleranceOptions {
+    fn default() -> Self {
+        ToleranceOptions {
+            default: HashMap::new(),
+            multiplier: Decimal::new(5, 1),
+        }
+    }
+}
+
+impl ToleranceOptions {
+    /// Collect the tolerance options from the `option` directives of a ledger. Values which
+    /// cannot be parsed are ignored, see [Options](../options/struct.Options.html).
+    pub fn from_directives(directives: &[Directive]) -> Self {
+        Options::from_directives(directives).0.tolerance_options()
+    }
+
+    /// The default tolerance of a currency: the tolerance configured for that currency, the
+    /// wildcard tolerance, or zero.
+    pub fn default_for(&self, currency: &str) -> Decimal {
+        self.default
+            .get(currency)
+            .or_else(|| self.default.get("*"))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Infer the tolerance of each currency from the precision of the units in a list of postings.
+///
+/// A number with `n` decimal places has a tolerance of the multiplier times `10^-n`, and the
+/// tolerance of a currency is the largest of those of its numbers. Integer numbers do not
+/// contribute, so currencies only used with integers are absent from the result and should use
+/// [default_for](struct.ToleranceOptions.html#method.default_for).
+pub fn infer_tolerances(
+    postings: &[Posting],
+    options: &ToleranceOptions,
+) -> HashMap<Currency, Decimal> {
+    let mut tolerances = HashMap::new();
+    for posting in postings {
+        if let (Some(num), Some(currency)) = (&posting.units.num, &posting.units.currency) {
+            if num.scale() == 0 {
+                continue;
+            }
+            let tolerance = options.multiplier * Decimal::new(1, num.scale());
+            let entry = tolerances.entry(currency.clone()).or_insert(tolerance);
+            *entry = tolerance.max(*entry);
+        }
+    }
+    tolerances
+}
+
+/// Sum the weights of a list of postings. Returns `None` if the weight of any posting is unknown.
+pub fn compute_residual(postings: &[Posting]) -> Option<Inventory> {
+    let mut residual = Inventory::new();
+    for posting in postings {
+        residual.add_amount(posting.weight()?, None);
+    }
+    Some(residual)
+}
+
+/// Complete all transactions of a stream of directives with
+/// [complete_transaction](fn.complete_transaction.html). Transactions that cannot be completed are
+/// dropped from the result and reported as errors.
+pub fn interpolate(directives: Vec<Directive>) -> (Vec<Directive>, Vec<InterpolationError>) {
+    let mut completed = Vec::with_capacity(directives.len());
+    let mut errors = Vec::new();
+    for directive in directives {
+        match directive {
+            Directive::Transaction(txn) => match complete_transaction(&txn) {
+                Ok(txn) => completed.push(Directive::Transaction(txn)),
+                Err(kind) => errors.push(InterpolationError {
+                    kind,
+                    transaction: txn,
+                }),
+            },
+            _ => completed.push(directive),
+        }
+    }
+    (completed, errors)
+}
+
+/// Fill in the parts of a transaction that were elided in the input.
+///
+/// Missing currencies of costs and prices are taken from each other, and any currency still
+/// missing is inferred if all the other postings of the transaction are weighed in a single
+/// currency. Then a single missing number (units, cost or price) is computed so that the
+/// transaction balances. A posting without any amount absorbs the residual of the whole
+/// transaction, and is split into one posting per currency if that residual holds several.
+/// Interpolated units are rounded to the precision used for the same currency elsewhere in the
+/// transaction.
+///
+/// Costs have to be booked for reductions to be weighed properly, see
+/// [book](../booking/fn.book.html), which completes the transactions it books.
+///
+/// <https://beancount.github.io/docs/beancount_language_syntax.html#balancing-rule-the-weight-of-postings>
+pub fn complete_transaction(txn: &Transaction) -> Result<Transaction, InterpolationErrorKind> {
+    let mut postings = txn.postings.clone();
+    infer_currencies(&mut postings)?;
+
+    let autos: Vec<usize> = postings
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| is_auto_posting(p))
+        .map(|(i, _)| i)
+        .collect();
+    let missing: Vec<usize> = postings
+        .iter()
+        .enumerate()
+        .flat_map(|(i, p)| std::iter::repeat_n(i, missing_numbers(p)))
+        .collect();
+
+    if autos.len() > 1 {
+        return Err(InterpolationErrorKind::MultipleAutoPostings);
+    }
+    if missing.len() + autos.len() > 1 {
+        return Err(InterpolationErrorKind::TooManyMissingNumbers {
+            count: missing.len() + autos.len(),
+        });
+    }
+
+    let precision = units_precision(&postings);
+    let incomplete = autos.first().or(missing.first()).copied();
+    if let Some(index) = incomplete {
+        let mut residual = Inventory::new();
+        for (i, posting) in postings.iter().enumerate() {
+            if i != index {
+                let weight = posting
+                    .weight()
+                    .ok_or(InterpolationErrorKind::Unsolvable { posting: i })?;
+                residual.add_amount(weight, None);
+            }
+        }
+
+        if autos.is_empty() {
+            solve_posting(&mut postings[index], &residual, &precision)
+                .ok_or(InterpolationErrorKind::Unsolvable { posting: index })?;
+        } else {
+            let auto = postings.remove(index);
+            let filled = residual
+                .iter()
+                .filter_map(|pos| {
+                    let num = quantize(-pos.units.num, &pos.units.currency, &precision);
+                    (!num.is_zero()).then(|| Posting {
+                        units: Amount {
+                            num,
+                            currency: pos.units.currency.clone(),
+                        }
+                        .into(),
+                        ..auto.clone()
+                    })
+                })
+                .collect::<Vec<_>>();
+            postings.splice(index..index, filled);
+        }
+    }
+
+    Ok(Transaction {
+        postings,
+        ..txn.clone()
+    })
+}
+
+/// A posting without any amount, cost or price, whose units are entirely interpolated.
+fn is_auto_posting(posting: &Posting) -> bool {
+    posting.units.num.is_none()
+        && posting.units.currency.is_none()
+        && posting.cost.is_none()
+        && posting.price.is_none()
+}
+
+/// The number of numbers missing from a posting which is not an auto posting.
+fn missing_numbers(posting: &Posting) -> usize {
+    if is_auto_posting(posting) {
+        return 0;
+    }
+    let units = usize::from(posting.units.num.is_none());
+    let cost = usize::from(
+        posting
+            .cost
+            .as_ref()
+            .is_some_and(|c| c.number_per.is_none() && c.number_total.is_none()),
+    );
+    let price = usize::from(
+        posting
+            .price
+            .as_ref()
+            .is_some_and(|p| p.amount().num.is_none()),
+    );
+    units + cost + price
+}
+
+/// The currency the weight of a posting is expressed in, if known.
+fn weight_currency(posting: &Posting) -> Option<&Currency> {
+    match (&posting.cost, &posting.price) {
+        (Some(cost), _) => cost.currency.as_ref(),
+        (None, Some(price)) => price.amount().currency.as_ref(),
+        (None, None) => posting.units.currency.as_ref(),
+    }
+}
+
+fn infer_currencies(postings: &mut [Posting]) -> Result<(), InterpolationErrorKind> {
+    // The cost and price of a posting are (nearly always) in the same currency.
+    for posting in postings.iter_mut() {
+        if let (Some(cost), Some(price)) = (&mut posting.cost, &mut posting.price) {
+            let price = price.amount_mut();
+            match (&cost.currency, &price.currency) {
+                (None, Some(currency)) => cost.currency = Some(currency.clone()),
+                (Some(currency), None) => price.currency = Some(currency.clone()),
+                _ => {}
+            }
+        }
+    }
+
+    let mut currencies: Vec<&Currency> = postings.iter().filter_map(weight_currency).collect();
+    currencies.sort();
+    currencies.dedup();
+    let inferred = match currencies.as_slice() {
+        [currency] => Some((*currency).clone()),
+        _ => None,
+    };
+
+    for (index, posting) in postings.iter_mut().enumerate() {
+        if is_auto_posting(posting) {
+            continue;
+        }
+        let unresolved = InterpolationErrorKind::UnresolvedCurrency { posting: index };
+        if let Some(cost) = &mut posting.cost {
+            if cost.currency.is_none() {
+                cost.currency = Some(inferred.clone().ok_or(unresolved.clone())?);
+            }
+        }
+        if let Some(price) = &mut posting.price {
+            let price = price.amount_mut();
+            if price.currency.is_none() {
+                price.currency = Some(inferred.clone().ok_or(unresolved.clone())?);
+            }
+        }
+        if posting.units.currency.is_none() {
+            if posting.cost.is_some() || posting.price.is_some() {
+                return Err(unresolved);
+            }
+            posting.units.currency = Some(inferred.clone().ok_or(unresolved)?);
+        }
+    }
+    Ok(())
+}
+
+/// Solve for the single missing number of a posting, so that its weight cancels the residual of
+/// the other postings.
+fn solve_posting(
+    posting: &mut Posting,
+    residual: &Inventory,
+    precision: &HashMap<Currency, u32>,
+) -> Option<()> {
+    let currency = weight_currency(posting)?.clone();
+    let weight = -residual.units_of(&currency);
+
+    match (posting.units.num, &mut posting.cost, &mut posting.price) {
+        (None, cost, price) => {
+            let per_unit = match (cost, price) {
+                (Some(cost), _) if cost.number_total.is_none() => cost.number_per?,
+                (Some(_), _) => return None,
+                (None, Some(PriceSpec::PerUnit(price))) => price.num?,
+                (None, Some(PriceSpec::Total(_))) => return None,
+                (None, None) => Decimal::ONE,
+            };
+            let units = weight.checked_div(per_unit)?;
+            let units_currency = posting.units.currency.as_ref()?;
+            posting.units.num = Some(quantize(units, units_currency, precision));
+        }
+        (Some(units), Some(cost), _)
+            if cost.number_per.is_none() && cost.number_total.is_none() =>
+        {
+            cost.number_per = Some(weight.checked_div(units)?);
+        }
+        (Some(units), None, Some(price)) => {
+            let num = match price {
+                PriceSpec::PerUnit(_) => weight.checked_div(units)?,
+                PriceSpec::Total(_) => weight.abs(),
+            };
+            *price.amount_mut() = IncompleteAmount {
+                num: Some(num),
+                currency: Some(currency),
+            };
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+/// The largest number of decimal places used for the units of each currency in the postings.
+fn units_precision(postings: &[Posting]) -> HashMap<Currency, u32> {
+    let mut precision = HashMap::new();
+    for posting in postings {
+        if let (Some(num), Some(currency)) = (&posting.units.num, &posting.units.currency) {
+            let scale = precision.entry(currency.clone()).or_insert(0);
+            *scale = num.scale().max(*scale);
+        }
+    }
+    precision
+}
+
+fn quantize(num: Decimal, currency: &str, precision: &HashMap<Currency, u32>) -> Decimal {
+    match precision.get(currency) {
+        Some(scale) if num.scale() > *scale => num.round_dp(*scale),
+        _ => num,
+    }
+}
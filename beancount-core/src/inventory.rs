@@ -0,0 +1,420 @@
+use std::collections::HashSet;
+use std::ops::Neg;
+
+use rust_decimal::Decimal;
+
+use super::amount::Amount;
+use super::position::{Cost, Position};
+use super::Currency;
+
+/// The outcome of adding a position to an [Inventory](struct.Inventory.html).
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum MatchResult {
+    /// A new lot was created.
+    Created,
+
+    /// An existing lot was reduced (or closed out entirely).
+    Reduced,
+
+    /// An existing lot was augmented.
+    Augmented,
+
+    /// The position was zero and nothing was changed.
+    Ignored,
+}
+
+/// A collection of positions, such as the holdings of an account.
+///
+/// Positions are keyed by their commodity and their cost, so adding units of a commodity held at a
+/// given cost merges them into the lot with that same cost, while positions held at different
+/// costs are kept apart. Positions whose number of units reaches zero are removed.
+///
+/// Lots are kept in the order in which they were created.
+///
+/// # Example
+/// ```rust
+/// use beancount_core::{Amount, Inventory};
+///
+/// let mut inventory = Inventory::new();
+/// inventory.add_amount(Amount::builder().num(10.into()).currency("USD".into()).build(), None);
+/// inventory.add_amount(Amount::builder().num((-4).into()).currency("USD".into()).build(), None);
+/// assert_eq!(inventory.units_of("USD"), 6.into());
+/// ```
+#[derive(Clone, Debug, Eq, Default)]
+pub struct Inventory {
+    positions: Vec<Position>,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if the inventory holds no positions.
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// The number of lots held in the inventory.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Iterates over the lots held in the inventory, in creation order.
+    pub fn iter(&self) -> std::slice::Iter<'_, Position> {
+        self.positions.iter()
+    }
+
+    /// Look up the lot of the given commodity held at the given cost.
+    pub fn get(&self, currency: &str, cost: Option<&Cost>) -> Option<&Position> {
+        self.positions
+            .iter()
+            .find(|pos| pos.units.currency == currency && pos.cost.as_ref() == cost)
+    }
+
+    /// The set of commodities held in the inventory.
+    pub fn currencies(&self) -> HashSet<&Currency> {
+        self.positions
+            .iter()
+            .map(|pos| &pos.units.currency)
+            .collect()
+    }
+
+    /// The total number of units of the given commodity, across all lots.
+    pub fn units_of(&self, currency: &str) -> Decimal {
+        self.positions
+            .iter()
+            .filter(|pos| pos.units.currency == currency)
+            .map(|pos| pos.units.num)
+            .sum()
+    }
+
+    /// Add a number of units, optionally held at cost, to the inventory.
+    pub fn add_amount(&mut self, units: Amount, cost: Option<Cost>) -> MatchResult {
+        if units.num.is_zero() {
+            return MatchResult::Ignored;
+        }
+        let existing = self
+            .positions
+            .iter()
+            .position(|pos| pos.units.currency == units.currency && pos.cost == cost);
+        match existing {
+            Some(index) => {
+                let pos = &mut self.positions[index];
+                let result = if pos.units.num.is_sign_negative() == units.num.is_sign_negative() {
+                    MatchResult::Augmented
+                } else {
+                    MatchResult::Reduced
+                };
+                pos.units.num += units.num;
+                if pos.units.num.is_zero() {
+                    self.positions.remove(index);
+                }
+                result
+            }
+            None => {
+                self.positions.push(Position { units, cost });
+                MatchResult::Created
+            }
+        }
+    }
+
+    /// Add a position to the inventory, merging it with the lot of the same commodity and cost if
+    /// there is one.
+    pub fn add_position(&mut self, position: Position) -> MatchResult {
+        self.add_amount(position.units, position.cost)
+    }
+
+    /// Add all the positions of another inventory to this one.
+    pub fn add_inventory(&mut self, other: &Inventory) {
+        for pos in other.iter() {
+            self.add_position(pos.clone());
+        }
+    }
+
+    /// Returns `true` if reducing the inventory by the given amount would reduce at least one
+    /// existing lot, i.e. if some lot of the commodity has the opposite sign.
+    pub fn is_reduced_by(&self, amount: &Amount) -> bool {
+        self.positions.iter().any(|pos| {
+            pos.units.currency == amount.currency
+                && !pos.units.num.is_zero()
+                && pos.units.num.is_sign_negative() != amount.num.is_sign_negative()
+        })
+    }
+
+    /// Returns `true` if the inventory holds both long and short lots of the same commodity.
+    pub fn is_mixed(&self) -> bool {
+        let mut signs: Vec<(&Currency, bool)> = Vec::new();
+        for pos in &self.positions {
+            let negative = pos.units.num.is_sign_negative();
+            match signs.iter().find(|(cur, _)| *cur == &pos.units.currency) {
+                Some((_, sign)) if *sign != negative => return true,
+                Some(_) => {}
+                None => signs.push((&pos.units.currency, negative)),
+            }
+        }
+        false
+    }
+
+    /// Reduce the inventory to its units, dropping all cost information.
+    pub fn units(&self) -> Inventory {
+        self.reduce(|pos| pos.units.clone())
+    }
+
+    /// Reduce the inventory to its cost basis. Positions not held at cost are kept as units.
+    pub fn at_cost(&self) -> Inventory {
+        self.reduce(Position::weight)
+    }
+
+    /// Reduce the inventory to its market value in `currency`, using `price` to look up the price
+    /// of one unit of a commodity. Positions which already are in `currency`, or for which no
+    /// price is available, are kept as units.
+    pub fn market_value<F>(&self, currency: &str, mut price: F) -> Inventory
+    where
+        F: FnMut(&Currency) -> Option<Decimal>,
+    {
+        self.reduce(|pos| {
+            if pos.units.currency == currency {
+                return pos.units.clone();
+            }
+            match price(&pos.units.currency) {
+                Some(rate) => Amount {
+                    num: pos.units.num * rate,
+                    currency: currency.to_string(),
+                },
+                None => pos.units.clone(),
+            }
+        })
+    }
+
+    /// Reduce the inventory by mapping every position to an amount and summing those.
+    pub fn reduce<F>(&self, mut f: F) -> Inventory
+    where
+        F: FnMut(&Position) -> Amount,
+    {
+        let mut result = Inventory::new();
+        for pos in &self.positions {
+            result.add_amount(f(pos), None);
+        }
+        result
+    }
+
+    /// Merge all the lots of each commodity held at cost into a single lot at their average cost.
+    ///
+    /// The merged lot takes the date of the earliest lot and no label. Lots which are not held at
+    /// cost are left untouched.
+    pub fn average(&self) -> Inventory {
+        let mut result = Inventory::new();
+        let mut merged: Vec<(Currency, Currency, Decimal, Decimal, Cost)> = Vec::new();
+        for pos in &self.positions {
+            let cost = match &pos.cost {
+                Some(cost) => cost,
+                None => {
+                    result.add_position(pos.clone());
+                    continue;
+                }
+            };
+            let entry = merged.iter_mut().find(|(cur, cost_cur, ..)| {
+                *cur == pos.units.currency && *cost_cur == cost.currency
+            });
+            match entry {
+                Some((_, _, units, total, first)) => {
+                    *units += pos.units.num;
+                    *total += pos.units.num * cost.number;
+                    if cost.date < first.date {
+                        first.date = cost.date;
+                    }
+                }
+                None => merged.push((
+                    pos.units.currency.clone(),
+                    cost.currency.clone(),
+                    pos.units.num,
+                    pos.units.num * cost.number,
+                    cost.clone(),
+                )),
+            }
+        }
+        for (currency, cost_currency, units, total, first) in merged {
+            if units.is_zero() {
+                continue;
+            }
+            result.add_amount(
+                Amount {
+                    num: units,
+                    currency,
+                },
+                Some(Cost {
+                    number: total / units,
+                    currency: cost_currency,
+                    date: first.date,
+                    label: None,
+                }),
+            );
+        }
+        result
+    }
+}
+
+impl PartialEq for Inventory {
+    fn eq(&self, other: &Inventory) -> bool {
+        self.positions.len() == other.positions.len()
+            && self
+                .positions
+                .iter()
+                .all(|pos| other.positions.contains(pos))
+    }
+}
+
+impl Neg for Inventory {
+    type Output = Inventory;
+
+    fn neg(self) -> Self::Output {
+        Inventory {
+            positions: self.positions.into_iter().map(Neg::neg).collect(),
+        }
+    }
+}
+
+impl FromIterator<Position> for Inventory {
+    fn from_iter<I: IntoIterator<Item = Position>>(iter: I) -> Self {
+        let mut inventory = Inventory::new();
+        inventory.extend(iter);
+        inventory
+    }
+}
+
+impl Extend<Position> for Inventory {
+    fn extend<I: IntoIterator<Item = Position>>(&mut self, iter: I) {
+        for pos in iter {
+            self.add_position(pos);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Inventory {
+    type Item = &'a Position;
+    type IntoIter = std::slice::Iter<'a, Position>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.positions.iter()
+    }
+}
+
+impl IntoIterator for Inventory {
+    type Item = Position;
+    type IntoIter = std::vec::IntoIter<Position>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.positions.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(num: i64, currency: &str) -> Amount {
+        Amount {
+            num: num.into(),
+            currency: currency.into(),
+        }
+    }
+
+    fn cost(num: i64, currency: &str, date: &str) -> Cost {
+        Cost {
+            number: num.into(),
+            currency: currency.into(),
+            date: date.parse().unwrap(),
+            label: None,
+        }
+    }
+
+    #[test]
+    fn add_amount_merges_by_currency_and_cost() {
+        let mut inv = Inventory::new();
+        assert_eq!(
+            inv.add_amount(amount(10, "USD"), None),
+            MatchResult::Created
+        );
+        assert_eq!(
+            inv.add_amount(amount(5, "USD"), None),
+            MatchResult::Augmented
+        );
+        assert_eq!(
+            inv.add_amount(amount(-3, "USD"), None),
+            MatchResult::Reduced
+        );
+        assert_eq!(inv.add_amount(amount(0, "USD"), None), MatchResult::Ignored);
+        assert_eq!(inv.len(), 1);
+        assert_eq!(inv.units_of("USD"), 12.into());
+
+        let lot = cost(500, "USD", "2020-01-01");
+        inv.add_amount(amount(2, "HOOL"), Some(lot.clone()));
+        inv.add_amount(amount(1, "HOOL"), Some(cost(520, "USD", "2020-02-01")));
+        assert_eq!(inv.len(), 3);
+        assert_eq!(inv.units_of("HOOL"), 3.into());
+
+        assert_eq!(
+            inv.add_amount(amount(-2, "HOOL"), Some(lot.clone())),
+            MatchResult::Reduced
+        );
+        assert_eq!(inv.len(), 2);
+        assert!(inv.get("HOOL", Some(&lot)).is_none());
+    }
+
+    #[test]
+    fn reductions() {
+        let inv: Inventory = vec![
+            Position {
+                units: amount(2, "HOOL"),
+                cost: Some(cost(500, "USD", "2020-01-01")),
+            },
+            Position {
+                units: amount(1, "HOOL"),
+                cost: Some(cost(530, "USD", "2020-02-01")),
+            },
+            Position {
+                units: amount(100, "USD"),
+                cost: None,
+            },
+        ]
+        .into_iter()
+        .collect();
+
+        let units: Inventory = vec![
+            Position::builder().units(amount(3, "HOOL")).build(),
+            Position::builder().units(amount(100, "USD")).build(),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(inv.units(), units);
+
+        assert_eq!(inv.at_cost().units_of("USD"), 1630.into());
+        assert_eq!(inv.at_cost().len(), 1);
+
+        let value = inv.market_value("USD", |cur| (cur == "HOOL").then(|| 600.into()));
+        assert_eq!(value.units_of("USD"), 1900.into());
+        let value = inv.market_value("USD", |_| None);
+        assert_eq!(value.units_of("HOOL"), 3.into());
+
+        let avg = inv.average();
+        assert_eq!(avg.len(), 2);
+        assert_eq!(
+            avg.get("HOOL", Some(&cost(510, "USD", "2020-01-01")))
+                .map(|pos| pos.units.num),
+            Some(3.into())
+        );
+    }
+
+    #[test]
+    fn mixed_inventories() {
+        let mut inv = Inventory::new();
+        inv.add_amount(amount(2, "HOOL"), Some(cost(500, "USD", "2020-01-01")));
+        assert!(!inv.is_mixed());
+        assert!(inv.is_reduced_by(&amount(-1, "HOOL")));
+        assert!(!inv.is_reduced_by(&amount(1, "HOOL")));
+        inv.add_amount(amount(-1, "HOOL"), Some(cost(510, "USD", "2020-01-01")));
+        assert!(inv.is_mixed());
+        assert!((-inv).is_mixed());
+    }
+}
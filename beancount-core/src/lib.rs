@@ -1,30 +1,47 @@
 
 use typed_builder::TypedBuilder;
 
-pub use account::Account;
+pub use account::{Account, ParseAccountError};
 pub use account_types::AccountType;
 pub use amount::{Amount, IncompleteAmount};
-pub use date::Date;
+#[cfg(feature = "chrono")]
+pub use date::DateOutOfRangeError;
+pub use date::{Date, ParseDateError, Weekday};
 pub use directives::*;
 pub use flags::Flag;
-pub use position::CostSpec;
+pub use inventory::Inventory;
+pub use location::Location;
+pub use position::{Cost, CostSpec, Position};
 pub use posting::Posting;
 pub use posting::PriceSpec;
 
 pub mod account;
 pub mod account_types;
 pub mod amount;
+pub mod booking;
+pub mod convert;
 mod date;
 pub mod directives;
 pub mod flags;
+pub mod interpolate;
+pub mod inventory;
+pub mod location;
 pub mod metadata;
+pub mod ops;
+pub mod options;
+pub mod plugins;
 pub mod position;
 pub mod posting;
+pub mod prices;
+pub mod realization;
 pub mod render;
+#[cfg(feature = "serde")]
+pub mod serialization;
 
 /// Represents the complete ledger consisting of a number of directives.
 // TODO: Derive Hash when possible
 #[derive(Clone, Debug, PartialEq, Default, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ledger {
     pub directives: Vec<Directive>,
 }
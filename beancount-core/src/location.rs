@@ -0,0 +1,27 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Identifies the file a directive was parsed from. A single parsed string is file `0`; a ledger
+/// loaded from several files numbers them in the order they were loaded.
+pub type FileId = usize;
+
+/// The location of a directive, posting or metadata entry in its source file.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Location {
+    /// The file containing the item.
+    pub file: FileId,
+
+    /// The line the item starts on, starting at 1.
+    pub line: usize,
+
+    /// The column the item starts at, in characters, starting at 1.
+    pub column: usize,
+
+    /// The byte range of the item within its file.
+    pub span: Range<usize>,
+
+    /// The locations of the metadata entries attached to a directive or posting, by key. Always
+    /// empty for the location of a metadata entry.
+    pub meta: HashMap<String, Location>,
+}
@@ -6,8 +6,12 @@ use rust_decimal::Decimal;
 pub type Meta = HashMap<String, MetaValue>;
 
 /// An enum of the valid values in a metadata map.
-// TODO: Implement Display
 #[derive(Eq, PartialEq, Debug, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "type", content = "value", rename_all = "snake_case")
+)]
 pub enum MetaValue {
     Text(String),
     Account(super::account::Account),
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+use crate::account::Account;
+use crate::amount::Amount;
+use crate::directives::{compare_directives, Balance, Directive};
+use crate::interpolate::ToleranceOptions;
+use crate::inventory::Inventory;
+
+/// A `balance` directive whose assertion does not hold.
+#[derive(Clone, Debug, PartialEq, Error)]
+#[error(
+    "balance failed for {}: expected {} {}, accumulated {} {} ({} {} too {})",
+    balance.account,
+    balance.amount.num,
+    balance.amount.currency,
+    actual.num,
+    actual.currency,
+    difference.num.abs(),
+    difference.currency,
+    if difference.num.is_sign_negative() { "little" } else { "much" }
+)]
+pub struct BalanceError {
+    /// The failing directive.
+    pub balance: Balance,
+
+    /// The balance accumulated in the account (and its sub-accounts) at the start of the date.
+    pub actual: Amount,
+
+    /// The difference between the actual and the expected amount.
+    pub difference: Amount,
+}
+
+/// Check every `balance` directive against the balance of its account.
+///
+/// Directives are processed in date order (see
+/// [compare_directives](../../directives/fn.compare_directives.html)), so a balance assertion
+/// applies at the start of its date, before any transaction on that date. The asserted amount is
+/// compared against the units of its currency held in the account and all of its sub-accounts,
+/// regardless of cost. The difference may not exceed the explicit tolerance of the directive
+/// (`~ 0.01`) or, in its absence, a tolerance inferred from the precision of the asserted amount.
+///
+/// Transactions are expected to be complete, see [book](../../booking/fn.book.html); postings
+/// with missing units are ignored.
+///
+/// <https://beancount.github.io/docs/balance_assertions_in_beancount.html>
+pub fn validate_balances(
+    directives: &[Directive],
+    options: &ToleranceOptions,
+) -> Vec<BalanceError> {
+    let mut sorted: Vec<&Directive> = directives.iter().collect();
+    sorted.sort_by(|a, b| compare_directives(a, b));
+
+    let mut balances: HashMap<&Account, Inventory> = HashMap::new();
+    let mut errors = Vec::new();
+    for directive in sorted {
+        match directive {
+            Directive::Transaction(txn) => {
+                for posting in &txn.postings {
+                    if let (Some(num), Some(currency)) =
+                        (posting.units.num, &posting.units.currency)
+                    {
+                        let units = Amount {
+                            num,
+                            currency: currency.clone(),
+                        };
+                        balances
+                            .entry(&posting.account)
+                            .or_default()
+                            .add_amount(units, None);
+                    }
+                }
+            }
+            Directive::Balance(balance) => {
+                if let Some(error) = check_balance(balance, &balances, options) {
+                    errors.push(error);
+                }
+            }
+            _ => {}
+        }
+    }
+    errors
+}
+
+fn check_balance(
+    balance: &Balance,
+    balances: &HashMap<&Account, Inventory>,
+    options: &ToleranceOptions,
+) -> Option<BalanceError> {
+    let currency = &balance.amount.currency;
+    let actual: Decimal = balances
+        .iter()
+        .filter(|(account, _)| account.is_same_or_descendant_of(&balance.account))
+        .map(|(_, inventory)| inventory.units_of(currency))
+        .sum();
+    let difference = actual - balance.amount.num;
+    if difference.abs() <= balance_tolerance(balance, options) {
+        return None;
+    }
+    Some(BalanceError {
+        balance: balance.clone(),
+        actual: Amount {
+            num: actual,
+            currency: currency.clone(),
+        },
+        difference: Amount {
+            num: difference,
+            currency: currency.clone(),
+        },
+    })
+}
+
+/// The tolerance of a balance assertion: the explicit tolerance if there is one, otherwise twice
+/// the tolerance inferred from the precision of the asserted amount, i.e. one unit of its last
+/// digit with the default multiplier.
+pub fn balance_tolerance(balance: &Balance, options: &ToleranceOptions) -> Decimal {
+    match balance.tolerance {
+        Some(tolerance) => tolerance,
+        None if balance.amount.num.scale() > 0 => {
+            options.multiplier * Decimal::new(2, balance.amount.num.scale())
+        }
+        None => Decimal::ZERO,
+    }
+}
@@ -0,0 +1,5 @@
+//! Checks and transformations operating on a stream of (booked) directives.
+
+pub mod balance;
+pub mod pad;
+pub mod validation;
@@ -0,0 +1,157 @@
+use std::collections::{HashMap, HashSet};
+
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+use super::balance::balance_tolerance;
+use crate::account::Account;
+use crate::amount::Amount;
+use crate::directives::{sort_directives, Balance, Directive, Pad, Transaction};
+use crate::flags::Flag;
+use crate::interpolate::ToleranceOptions;
+use crate::inventory::Inventory;
+use crate::posting::Posting;
+use crate::Currency;
+
+/// A `pad` directive that was never used to satisfy a balance assertion.
+#[derive(Clone, Debug, PartialEq, Error)]
+#[error("unused pad entry for {}", pad.pad_to_account)]
+pub struct PadError {
+    /// The unused directive.
+    pub pad: Pad,
+}
+
+struct ActivePad {
+    index: usize,
+    padded: HashSet<Currency>,
+}
+
+/// Expand every `pad` directive into synthetic transactions.
+///
+/// A `pad` directive remains active on its account until the next `pad` on the same account. The
+/// first failing `balance` assertion of each currency on the padded account while the pad is
+/// active is satisfied by inserting a transaction, dated at the pad and flagged with
+/// [Flag::Padding](../../flags/enum.Flag.html#variant.Padding), which moves the missing amount
+/// from the source account of the pad. Assertions which already hold within their tolerance, see
+/// [balance_tolerance](../balance/fn.balance_tolerance.html), are left alone and do not use up the
+/// pad for their currency. Pads that never insert a transaction are reported as errors.
+///
+/// Transactions are expected to be complete, see [book](../../booking/fn.book.html). The returned
+/// directives are sorted, with every synthetic transaction following its `pad` directive.
+///
+/// <https://beancount.github.io/docs/beancount_language_syntax.html#pad>
+pub fn expand_pads(
+    mut directives: Vec<Directive>,
+    options: &ToleranceOptions,
+) -> (Vec<Directive>, Vec<PadError>) {
+    sort_directives(&mut directives);
+
+    let mut balances: HashMap<Account, Inventory> = HashMap::new();
+    let mut active: HashMap<Account, ActivePad> = HashMap::new();
+    let mut inserted: HashMap<usize, Vec<Transaction>> = HashMap::new();
+    for (index, directive) in directives.iter().enumerate() {
+        match directive {
+            Directive::Pad(pad) => {
+                let padded = HashSet::new();
+                active.insert(pad.pad_to_account.clone(), ActivePad { index, padded });
+            }
+            Directive::Transaction(txn) => {
+                for posting in &txn.postings {
+                    add_units(&mut balances, posting);
+                }
+            }
+            Directive::Balance(balance) => {
+                let active = match active.get_mut(&balance.account) {
+                    Some(active) => active,
+                    None => continue,
+                };
+                if active.padded.contains(&balance.amount.currency) {
+                    continue;
+                }
+                let pad = match &directives[active.index] {
+                    Directive::Pad(pad) => pad,
+                    _ => unreachable!(),
+                };
+                if let Some(txn) = padding_transaction(pad, balance, &balances, options) {
+                    for posting in &txn.postings {
+                        add_units(&mut balances, posting);
+                    }
+                    active.padded.insert(balance.amount.currency.clone());
+                    inserted.entry(active.index).or_default().push(txn);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut errors = Vec::new();
+    let mut expanded = Vec::with_capacity(directives.len() + inserted.len());
+    for (index, directive) in directives.into_iter().enumerate() {
+        let padding = inserted.remove(&index);
+        if let (Directive::Pad(pad), None) = (&directive, &padding) {
+            errors.push(PadError { pad: pad.clone() });
+        }
+        expanded.push(directive);
+        expanded.extend(padding.into_iter().flatten().map(Directive::Transaction));
+    }
+    (expanded, errors)
+}
+
+fn padding_transaction(
+    pad: &Pad,
+    balance: &Balance,
+    balances: &HashMap<Account, Inventory>,
+    options: &ToleranceOptions,
+) -> Option<Transaction> {
+    let currency = &balance.amount.currency;
+    let actual: Decimal = balances
+        .iter()
+        .filter(|(account, _)| account.is_same_or_descendant_of(&balance.account))
+        .map(|(_, inventory)| inventory.units_of(currency))
+        .sum();
+    let difference = balance.amount.num - actual;
+    if difference.abs() <= balance_tolerance(balance, options) {
+        return None;
+    }
+
+    let posting = |account: &Account, num: Decimal| {
+        Posting::builder()
+            .account(account.clone())
+            .units(
+                Amount {
+                    num,
+                    currency: currency.clone(),
+                }
+                .into(),
+            )
+            .build()
+    };
+    Some(
+        Transaction::builder()
+            .date(pad.date)
+            .flag(Flag::Padding)
+            .narration(format!(
+                "(Padding inserted for Balance of {} {} for difference {} {})",
+                balance.amount.num, currency, difference, currency
+            ))
+            .postings(vec![
+                posting(&pad.pad_to_account, difference),
+                posting(&pad.pad_from_account, -difference),
+            ])
+            .meta(pad.meta.clone())
+            .build(),
+    )
+}
+
+fn add_units(balances: &mut HashMap<Account, Inventory>, posting: &Posting) {
+    if let (Some(num), Some(currency)) = (posting.units.num, &posting.units.currency) {
+        let units = Amount {
+            num,
+            currency: currency.clone(),
+        };
+        balances
+            .entry(posting.account.clone())
+            .or_default()
+            .add_amount(units, None);
+    }
+}
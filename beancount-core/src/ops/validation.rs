@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+use crate::account::Account;
+use crate::amount::Amount;
+use crate::directives::{
+    compare_directives, Balance, Close, Directive, Document, Note, Open, Transaction,
+};
+use crate::interpolate::{compute_residual, infer_tolerances, ToleranceOptions};
+use crate::inventory::Inventory;
+use crate::{Currency, Date};
+
+/// An error found while validating a stream of directives.
+#[derive(Clone, Debug, PartialEq, Error)]
+pub enum ValidationError {
+    /// The weights of the postings of a transaction do not sum to zero, within tolerance.
+    #[error("transaction does not balance: {}", format_amounts(residual))]
+    Unbalanced {
+        transaction: Transaction,
+        /// The non-zero sum of the weights, per currency.
+        residual: Vec<Amount>,
+    },
+
+    /// The weight of some posting of a transaction is unknown, so its balance cannot be checked.
+    #[error("transaction is incomplete")]
+    Incomplete { transaction: Transaction },
+
+    /// A directive references an account which is not open on its date, either because it has
+    /// not been opened yet or because it has already been closed.
+    #[error("account {account} is not open on {date}")]
+    InactiveAccount {
+        directive: Directive,
+        account: Account,
+        date: Date,
+    },
+
+    /// An account is opened more than once.
+    #[error("account {} is already open", open.account)]
+    DuplicateOpen {
+        open: Open,
+        /// The directive which first opened the account.
+        previous: Open,
+    },
+
+    /// An account is closed while it still holds a non-zero balance.
+    #[error(
+        "account {} is closed with a non-zero balance: {}",
+        close.account,
+        format_amounts(balance)
+    )]
+    NonZeroClose {
+        close: Close,
+        /// The units held in the account when it is closed, per currency.
+        balance: Vec<Amount>,
+    },
+
+    /// A posting uses a currency which is not among the currencies allowed by the `open`
+    /// directive of its account.
+    #[error(
+        "currency {currency} is not allowed in account {}",
+        transaction.postings[*posting].account
+    )]
+    DisallowedCurrency {
+        transaction: Transaction,
+        /// Index of the offending posting within the transaction.
+        posting: usize,
+        currency: Currency,
+    },
+}
+
+fn format_amounts(amounts: &[Amount]) -> String {
+    amounts
+        .iter()
+        .map(|a| format!("{} {}", a.num, a.currency))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Check that the postings of every transaction balance.
+///
+/// The weights of the postings are summed per currency, and the sum of each currency must be
+/// within the tolerance inferred from the precision of the numbers in the transaction (see
+/// [infer_tolerances](../../interpolate/fn.infer_tolerances.html)), or the default tolerance
+/// of the currency if none can be inferred.
+pub fn validate_transaction_balances(
+    directives: &[Directive],
+    options: &ToleranceOptions,
+) -> Vec<ValidationError> {
+    directives
+        .iter()
+        .filter_map(|directive| match directive {
+            Directive::Transaction(txn) => check_transaction_balance(txn, options),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Check that the postings of a single transaction balance, returning the error found if they
+/// do not. See [validate_transaction_balances](fn.validate_transaction_balances.html).
+pub fn check_transaction_balance(
+    txn: &Transaction,
+    options: &ToleranceOptions,
+) -> Option<ValidationError> {
+    let residual = match compute_residual(&txn.postings) {
+        Some(residual) => residual,
+        None => {
+            return Some(ValidationError::Incomplete {
+                transaction: txn.clone(),
+            })
+        }
+    };
+    let tolerances = infer_tolerances(&txn.postings, options);
+    let unbalanced: Vec<Amount> = residual
+        .iter()
+        .filter(|pos| {
+            let tolerance: Decimal = tolerances
+                .get(&pos.units.currency)
+                .copied()
+                .unwrap_or_else(|| options.default_for(&pos.units.currency));
+            pos.units.num.abs() > tolerance
+        })
+        .map(|pos| pos.units.clone())
+        .collect();
+    (!unbalanced.is_empty()).then(|| ValidationError::Unbalanced {
+        transaction: txn.clone(),
+        residual: unbalanced,
+    })
+}
+
+/// Check that every directive only references accounts which are open on its date.
+///
+/// An account is open from the date of its `open` directive up to and including the date of its
+/// `close` directive. Accounts are referenced by the postings of transactions and by `balance`,
+/// `note`, `document`, `pad` and `close` directives. In addition:
+///
+/// - an account may only be opened once,
+/// - an account must have a zero balance, in every currency, when it is closed,
+/// - the units of a posting must be in one of the currencies listed by the `open` directive of its
+///   account, if any.
+///
+/// Transactions are expected to be complete, see [book](../../booking/fn.book.html); postings
+/// with missing units are not checked against currency constraints and do not count towards the
+/// balance of closed accounts.
+pub fn validate_accounts(directives: &[Directive]) -> Vec<ValidationError> {
+    let mut sorted: Vec<&Directive> = directives.iter().collect();
+    sorted.sort_by(|a, b| compare_directives(a, b));
+
+    let mut accounts = AccountStates::default();
+    let mut errors = Vec::new();
+    for directive in sorted {
+        let date = match directive.date() {
+            Some(date) => date,
+            None => continue,
+        };
+        let inactive = |account: &Account| ValidationError::InactiveAccount {
+            directive: directive.clone(),
+            account: account.clone(),
+            date: *date,
+        };
+        match directive {
+            Directive::Open(open) => match accounts.opened.get(&open.account) {
+                Some(previous) => errors.push(ValidationError::DuplicateOpen {
+                    open: open.clone(),
+                    previous: (*previous).clone(),
+                }),
+                None => {
+                    accounts.opened.insert(&open.account, open);
+                }
+            },
+            Directive::Close(close) => {
+                if !accounts.is_active(&close.account) {
+                    errors.push(inactive(&close.account));
+                    continue;
+                }
+                let balance = accounts.balances.remove(&close.account);
+                if let Some(balance) = balance.filter(|balance| !balance.is_empty()) {
+                    errors.push(ValidationError::NonZeroClose {
+                        close: close.clone(),
+                        balance: balance.iter().map(|pos| pos.units.clone()).collect(),
+                    });
+                }
+                accounts.closed.insert(&close.account, close);
+            }
+            Directive::Transaction(txn) => {
+                for (index, posting) in txn.postings.iter().enumerate() {
+                    if !accounts.is_active(&posting.account) {
+                        errors.push(inactive(&posting.account));
+                        continue;
+                    }
+                    let (num, currency) = match (posting.units.num, &posting.units.currency) {
+                        (Some(num), Some(currency)) => (num, currency),
+                        _ => continue,
+                    };
+                    let allowed = &accounts.opened[&posting.account].currencies;
+                    if !allowed.is_empty() && !allowed.contains(currency) {
+                        errors.push(ValidationError::DisallowedCurrency {
+                            transaction: txn.clone(),
+                            posting: index,
+                            currency: currency.clone(),
+                        });
+                    }
+                    let units = Amount {
+                        num,
+                        currency: currency.clone(),
+                    };
+                    accounts
+                        .balances
+                        .entry(&posting.account)
+                        .or_default()
+                        .add_amount(units, None);
+                }
+            }
+            Directive::Balance(Balance { account, .. })
+            | Directive::Note(Note { account, .. })
+            | Directive::Document(Document { account, .. })
+                if !accounts.is_active(account) =>
+            {
+                errors.push(inactive(account));
+            }
+            Directive::Pad(pad) => {
+                for account in [&pad.pad_to_account, &pad.pad_from_account] {
+                    if !accounts.is_active(account) {
+                        errors.push(inactive(account));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    errors
+}
+
+#[derive(Default)]
+struct AccountStates<'a> {
+    opened: HashMap<&'a Account, &'a Open>,
+    closed: HashMap<&'a Account, &'a Close>,
+    balances: HashMap<&'a Account, Inventory>,
+}
+
+impl AccountStates<'_> {
+    fn is_active(&self, account: &Account) -> bool {
+        self.opened.contains_key(account) && !self.closed.contains_key(account)
+    }
+}
@@ -0,0 +1,334 @@
+//! The options of a ledger, as set by its `option` directives.
+//!
+//! <https://beancount.github.io/docs/beancount_options_reference.html>
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+use super::account::is_valid_part;
+use super::account_types::{AccountType, RootNames};
+use super::directives::{BcOption, Booking, Directive};
+use super::interpolate::ToleranceOptions;
+use super::Currency;
+
+/// An `option` directive which could not be applied.
+#[derive(Clone, Debug, PartialEq, Error)]
+#[error("option '{}': {kind}", option.name)]
+pub struct OptionError {
+    /// The type of error.
+    pub kind: OptionErrorKind,
+
+    /// The offending option.
+    pub option: BcOption,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+pub enum OptionErrorKind {
+    /// The option is not a known Beancount option.
+    #[error("unknown option")]
+    Unknown,
+
+    /// The value of the option cannot be converted to the type of the option.
+    #[error("invalid value '{value}': {reason}")]
+    InvalidValue { value: String, reason: String },
+
+    /// The option is no longer supported by Beancount and is ignored.
+    #[error("deprecated option, ignored")]
+    Deprecated,
+
+    /// The option is computed while loading the ledger and cannot be set.
+    #[error("read-only option")]
+    ReadOnly,
+}
+
+/// How the plugins of a ledger are run, set by the `plugin_processing_mode` option.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Default)]
+pub enum PluginProcessingMode {
+    /// Run the default plugins of Beancount before the plugins of the ledger.
+    #[default]
+    Default,
+
+    /// Only run the plugins of the ledger.
+    Raw,
+}
+
+/// The options of a ledger, with their Beancount defaults.
+///
+/// # Example
+/// ```rust
+/// use beancount_core::options::Options;
+/// use beancount_core::{BcOption, Booking};
+///
+/// let option = |name: &str, val: &str| {
+///     BcOption::builder().name(name.into()).val(val.into()).build()
+/// };
+/// let (options, errors) = Options::from_options(&[
+///     option("title", "Personal ledger"),
+///     option("operating_currency", "USD"),
+///     option("operating_currency", "EUR"),
+///     option("booking_method", "FIFO"),
+///     option("render_commas", "maybe"),
+/// ]);
+/// assert_eq!(options.title, "Personal ledger");
+/// assert_eq!(options.operating_currency, ["USD", "EUR"]);
+/// assert_eq!(options.booking_method, Booking::Fifo);
+/// assert!(!options.render_commas);
+/// assert_eq!(errors.len(), 1);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    /// The title of the ledger.
+    pub title: String,
+
+    /// The names of the five root accounts, set by the `name_assets`, `name_liabilities`,
+    /// `name_equity`, `name_income` and `name_expenses` options.
+    pub root_names: RootNames,
+
+    /// The account, under the equity root, receiving the balances of the balance sheet accounts
+    /// at the start of a reporting period.
+    pub account_previous_balances: String,
+
+    /// The account, under the equity root, receiving the balances of the income statement
+    /// accounts before the start of a reporting period.
+    pub account_previous_earnings: String,
+
+    /// The account, under the equity root, receiving the conversions before the start of a
+    /// reporting period.
+    pub account_previous_conversions: String,
+
+    /// The account, under the equity root, receiving the balances of the income statement
+    /// accounts during a reporting period.
+    pub account_current_earnings: String,
+
+    /// The account, under the equity root, receiving the conversions during a reporting period.
+    pub account_current_conversions: String,
+
+    /// The account, under the income root, receiving unrealized gains.
+    pub account_unrealized_gains: String,
+
+    /// The account, under the equity root, receiving the rounding errors of transactions, if any.
+    pub account_rounding: Option<String>,
+
+    /// The currency in which conversions are booked at the end of a reporting period.
+    pub conversion_currency: Currency,
+
+    /// The tolerance of each currency whose tolerance cannot be inferred, see
+    /// [ToleranceOptions](../interpolate/struct.ToleranceOptions.html).
+    pub inferred_tolerance_default: HashMap<Currency, Decimal>,
+
+    /// Multiplier applied to the last significant digit of a number to get its tolerance.
+    pub inferred_tolerance_multiplier: Decimal,
+
+    /// Whether tolerances are also inferred from the cost of postings.
+    pub infer_tolerance_from_cost: bool,
+
+    /// Directories searched for documents.
+    pub documents: Vec<String>,
+
+    /// The main currencies of the ledger, in the order they are declared.
+    pub operating_currency: Vec<Currency>,
+
+    /// Whether numbers are rendered with thousands separators.
+    pub render_commas: bool,
+
+    /// How the plugins of the ledger are run.
+    pub plugin_processing_mode: PluginProcessingMode,
+
+    /// The maximum number of lines of a string.
+    pub long_string_maxlines: usize,
+
+    /// The booking method of accounts whose `open` directive does not specify one.
+    pub booking_method: Booking,
+
+    /// Whether `NULL` is accepted in place of the tags and links of a transaction.
+    pub allow_deprecated_none_for_tags_and_links: bool,
+
+    /// Whether the directory of the ledger is added to the Python path of plugins.
+    pub insert_pythonpath: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            title: "Beancount".to_string(),
+            root_names: RootNames::default(),
+            account_previous_balances: "Opening-Balances".to_string(),
+            account_previous_earnings: "Earnings:Previous".to_string(),
+            account_previous_conversions: "Conversions:Previous".to_string(),
+            account_current_earnings: "Earnings:Current".to_string(),
+            account_current_conversions: "Conversions:Current".to_string(),
+            account_unrealized_gains: "Earnings:Unrealized".to_string(),
+            account_rounding: None,
+            conversion_currency: "NOTHING".to_string(),
+            inferred_tolerance_default: HashMap::new(),
+            inferred_tolerance_multiplier: Decimal::new(5, 1),
+            infer_tolerance_from_cost: false,
+            documents: Vec::new(),
+            operating_currency: Vec::new(),
+            render_commas: false,
+            plugin_processing_mode: PluginProcessingMode::Default,
+            long_string_maxlines: 64,
+            booking_method: Booking::Strict,
+            allow_deprecated_none_for_tags_and_links: false,
+            insert_pythonpath: false,
+        }
+    }
+}
+
+const DEPRECATED: [&str; 4] = [
+    "allow_pipe_separator",
+    "default_tolerance",
+    "experiment_explicit_tolerances",
+    "tolerance",
+];
+
+const READ_ONLY: [&str; 5] = [
+    "commodities",
+    "dcontext",
+    "filename",
+    "include",
+    "input_hash",
+];
+
+impl Options {
+    /// Collect the options from the `option` directives of a ledger, in order. Options which
+    /// cannot be applied are reported as errors and leave the option unchanged.
+    pub fn from_directives(directives: &[Directive]) -> (Self, Vec<OptionError>) {
+        let options: Vec<_> = directives
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Option(option) => Some(option.clone()),
+                _ => None,
+            })
+            .collect();
+        Options::from_options(&options)
+    }
+
+    /// Collect the options from a list of options, see
+    /// [from_directives](#method.from_directives).
+    pub fn from_options(options: &[BcOption]) -> (Self, Vec<OptionError>) {
+        let mut result = Options::default();
+        let errors = options
+            .iter()
+            .filter_map(|option| {
+                let kind = result.apply(&option.name, option.val.trim()).err()?;
+                Some(OptionError {
+                    kind,
+                    option: option.clone(),
+                })
+            })
+            .collect();
+        (result, errors)
+    }
+
+    /// Apply a single option. Options which can be given several times, like
+    /// `operating_currency`, add to the values given before; other options replace them.
+    pub fn apply(&mut self, name: &str, val: &str) -> Result<(), OptionErrorKind> {
+        match name {
+            "title" => self.title = val.to_string(),
+            "name_assets" => self.set_root_name(AccountType::Assets, val)?,
+            "name_liabilities" => self.set_root_name(AccountType::Liabilities, val)?,
+            "name_equity" => self.set_root_name(AccountType::Equity, val)?,
+            "name_income" => self.set_root_name(AccountType::Income, val)?,
+            "name_expenses" => self.set_root_name(AccountType::Expenses, val)?,
+            "account_previous_balances" => self.account_previous_balances = account_name(val)?,
+            "account_previous_earnings" => self.account_previous_earnings = account_name(val)?,
+            "account_previous_conversions" => {
+                self.account_previous_conversions = account_name(val)?
+            }
+            "account_current_earnings" => self.account_current_earnings = account_name(val)?,
+            "account_current_conversions" => self.account_current_conversions = account_name(val)?,
+            "account_unrealized_gains" => self.account_unrealized_gains = account_name(val)?,
+            "account_rounding" => self.account_rounding = Some(account_name(val)?),
+            "conversion_currency" => self.conversion_currency = val.to_string(),
+            "inferred_tolerance_default" => {
+                let (currency, tolerance) = val
+                    .split_once(':')
+                    .ok_or_else(|| invalid(val, "expected CURRENCY:TOLERANCE"))?;
+                let tolerance = decimal(tolerance.trim())?;
+                self.inferred_tolerance_default
+                    .insert(currency.trim().to_string(), tolerance);
+            }
+            "inferred_tolerance_multiplier" => self.inferred_tolerance_multiplier = decimal(val)?,
+            "infer_tolerance_from_cost" => self.infer_tolerance_from_cost = boolean(val)?,
+            "documents" => self.documents.push(val.to_string()),
+            "operating_currency" => self.operating_currency.push(val.to_string()),
+            "render_commas" => self.render_commas = boolean(val)?,
+            "plugin_processing_mode" => {
+                self.plugin_processing_mode = match val {
+                    "default" => PluginProcessingMode::Default,
+                    "raw" => PluginProcessingMode::Raw,
+                    _ => return Err(invalid(val, "expected 'default' or 'raw'")),
+                }
+            }
+            "long_string_maxlines" => {
+                self.long_string_maxlines = val
+                    .parse()
+                    .map_err(|_| invalid(val, "expected a non-negative integer"))?
+            }
+            "booking_method" => {
+                self.booking_method = Booking::try_from(val).map_err(|_| {
+                    invalid(
+                        val,
+                        "expected one of STRICT, STRICT_WITH_SIZE, NONE, AVERAGE, FIFO, LIFO",
+                    )
+                })?
+            }
+            "allow_deprecated_none_for_tags_and_links" => {
+                self.allow_deprecated_none_for_tags_and_links = boolean(val)?
+            }
+            "insert_pythonpath" => self.insert_pythonpath = boolean(val)?,
+            _ if DEPRECATED.contains(&name) => return Err(OptionErrorKind::Deprecated),
+            _ if READ_ONLY.contains(&name) => return Err(OptionErrorKind::ReadOnly),
+            _ => return Err(OptionErrorKind::Unknown),
+        }
+        Ok(())
+    }
+
+    fn set_root_name(&mut self, ty: AccountType, val: &str) -> Result<(), OptionErrorKind> {
+        if val.contains(':') {
+            return Err(invalid(val, "expected a single account name component"));
+        }
+        self.root_names.set(ty, account_name(val)?);
+        Ok(())
+    }
+
+    /// The options used to infer tolerances.
+    pub fn tolerance_options(&self) -> ToleranceOptions {
+        ToleranceOptions {
+            default: self.inferred_tolerance_default.clone(),
+            multiplier: self.inferred_tolerance_multiplier,
+        }
+    }
+}
+
+fn invalid(val: &str, reason: &str) -> OptionErrorKind {
+    OptionErrorKind::InvalidValue {
+        value: val.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+fn decimal(val: &str) -> Result<Decimal, OptionErrorKind> {
+    val.parse().map_err(|_| invalid(val, "expected a number"))
+}
+
+fn boolean(val: &str) -> Result<bool, OptionErrorKind> {
+    match val.to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" => Ok(true),
+        "false" | "0" | "no" => Ok(false),
+        _ => Err(invalid(val, "expected TRUE or FALSE")),
+    }
+}
+
+/// Check that `val` is made of valid account name components, e.g. `Earnings:Previous`.
+fn account_name(val: &str) -> Result<String, OptionErrorKind> {
+    if val.split(':').all(is_valid_part) {
+        Ok(val.to_string())
+    } else {
+        Err(invalid(val, "expected an account name"))
+    }
+}
@@ -0,0 +1,53 @@
+//! Insert `open` directives for the accounts which are used without being opened.
+//!
+//! <https://github.com/beancount/beancount/blob/v2/beancount/plugins/auto_accounts.py>
+
+use std::collections::{HashMap, HashSet};
+
+use super::PluginError;
+use crate::account::Account;
+use crate::directives::{sort_directives, BcOption, Directive, Open};
+use crate::Date;
+
+pub const MODULE: &str = "beancount.plugins.auto_accounts";
+
+/// Open every account referred to by a directive but never opened, on the date it is first used.
+/// The directives are sorted afterwards, see [sort_directives](../../directives/fn.sort_directives.html).
+pub fn auto_accounts(
+    directives: Vec<Directive>,
+    _options: &[BcOption],
+    _config: Option<&str>,
+) -> (Vec<Directive>, Vec<PluginError>) {
+    let mut opened = HashSet::new();
+    let mut first_used: HashMap<&Account, Date> = HashMap::new();
+    for directive in &directives {
+        match (directive, directive.date()) {
+            (Directive::Open(open), _) => {
+                opened.insert(&open.account);
+            }
+            (_, Some(date)) => {
+                for account in directive.accounts() {
+                    let first = first_used.entry(account).or_insert(*date);
+                    *first = (*first).min(*date);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut opens: Vec<_> = first_used
+        .into_iter()
+        .filter(|(account, _)| !opened.contains(account))
+        .map(|(account, date)| (account.to_string(), account.clone(), date))
+        .collect();
+    opens.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut output: Vec<_> = opens
+        .into_iter()
+        .map(|(_, account, date)| {
+            Directive::Open(Open::builder().date(date).account(account).build())
+        })
+        .collect();
+    output.extend(directives);
+    sort_directives(&mut output);
+    (output, Vec::new())
+}
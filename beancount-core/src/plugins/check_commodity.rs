@@ -0,0 +1,62 @@
+//! Check that every currency used is declared with a `commodity` directive.
+//!
+//! <https://github.com/beancount/beancount/blob/v2/beancount/plugins/check_commodity.py>
+
+use std::collections::HashSet;
+
+use super::PluginError;
+use crate::directives::{BcOption, Directive};
+use crate::Currency;
+
+pub const MODULE: &str = "beancount.plugins.check_commodity";
+
+/// Report every currency used by an `open`, `balance`, `price` or transaction directive for which
+/// there is no `commodity` directive. Each currency is reported once, for the first directive
+/// using it.
+pub fn check_commodity(
+    directives: Vec<Directive>,
+    _options: &[BcOption],
+    _config: Option<&str>,
+) -> (Vec<Directive>, Vec<PluginError>) {
+    let mut reported: HashSet<&Currency> = directives
+        .iter()
+        .filter_map(|directive| match directive {
+            Directive::Commodity(commodity) => Some(&commodity.name),
+            _ => None,
+        })
+        .collect();
+    let mut errors = Vec::new();
+    for directive in &directives {
+        for currency in currencies(directive) {
+            if reported.insert(currency) {
+                let message = format!("Missing Commodity directive for '{}'", currency);
+                errors.push(PluginError::with_directive(message, directive.clone()));
+            }
+        }
+    }
+    (directives, errors)
+}
+
+fn currencies(directive: &Directive) -> Vec<&Currency> {
+    match directive {
+        Directive::Open(open) => open.currencies.iter().collect(),
+        Directive::Balance(balance) => vec![&balance.amount.currency],
+        Directive::Price(price) => vec![&price.currency, &price.amount.currency],
+        Directive::Transaction(txn) => txn
+            .postings
+            .iter()
+            .flat_map(|posting| {
+                let cost = posting
+                    .cost
+                    .as_ref()
+                    .and_then(|cost| cost.currency.as_ref());
+                let price = posting
+                    .price
+                    .as_ref()
+                    .and_then(|price| price.amount().currency.as_ref());
+                posting.units.currency.iter().chain(cost).chain(price)
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,42 @@
+//! Check that each currency is either always or never held at cost.
+//!
+//! <https://github.com/beancount/beancount/blob/v2/beancount/plugins/coherent_cost.py>
+
+use std::collections::{HashMap, HashSet};
+
+use super::PluginError;
+use crate::directives::{BcOption, Directive};
+use crate::Currency;
+
+pub const MODULE: &str = "beancount.plugins.coherent_cost";
+
+/// Report every currency used in postings both with and without a cost, once, for the first
+/// transaction where both kinds of use have been seen.
+pub fn coherent_cost(
+    directives: Vec<Directive>,
+    _options: &[BcOption],
+    _config: Option<&str>,
+) -> (Vec<Directive>, Vec<PluginError>) {
+    let mut at_cost: HashMap<&Currency, bool> = HashMap::new();
+    let mut reported = HashSet::new();
+    let mut errors = Vec::new();
+    for directive in &directives {
+        if let Directive::Transaction(txn) = directive {
+            for posting in &txn.postings {
+                let currency = match &posting.units.currency {
+                    Some(currency) => currency,
+                    None => continue,
+                };
+                let held_at_cost = posting.cost.is_some();
+                if *at_cost.entry(currency).or_insert(held_at_cost) != held_at_cost
+                    && reported.insert(currency)
+                {
+                    let message =
+                        format!("Currency '{}' is used both with and without cost", currency);
+                    errors.push(PluginError::with_directive(message, directive.clone()));
+                }
+            }
+        }
+    }
+    (directives, errors)
+}
@@ -0,0 +1,73 @@
+//! Emit `price` directives for the prices and costs of postings.
+//!
+//! <https://github.com/beancount/beancount/blob/v2/beancount/plugins/implicit_prices.py>
+
+use std::collections::{HashMap, HashSet};
+
+use super::PluginError;
+use crate::account::Account;
+use crate::directives::{BcOption, Directive, Price};
+use crate::inventory::{Inventory, MatchResult};
+use crate::metadata::MetaValue;
+use crate::prices::implicit_price;
+
+pub const MODULE: &str = "beancount.plugins.implicit_prices";
+
+/// The metadata key recording where an implied price comes from, either `from_price` or
+/// `from_cost`.
+pub const META_KEY: &str = "__implicit_prices__";
+
+/// Insert a `price` directive after each transaction for the price of each of its postings which
+/// has one, and for the cost of each posting which is held at cost and does not reduce an existing
+/// lot. Identical prices implied on the same date are only inserted once.
+///
+/// Transactions are expected to be complete, see [book](../../booking/fn.book.html).
+pub fn implicit_prices(
+    directives: Vec<Directive>,
+    _options: &[BcOption],
+    _config: Option<&str>,
+) -> (Vec<Directive>, Vec<PluginError>) {
+    let mut balances: HashMap<Account, Inventory> = HashMap::new();
+    let mut inserted = HashSet::new();
+    let mut output = Vec::with_capacity(directives.len());
+    for directive in directives {
+        let mut prices = Vec::new();
+        if let Directive::Transaction(txn) = &directive {
+            for posting in &txn.postings {
+                let result = posting.position().map(|position| {
+                    balances
+                        .entry(posting.account.clone())
+                        .or_default()
+                        .add_position(position)
+                });
+                let origin = if posting.price.is_some() {
+                    "from_price"
+                } else if posting.cost.is_some() && result != Some(MatchResult::Reduced) {
+                    "from_cost"
+                } else {
+                    continue;
+                };
+                let (currency, amount) = match implicit_price(posting) {
+                    Some(price) => price,
+                    None => continue,
+                };
+                if !inserted.insert((txn.date, currency.clone(), amount.clone())) {
+                    continue;
+                }
+                let meta = [(META_KEY.to_string(), MetaValue::Text(origin.to_string()))];
+                prices.push(Directive::Price(
+                    Price::builder()
+                        .date(txn.date)
+                        .currency(currency.clone())
+                        .amount(amount)
+                        .meta(meta.into_iter().collect())
+                        .location(posting.location.clone())
+                        .build(),
+                ));
+            }
+        }
+        output.push(directive);
+        output.extend(prices);
+    }
+    (output, Vec::new())
+}
@@ -0,0 +1,38 @@
+//! Check that no postings are made to accounts which have sub-accounts.
+//!
+//! <https://github.com/beancount/beancount/blob/v2/beancount/plugins/leafonly.py>
+
+use super::PluginError;
+use crate::directives::{BcOption, Directive};
+use crate::realization::realize;
+use crate::Ledger;
+
+pub const MODULE: &str = "beancount.plugins.leafonly";
+
+/// Report every account which has both sub-accounts and postings of its own. The error refers to
+/// the `open` directive of the account if there is one, and to its first posting otherwise.
+pub fn leafonly(
+    directives: Vec<Directive>,
+    _options: &[BcOption],
+    _config: Option<&str>,
+) -> (Vec<Directive>, Vec<PluginError>) {
+    let ledger = Ledger { directives };
+    let realization = realize(&ledger);
+    let errors = realization
+        .iter()
+        .filter(|node| node.children().next().is_some())
+        .filter_map(|node| {
+            let first = node.postings.first()?;
+            let open = node
+                .directives
+                .iter()
+                .find(|d| matches!(d, Directive::Open(_)))
+                .map(|d| (*d).clone());
+            let directive =
+                open.unwrap_or_else(|| Directive::Transaction(first.transaction.clone()));
+            let message = format!("Non-leaf account '{}' has postings on it", node.account);
+            Some(PluginError::with_directive(message, directive))
+        })
+        .collect();
+    (ledger.directives, errors)
+}
@@ -0,0 +1,211 @@
+//! Plugins transforming the stream of directives of a ledger, the Rust counterpart of Beancount's
+//! Python plugins.
+//!
+//! A plugin is registered in a [PluginRegistry](struct.PluginRegistry.html) under a module name,
+//! and runs when a `plugin` directive names that module:
+//!
+//! ```text
+//! plugin "beancount.plugins.module_name" "configuration data"
+//! ```
+//!
+//! <https://beancount.github.io/docs/beancount_scripting_plugins.html#plugins>
+
+use std::collections::HashMap;
+use std::fmt;
+
+use thiserror::Error;
+
+use super::directives::{BcOption, Directive};
+
+pub mod auto_accounts;
+pub mod check_commodity;
+pub mod coherent_cost;
+pub mod implicit_prices;
+pub mod leafonly;
+pub mod noduplicates;
+pub mod onecommodity;
+pub mod sellgains;
+pub mod unique_prices;
+
+/// A transformation of the directives of a ledger.
+///
+/// A plugin receives all the directives of the ledger, the `option` directives of the ledger and
+/// the configuration string given in its `plugin` directive, if any. It returns the transformed
+/// directives together with any errors it found.
+///
+/// Closures with the same signature as [run](#tymethod.run) implement this trait.
+///
+/// # Example
+/// ```rust
+/// use beancount_core::plugins::{PluginError, PluginRegistry};
+/// use beancount_core::{BcOption, Directive, Plugin};
+///
+/// let mut registry = PluginRegistry::new();
+/// registry.register(
+///     "drop_notes",
+///     |directives: Vec<Directive>, _: &[BcOption], _: Option<&str>| {
+///         let kept = directives
+///             .into_iter()
+///             .filter(|d| !matches!(d, Directive::Note(_)))
+///             .collect();
+///         (kept, Vec::<PluginError>::new())
+///     },
+/// );
+///
+/// let directives = vec![Directive::Plugin(
+///     Plugin::builder().module("drop_notes".into()).build(),
+/// )];
+/// let (directives, errors) = registry.run(directives);
+/// assert_eq!(directives.len(), 1);
+/// assert!(errors.is_empty());
+/// ```
+pub trait Plugin {
+    fn run(
+        &self,
+        directives: Vec<Directive>,
+        options: &[BcOption],
+        config: Option<&str>,
+    ) -> (Vec<Directive>, Vec<PluginError>);
+}
+
+impl<F> Plugin for F
+where
+    F: Fn(Vec<Directive>, &[BcOption], Option<&str>) -> (Vec<Directive>, Vec<PluginError>),
+{
+    fn run(
+        &self,
+        directives: Vec<Directive>,
+        options: &[BcOption],
+        config: Option<&str>,
+    ) -> (Vec<Directive>, Vec<PluginError>) {
+        self(directives, options, config)
+    }
+}
+
+/// An error reported by a plugin, or encountered while running it.
+#[derive(Clone, Debug, PartialEq, Error)]
+#[error("{module}: {message}")]
+pub struct PluginError {
+    /// The module name of the plugin. Filled in by the registry when running the plugin.
+    pub module: String,
+
+    /// A description of the error.
+    pub message: String,
+
+    /// The directive the error is about, if any.
+    pub directive: Option<Directive>,
+}
+
+impl PluginError {
+    pub fn new<T: ToString>(message: T) -> Self {
+        PluginError {
+            module: String::new(),
+            message: message.to_string(),
+            directive: None,
+        }
+    }
+
+    pub fn with_directive<T: ToString>(message: T, directive: Directive) -> Self {
+        PluginError {
+            directive: Some(directive),
+            ..PluginError::new(message)
+        }
+    }
+}
+
+/// Plugins by module name.
+#[derive(Default)]
+pub struct PluginRegistry {
+    plugins: HashMap<String, Box<dyn Plugin>>,
+}
+
+impl PluginRegistry {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// A registry of the native ports of Beancount's standard plugins, registered under their
+    /// Python module names, e.g. `beancount.plugins.auto_accounts`.
+    pub fn with_standard_plugins() -> Self {
+        let mut registry = PluginRegistry::new();
+        registry.register(auto_accounts::MODULE, auto_accounts::auto_accounts);
+        registry.register(check_commodity::MODULE, check_commodity::check_commodity);
+        registry.register(coherent_cost::MODULE, coherent_cost::coherent_cost);
+        registry.register(implicit_prices::MODULE, implicit_prices::implicit_prices);
+        registry.register(leafonly::MODULE, leafonly::leafonly);
+        registry.register(noduplicates::MODULE, noduplicates::noduplicates);
+        registry.register(onecommodity::MODULE, onecommodity::onecommodity);
+        registry.register(sellgains::MODULE, sellgains::sellgains);
+        registry.register(unique_prices::MODULE, unique_prices::unique_prices);
+        registry
+    }
+
+    /// Registers a plugin under the given module name, replacing any plugin registered under that
+    /// name before.
+    pub fn register<P: Plugin + 'static>(&mut self, module: impl Into<String>, plugin: P) {
+        self.plugins.insert(module.into(), Box::new(plugin));
+    }
+
+    /// The plugin registered under the given module name.
+    pub fn get(&self, module: &str) -> Option<&dyn Plugin> {
+        self.plugins.get(module).map(|plugin| plugin.as_ref())
+    }
+
+    /// The module names of the registered plugins, in no particular order.
+    pub fn modules(&self) -> impl Iterator<Item = &str> {
+        self.plugins.keys().map(String::as_str)
+    }
+
+    /// Runs the plugins named by the `plugin` directives among `directives`, in the order of those
+    /// directives, each on the output of the previous one.
+    ///
+    /// A `plugin` directive naming a module without a registered plugin is reported as an error,
+    /// and the remaining plugins still run.
+    pub fn run(&self, mut directives: Vec<Directive>) -> (Vec<Directive>, Vec<PluginError>) {
+        let plugins: Vec<_> = directives
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Plugin(plugin) => Some(plugin.clone()),
+                _ => None,
+            })
+            .collect();
+        let options: Vec<_> = directives
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Option(option) => Some(option.clone()),
+                _ => None,
+            })
+            .collect();
+
+        let mut errors = Vec::new();
+        for plugin in plugins {
+            let module = plugin.module.clone();
+            let (transformed, plugin_errors) = match self.get(&module) {
+                Some(implementation) => {
+                    implementation.run(directives, &options, plugin.config.as_deref())
+                }
+                None => {
+                    let message = format!("no plugin registered for module '{}'", module);
+                    let error = PluginError::with_directive(message, Directive::Plugin(plugin));
+                    (directives, vec![error])
+                }
+            };
+            directives = transformed;
+            errors.extend(plugin_errors.into_iter().map(|error| PluginError {
+                module: module.clone(),
+                ..error
+            }));
+        }
+        (directives, errors)
+    }
+}
+
+impl fmt::Debug for PluginRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut modules: Vec<_> = self.modules().collect();
+        modules.sort_unstable();
+        f.debug_struct("PluginRegistry")
+            .field("modules", &modules)
+            .finish()
+    }
+}
@@ -0,0 +1,74 @@
+//! Check that no directive is entered twice.
+//!
+//! <https://github.com/beancount/beancount/blob/v2/beancount/plugins/noduplicates.py>
+
+use std::collections::HashMap;
+
+use super::PluginError;
+use crate::directives::{BcOption, Directive};
+use crate::Date;
+
+pub const MODULE: &str = "beancount.plugins.noduplicates";
+
+/// Report every dated directive which is identical to an earlier one, disregarding metadata,
+/// source strings and locations.
+pub fn noduplicates(
+    directives: Vec<Directive>,
+    _options: &[BcOption],
+    _config: Option<&str>,
+) -> (Vec<Directive>, Vec<PluginError>) {
+    let mut seen: HashMap<Date, Vec<Directive>> = HashMap::new();
+    let mut errors = Vec::new();
+    for directive in &directives {
+        let date = match directive.date() {
+            Some(date) => *date,
+            None => continue,
+        };
+        let stripped = strip(directive);
+        let same_date = seen.entry(date).or_default();
+        if same_date.contains(&stripped) {
+            let message = format!("Duplicate entry on {}", date);
+            errors.push(PluginError::with_directive(message, directive.clone()));
+        } else {
+            same_date.push(stripped);
+        }
+    }
+    (directives, errors)
+}
+
+/// A copy of the directive without metadata, source string and location.
+fn strip(directive: &Directive) -> Directive {
+    macro_rules! strip {
+        ($variant:ident, $d:expr) => {{
+            let mut d = $d.clone();
+            d.meta.clear();
+            d.source = None;
+            d.location = None;
+            Directive::$variant(d)
+        }};
+    }
+    match directive {
+        Directive::Open(d) => strip!(Open, d),
+        Directive::Close(d) => strip!(Close, d),
+        Directive::Balance(d) => strip!(Balance, d),
+        Directive::Commodity(d) => strip!(Commodity, d),
+        Directive::Custom(d) => strip!(Custom, d),
+        Directive::Document(d) => strip!(Document, d),
+        Directive::Event(d) => strip!(Event, d),
+        Directive::Note(d) => strip!(Note, d),
+        Directive::Pad(d) => strip!(Pad, d),
+        Directive::Price(d) => strip!(Price, d),
+        Directive::Query(d) => strip!(Query, d),
+        Directive::Transaction(txn) => {
+            let mut stripped = strip!(Transaction, txn);
+            if let Directive::Transaction(txn) = &mut stripped {
+                for posting in &mut txn.postings {
+                    posting.meta.clear();
+                    posting.location = None;
+                }
+            }
+            stripped
+        }
+        other => other.clone(),
+    }
+}
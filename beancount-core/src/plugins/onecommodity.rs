@@ -0,0 +1,95 @@
+//! Check that each account holds a single commodity.
+//!
+//! <https://github.com/beancount/beancount/blob/v2/beancount/plugins/onecommodity.py>
+
+use std::collections::{HashMap, HashSet};
+
+use super::PluginError;
+use crate::account::Account;
+use crate::directives::{BcOption, Directive, Transaction};
+use crate::metadata::MetaValue;
+use crate::Currency;
+
+pub const MODULE: &str = "beancount.plugins.onecommodity";
+
+/// The metadata key which, set to `FALSE` on the `open` directive of an account, exempts the
+/// account from the check.
+pub const META_KEY: &str = "onecommodity";
+
+/// Report every account whose postings use more than one currency for their units, or more than
+/// one currency for their cost. Accounts opened with more than one allowed currency, or with the
+/// `onecommodity: FALSE` metadata, are skipped.
+pub fn onecommodity(
+    directives: Vec<Directive>,
+    _options: &[BcOption],
+    _config: Option<&str>,
+) -> (Vec<Directive>, Vec<PluginError>) {
+    let mut skipped = HashSet::new();
+    let mut used: Vec<AccountCurrencies> = Vec::new();
+    let mut index: HashMap<&Account, usize> = HashMap::new();
+    for directive in &directives {
+        match directive {
+            Directive::Open(open)
+                if open.currencies.len() > 1
+                    || open.meta.get(META_KEY) == Some(&MetaValue::Bool(false)) =>
+            {
+                skipped.insert(&open.account);
+            }
+            Directive::Transaction(txn) => {
+                for posting in &txn.postings {
+                    let i = *index.entry(&posting.account).or_insert_with(|| {
+                        used.push(AccountCurrencies {
+                            account: &posting.account,
+                            transaction: txn,
+                            units: Vec::new(),
+                            cost: Vec::new(),
+                        });
+                        used.len() - 1
+                    });
+                    let cost = posting.cost.as_ref().and_then(|c| c.currency.as_ref());
+                    add_unique(&mut used[i].units, posting.units.currency.as_ref());
+                    add_unique(&mut used[i].cost, cost);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut errors = Vec::new();
+    for entry in used.iter().filter(|e| !skipped.contains(e.account)) {
+        for (kind, currencies) in [("currency", &entry.units), ("cost currency", &entry.cost)] {
+            if currencies.len() > 1 {
+                let message = format!(
+                    "More than one {} in account '{}': {}",
+                    kind,
+                    entry.account,
+                    currencies
+                        .iter()
+                        .map(|c| c.as_str())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                );
+                let directive = Directive::Transaction(entry.transaction.clone());
+                errors.push(PluginError::with_directive(message, directive));
+            }
+        }
+    }
+    (directives, errors)
+}
+
+/// The currencies used in the postings to an account, in the order of their first use.
+struct AccountCurrencies<'a> {
+    account: &'a Account,
+    /// The first transaction posting to the account.
+    transaction: &'a Transaction,
+    units: Vec<&'a Currency>,
+    cost: Vec<&'a Currency>,
+}
+
+fn add_unique<'a>(currencies: &mut Vec<&'a Currency>, currency: Option<&'a Currency>) {
+    if let Some(currency) = currency {
+        if !currencies.contains(&currency) {
+            currencies.push(currency);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Cross-check the proceeds of sales against the prices of the lots sold.
+//!
+//! <https://github.com/beancount/beancount/blob/v2/beancount/plugins/sellgains.py>
+
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+
+use super::PluginError;
+use crate::account_types::AccountType;
+use crate::directives::{BcOption, Directive, Transaction};
+use crate::interpolate::{infer_tolerances, ToleranceOptions};
+use crate::options::Options;
+use crate::posting::PriceSpec;
+use crate::Currency;
+
+pub const MODULE: &str = "beancount.plugins.sellgains";
+
+/// Rounding happens differently for prices and proceeds, so a looser tolerance than for balancing
+/// transactions is accepted.
+const EXTRA_TOLERANCE_MULTIPLIER: i64 = 2;
+
+/// Check every transaction selling lots held at cost at a price: the value of the lots sold at
+/// that price must match the sum of the weights of the postings to accounts other than `Income`
+/// accounts, i.e. the proceeds of the sale including fees.
+///
+/// Transactions are expected to be complete, see [book](../../booking/fn.book.html).
+pub fn sellgains(
+    directives: Vec<Directive>,
+    options: &[BcOption],
+    _config: Option<&str>,
+) -> (Vec<Directive>, Vec<PluginError>) {
+    let options = Options::from_options(options).0.tolerance_options();
+    let errors = directives
+        .iter()
+        .filter_map(|directive| match directive {
+            Directive::Transaction(txn) => check_transaction(txn, &options)
+                .map(|message| PluginError::with_directive(message, directive.clone())),
+            _ => None,
+        })
+        .collect();
+    (directives, errors)
+}
+
+fn check_transaction(txn: &Transaction, options: &ToleranceOptions) -> Option<String> {
+    let is_sale = txn.postings.iter().any(|posting| {
+        posting.cost.is_some()
+            && posting.price.is_some()
+            && posting.units.num.is_some_and(|num| num.is_sign_negative())
+    });
+    if !is_sale {
+        return None;
+    }
+
+    let mut price_total: HashMap<Currency, Decimal> = HashMap::new();
+    let mut proceeds: HashMap<Currency, Decimal> = HashMap::new();
+    for posting in &txn.postings {
+        if posting.cost.is_some() {
+            let units = posting.units.num?;
+            let (value, currency) = match posting.price.as_ref()? {
+                PriceSpec::PerUnit(price) => (-units * price.num?, &price.currency),
+                PriceSpec::Total(price) if units.is_sign_negative() => {
+                    (price.num?, &price.currency)
+                }
+                PriceSpec::Total(price) => (-price.num?, &price.currency),
+            };
+            *price_total.entry(currency.clone()?).or_default() += value;
+        } else if posting.account.ty != AccountType::Income {
+            let weight = posting.weight()?;
+            *proceeds.entry(weight.currency).or_default() += weight.num;
+        }
+    }
+
+    let tolerances = infer_tolerances(&txn.postings, options);
+    let within_tolerance = |currency: &Currency, difference: Decimal| {
+        let tolerance = tolerances
+            .get(currency)
+            .copied()
+            .unwrap_or_else(|| options.default_for(currency));
+        difference.abs() <= tolerance * Decimal::from(EXTRA_TOLERANCE_MULTIPLIER)
+    };
+    let mut currencies: Vec<&Currency> = price_total.keys().chain(proceeds.keys()).collect();
+    currencies.sort();
+    currencies.dedup();
+    let valid = currencies.iter().all(|currency| {
+        let price = price_total.get(*currency).copied().unwrap_or_default();
+        let proceeds = proceeds.get(*currency).copied().unwrap_or_default();
+        within_tolerance(currency, price - proceeds)
+    });
+    if valid {
+        return None;
+    }
+    let format = |totals: &HashMap<Currency, Decimal>| {
+        currencies
+            .iter()
+            .filter_map(|currency| Some(format!("{} {}", totals.get(*currency)?, currency)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    Some(format!(
+        "Invalid price vs. proceeds/gains: {} vs. {}",
+        format(&price_total),
+        format(&proceeds)
+    ))
+}
@@ -0,0 +1,38 @@
+//! Check that there is at most one price per currency pair and date.
+//!
+//! <https://github.com/beancount/beancount/blob/v2/beancount/plugins/unique_prices.py>
+
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+
+use super::PluginError;
+use crate::directives::{BcOption, Directive};
+use crate::{Currency, Date};
+
+pub const MODULE: &str = "beancount.plugins.unique_prices";
+
+/// Report every `price` directive which gives a different price than an earlier one for the same
+/// currency pair on the same date. Repeating the same price is allowed.
+pub fn unique_prices(
+    directives: Vec<Directive>,
+    _options: &[BcOption],
+    _config: Option<&str>,
+) -> (Vec<Directive>, Vec<PluginError>) {
+    let mut prices: HashMap<(Date, &Currency, &Currency), Decimal> = HashMap::new();
+    let mut errors = Vec::new();
+    for directive in &directives {
+        if let Directive::Price(price) = directive {
+            let key = (price.date, &price.currency, &price.amount.currency);
+            let first = *prices.entry(key).or_insert(price.amount.num);
+            if first != price.amount.num {
+                let message = format!(
+                    "Disagreeing price entries for {} in {} on {}: {} vs. {}",
+                    price.currency, price.amount.currency, price.date, first, price.amount.num
+                );
+                errors.push(PluginError::with_directive(message, directive.clone()));
+            }
+        }
+    }
+    (directives, errors)
+}
@@ -1,11 +1,18 @@
 
+use std::ops::Neg;
+
 use rust_decimal::Decimal;
 use typed_builder::TypedBuilder;
 
 use super::amount::Amount;
 use super::{Currency, Date};
 
+/// The cost basis of a lot, as held in an [Inventory](../inventory/struct.Inventory.html).
+///
+/// Unlike a [CostSpec](struct.CostSpec.html), which may leave out any of its components, a `Cost`
+/// is always fully specified.
 #[derive(Clone, Debug, Eq, PartialEq, Hash, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cost {
     pub number: Decimal,
     pub currency: Currency,
@@ -21,6 +28,7 @@ pub struct Cost {
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.mtqrwt24wnzs>
 #[derive(Clone, Debug, Eq, PartialEq, Hash, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CostSpec {
     #[builder(default)]
     pub number_per: Option<Decimal>,
@@ -40,8 +48,76 @@ pub struct CostSpec {
     pub merge_cost: bool,
 }
 
+impl CostSpec {
+    /// Converts a fully specified cost spec, i.e. one with a per-unit number, a currency and a
+    /// date, into a [Cost](struct.Cost.html).
+    pub fn to_cost(&self) -> Option<Cost> {
+        match (
+            self.number_per,
+            self.number_total,
+            &self.currency,
+            &self.date,
+        ) {
+            (Some(number), None, Some(currency), Some(date)) => Some(Cost {
+                number,
+                currency: currency.clone(),
+                date: *date,
+                label: self.label.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl From<Cost> for CostSpec {
+    fn from(cost: Cost) -> Self {
+        CostSpec {
+            number_per: Some(cost.number),
+            number_total: None,
+            currency: Some(cost.currency),
+            date: Some(cost.date),
+            label: cost.label,
+            merge_cost: false,
+        }
+    }
+}
+
+/// A number of units of a commodity, optionally held at cost.
 #[derive(Clone, Debug, Eq, PartialEq, Hash, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     pub units: Amount,
+    #[builder(default)]
     pub cost: Option<Cost>,
 }
+
+impl Position {
+    /// Total cost of this position, i.e. the number of units times the per-unit cost. Returns
+    /// `None` if the position is not held at cost.
+    pub fn total_cost(&self) -> Option<Amount> {
+        self.cost.as_ref().map(|cost| Amount {
+            num: self.units.num * cost.number,
+            currency: cost.currency.clone(),
+        })
+    }
+
+    /// The amount this position contributes to the balance of a transaction: its total cost if
+    /// it is held at cost, otherwise its units.
+    pub fn weight(&self) -> Amount {
+        self.total_cost().unwrap_or_else(|| self.units.clone())
+    }
+}
+
+impl Neg for Position {
+    type Output = Position;
+
+    fn neg(self) -> Self::Output {
+        Position {
+            units: Amount {
+                num: -self.units.num,
+                currency: self.units.currency,
+            },
+            cost: self.cost,
+        }
+    }
+}
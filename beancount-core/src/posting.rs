@@ -1,10 +1,13 @@
+use std::convert::TryFrom;
+
 use typed_builder::TypedBuilder;
 
 use super::account::Account;
-use super::amount::IncompleteAmount;
+use super::amount::{Amount, IncompleteAmount};
 use super::flags::Flag;
+use super::location::Location;
 use super::metadata::Meta;
-use super::position::CostSpec;
+use super::position::{CostSpec, Position};
 
 /// Represents a transaction posting.  Postings represent a single amount being deposited to or
 /// withdrawn from an account.
@@ -28,6 +31,7 @@ use super::position::CostSpec;
 ///
 /// <https://docs.google.com/document/d/1wAMVrKIA2qtRGmoVDSUBJGmYZSygUaR0uOMW1GV3YE0/edit#heading=h.mtqrwt24wnzs>
 #[derive(Clone, Debug, Eq, PartialEq, TypedBuilder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Posting {
     /// Account being posted to.
     pub account: Account,
@@ -49,10 +53,88 @@ pub struct Posting {
 
     #[builder(default)]
     pub meta: Meta,
+
+    /// Location of the posting in its source file.
+    #[builder(default)]
+    pub location: Option<Location>,
+}
+
+impl Posting {
+    /// The position of this posting, if its units are complete and its cost, if any, is fully
+    /// specified. This is the case for every posting once it has been booked.
+    pub fn position(&self) -> Option<Position> {
+        let units = Amount::try_from(self.units.clone()).ok()?;
+        let cost = match &self.cost {
+            Some(spec) => Some(spec.to_cost()?),
+            None => None,
+        };
+        Some(Position { units, cost })
+    }
+
+    /// The weight of this posting, i.e. the amount it contributes to the balance of its
+    /// transaction: the total cost if the posting is held at cost, otherwise the converted amount
+    /// if it has a price, and the units themselves otherwise. Returns `None` if any of the numbers
+    /// or currencies needed are missing.
+    pub fn weight(&self) -> Option<Amount> {
+        let units = self.units.num?;
+        if let Some(cost) = &self.cost {
+            let per_unit = cost.number_per.map(|per| units * per);
+            let total = cost.number_total.map(|total| {
+                if units.is_sign_negative() {
+                    -total
+                } else {
+                    total
+                }
+            });
+            let num = match (per_unit, total) {
+                (None, None) => return None,
+                (per_unit, total) => per_unit.unwrap_or_default() + total.unwrap_or_default(),
+            };
+            return Some(Amount {
+                num,
+                currency: cost.currency.clone()?,
+            });
+        }
+        match &self.price {
+            Some(PriceSpec::PerUnit(price)) => Some(Amount {
+                num: units * price.num?,
+                currency: price.currency.clone()?,
+            }),
+            Some(PriceSpec::Total(price)) => Some(Amount {
+                num: if units.is_sign_negative() {
+                    -price.num?
+                } else {
+                    price.num?
+                },
+                currency: price.currency.clone()?,
+            }),
+            None => Some(Amount {
+                num: units,
+                currency: self.units.currency.clone()?,
+            }),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
 pub enum PriceSpec {
     PerUnit(IncompleteAmount),
     Total(IncompleteAmount),
 }
+
+impl PriceSpec {
+    /// The (possibly incomplete) amount of the price.
+    pub fn amount(&self) -> &IncompleteAmount {
+        match self {
+            PriceSpec::PerUnit(amount) | PriceSpec::Total(amount) => amount,
+        }
+    }
+
+    pub fn amount_mut(&mut self) -> &mut IncompleteAmount {
+        match self {
+            PriceSpec::PerUnit(amount) | PriceSpec::Total(amount) => amount,
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! A database of the prices of commodities over time.
+//!
+//! <https://github.com/beancount/beancount/blob/master/beancount/core/prices.py>
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use rust_decimal::Decimal;
+
+use super::amount::Amount;
+use super::directives::Directive;
+use super::posting::{Posting, PriceSpec};
+use super::{Currency, Date};
+
+/// Prices of commodities indexed by currency pair and date.
+///
+/// A price of `base` in `quote` is the number of units of `quote` one unit of `base` is worth.
+/// Lookups fall back to the inverse of the prices recorded for the opposite pair and, failing
+/// that, to the product of the prices through a common currency.
+///
+/// # Example
+/// ```rust
+/// use beancount_core::prices::PriceMap;
+/// use beancount_core::{Amount, Date};
+///
+/// let date = |s: &str| s.parse::<Date>().unwrap();
+/// let amount = |num: &str, currency: &str| Amount {
+///     num: num.parse().unwrap(),
+///     currency: currency.into(),
+/// };
+/// let mut prices = PriceMap::new();
+/// prices.insert(date("2021-03-01"), "HOOL", amount("500", "USD"));
+/// prices.insert(date("2021-03-10"), "USD", amount("0.8", "EUR"));
+///
+/// assert_eq!(prices.get_price("HOOL", "USD", date("2021-02-15")), None);
+/// assert_eq!(
+///     prices.get_price("HOOL", "USD", date("2021-03-15")),
+///     Some((date("2021-03-01"), 500.into()))
+/// );
+/// assert_eq!(
+///     prices.get_price("EUR", "USD", date("2021-03-15")),
+///     Some((date("2021-03-10"), "1.25".parse().unwrap()))
+/// );
+/// assert_eq!(
+///     prices.get_price("HOOL", "EUR", date("2021-03-15")),
+///     Some((date("2021-03-01"), 400.into()))
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PriceMap {
+    /// The recorded prices, by base and quote currency.
+    prices: HashMap<(Currency, Currency), BTreeMap<Date, Decimal>>,
+
+    /// The currencies each currency has a price in, or is the price of.
+    links: HashMap<Currency, BTreeSet<Currency>>,
+}
+
+impl PriceMap {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// A price map of all the `price` directives in `directives`.
+    pub fn from_directives(directives: &[Directive]) -> Self {
+        let mut prices = PriceMap::new();
+        for directive in directives {
+            if let Directive::Price(price) = directive {
+                prices.insert(price.date, &price.currency, price.amount.clone());
+            }
+        }
+        prices
+    }
+
+    /// Records the prices implied by the postings of the transactions in `directives`: the price
+    /// of a posting if it has one, and otherwise its cost. Postings are expected to be complete,
+    /// see [book](../booking/fn.book.html); incomplete ones are ignored.
+    pub fn add_implicit_prices(&mut self, directives: &[Directive]) {
+        for directive in directives {
+            if let Directive::Transaction(txn) = directive {
+                for posting in &txn.postings {
+                    if let Some((currency, price)) = implicit_price(posting) {
+                        self.insert(txn.date, currency, price);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Records the price of one unit of `base` on `date`, replacing any price of the same pair
+    /// recorded earlier for that date.
+    pub fn insert(&mut self, date: Date, base: &str, price: Amount) {
+        self.links
+            .entry(base.to_owned())
+            .or_default()
+            .insert(price.currency.clone());
+        self.links
+            .entry(price.currency.clone())
+            .or_default()
+            .insert(base.to_owned());
+        self.prices
+            .entry((base.to_owned(), price.currency))
+            .or_default()
+            .insert(date, price.num);
+    }
+
+    /// The pairs of base and quote currencies with recorded prices.
+    pub fn pairs(&self) -> impl Iterator<Item = (&Currency, &Currency)> {
+        self.prices.keys().map(|(base, quote)| (base, quote))
+    }
+
+    /// All the prices of `base` in `quote` by date, including the inverses of the prices of
+    /// `quote` in `base`. A recorded price takes precedence over an inverse one on the same date.
+    pub fn prices(&self, base: &str, quote: &str) -> BTreeMap<Date, Decimal> {
+        let mut prices: BTreeMap<Date, Decimal> = self
+            .series(quote, base)
+            .into_iter()
+            .flatten()
+            .filter_map(|(date, rate)| Some((*date, invert(*rate)?)))
+            .collect();
+        prices.extend(self.series(base, quote).into_iter().flatten());
+        prices
+    }
+
+    /// The most recent price of `base` in `quote` on or before `date`, together with the date it
+    /// was recorded on. If there is no such price for the pair or its inverse, the price is
+    /// triangulated through a common currency, preferring the one whose prices are most recent;
+    /// the date returned is then the earlier of the dates of the two prices used. Common currencies
+    /// through which the rate would overflow are skipped.
+    pub fn get_price(&self, base: &str, quote: &str, date: Date) -> Option<(Date, Decimal)> {
+        if base == quote {
+            return Some((date, Decimal::ONE));
+        }
+        self.get_direct_price(base, quote, date).or_else(|| {
+            self.links
+                .get(base)?
+                .iter()
+                .filter_map(|common| {
+                    let (first_date, first) = self.get_direct_price(base, common, date)?;
+                    let (second_date, second) = self.get_direct_price(common, quote, date)?;
+                    Some((first_date.min(second_date), first.checked_mul(second)?))
+                })
+                .max_by_key(|(date, _)| *date)
+        })
+    }
+
+    /// The most recent price of `base` in `quote`, see [get_price](#method.get_price).
+    pub fn get_latest_price(&self, base: &str, quote: &str) -> Option<(Date, Decimal)> {
+        let latest = self.prices.values().flat_map(|s| s.keys()).max()?;
+        self.get_price(base, quote, *latest)
+    }
+
+    fn get_direct_price(&self, base: &str, quote: &str, date: Date) -> Option<(Date, Decimal)> {
+        let latest = |series: Option<&BTreeMap<Date, Decimal>>| {
+            series?
+                .range(..=date)
+                .next_back()
+                .map(|(date, rate)| (*date, *rate))
+        };
+        let direct = latest(self.series(base, quote));
+        let inverse =
+            latest(self.series(quote, base)).and_then(|(date, rate)| Some((date, invert(rate)?)));
+        match (direct, inverse) {
+            (Some(direct), Some(inverse)) if inverse.0 > direct.0 => Some(inverse),
+            (direct, inverse) => direct.or(inverse),
+        }
+    }
+
+    fn series(&self, base: &str, quote: &str) -> Option<&BTreeMap<Date, Decimal>> {
+        self.prices.get(&(base.to_owned(), quote.to_owned()))
+    }
+}
+
+fn invert(rate: Decimal) -> Option<Decimal> {
+    Decimal::ONE.checked_div(rate)
+}
+
+pub(crate) fn implicit_price(posting: &Posting) -> Option<(&Currency, Amount)> {
+    let units = posting.units.num?;
+    let currency = posting.units.currency.as_ref()?;
+    let (num, quote) = match (&posting.price, &posting.cost) {
+        (Some(PriceSpec::PerUnit(price)), _) => (price.num?, price.currency.clone()?),
+        (Some(PriceSpec::Total(price)), _) => (
+            price.num?.checked_div(units.abs())?,
+            price.currency.clone()?,
+        ),
+        (None, Some(cost)) => {
+            let cost = cost.to_cost()?;
+            (cost.number, cost.currency)
+        }
+        (None, None) => return None,
+    };
+    Some((
+        currency,
+        Amount {
+            num,
+            currency: quote,
+        },
+    ))
+}
@@ -0,0 +1,200 @@
+//! The tree of accounts of a ledger, with the postings and balances of every account.
+//!
+//! <https://github.com/beancount/beancount/blob/master/beancount/core/realization.py>
+
+use std::collections::BTreeMap;
+
+use super::account::Account;
+use super::account_types::AccountType;
+use super::directives::{Directive, Transaction};
+use super::inventory::Inventory;
+use super::posting::Posting;
+use super::Ledger;
+
+/// A posting of a transaction, together with the transaction it belongs to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TxnPosting<'a> {
+    pub transaction: &'a Transaction,
+    pub posting: &'a Posting,
+}
+
+/// A node of the account tree: an account together with the postings and directives referring to
+/// it, and the child accounts below it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RealAccount<'a> {
+    /// The account of this node.
+    pub account: Account,
+
+    /// The postings to this account itself, in the order of the ledger.
+    pub postings: Vec<TxnPosting<'a>>,
+
+    /// The other directives referring to this account itself, such as its `open`, `close`,
+    /// `balance`, `pad`, `note` and `document` directives, in the order of the ledger.
+    pub directives: Vec<&'a Directive>,
+
+    /// The balance of the postings to this account itself.
+    pub balance: Inventory,
+
+    /// The balance of this account and all of its descendants.
+    pub total: Inventory,
+
+    children: BTreeMap<String, RealAccount<'a>>,
+}
+
+impl<'a> RealAccount<'a> {
+    fn new(account: Account) -> Self {
+        RealAccount {
+            account,
+            postings: Vec::new(),
+            directives: Vec::new(),
+            balance: Inventory::new(),
+            total: Inventory::new(),
+            children: BTreeMap::new(),
+        }
+    }
+
+    /// The direct child accounts of this account, sorted by name.
+    pub fn children(&self) -> impl Iterator<Item = &RealAccount<'a>> {
+        self.children.values()
+    }
+
+    /// The direct child account with the given leaf name.
+    pub fn child(&self, name: &str) -> Option<&RealAccount<'a>> {
+        self.children.get(name)
+    }
+
+    /// The node of the given account, if it is this account or one of its descendants.
+    pub fn get(&self, account: &Account) -> Option<&RealAccount<'a>> {
+        if !account.is_same_or_descendant_of(&self.account) {
+            return None;
+        }
+        account.parts[self.account.parts.len()..]
+            .iter()
+            .try_fold(self, |node, name| node.child(name))
+    }
+
+    /// Iterates over this account and all of its descendants, depth first, with every account
+    /// followed by its children sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = &RealAccount<'a>> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.values().rev());
+            Some(node)
+        })
+    }
+
+    fn get_or_create(&mut self, account: &Account) -> &mut RealAccount<'a> {
+        let depth = self.account.parts.len();
+        account.parts[depth..].iter().fold(self, |node, name| {
+            let child = node.account.join(name);
+            node.children
+                .entry(name.clone())
+                .or_insert_with(|| RealAccount::new(child))
+        })
+    }
+
+    fn compute_totals(&mut self) {
+        let mut total = self.balance.clone();
+        for child in self.children.values_mut() {
+            child.compute_totals();
+            total.add_inventory(&child.total);
+        }
+        self.total = total;
+    }
+}
+
+/// The account tree of a ledger, see [realize](fn.realize.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Realization<'a> {
+    roots: Vec<RealAccount<'a>>,
+}
+
+impl<'a> Realization<'a> {
+    /// The root accounts which have any descendants or directives, in the order of
+    /// [AccountType::ALL](../account_types/enum.AccountType.html#associatedconstant.ALL).
+    pub fn roots(&self) -> impl Iterator<Item = &RealAccount<'a>> {
+        self.roots.iter()
+    }
+
+    /// The root account of the given type.
+    pub fn root(&self, ty: AccountType) -> Option<&RealAccount<'a>> {
+        self.roots.iter().find(|root| root.account.ty == ty)
+    }
+
+    /// The node of the given account.
+    pub fn get(&self, account: &Account) -> Option<&RealAccount<'a>> {
+        self.root(account.ty)?.get(account)
+    }
+
+    /// Iterates over all accounts of the tree, depth first.
+    pub fn iter(&self) -> impl Iterator<Item = &RealAccount<'a>> {
+        self.roots.iter().flat_map(RealAccount::iter)
+    }
+
+    fn get_or_create(&mut self, account: &Account) -> &mut RealAccount<'a> {
+        // Roots are kept in the order of the account types.
+        let index = match self
+            .roots
+            .binary_search_by_key(&(account.ty as usize), |root| root.account.ty as usize)
+        {
+            Ok(index) => index,
+            Err(index) => {
+                self.roots.insert(index, RealAccount::new(account.root()));
+                index
+            }
+        };
+        self.roots[index].get_or_create(account)
+    }
+}
+
+/// Builds the account tree of a ledger.
+///
+/// Every account referred to by a directive gets a node, along with all of its ancestors. Each
+/// node holds the postings to its account and the balance they sum up to, as well as the total
+/// balance over its subtree.
+///
+/// Transactions are expected to be complete, see [book](../booking/fn.book.html); postings
+/// whose position is not fully known are recorded but do not contribute to the balance.
+///
+/// # Example
+/// ```rust
+/// use beancount_core::realization::realize;
+/// use beancount_core::Ledger;
+///
+/// let ledger = Ledger::default();
+/// let realization = realize(&ledger);
+/// assert_eq!(realization.iter().count(), 0);
+/// assert!(realization.get(&"Assets:Cash".parse().unwrap()).is_none());
+/// ```
+pub fn realize(ledger: &Ledger) -> Realization<'_> {
+    let mut realization = Realization { roots: Vec::new() };
+    for directive in &ledger.directives {
+        match directive {
+            Directive::Transaction(transaction) => {
+                for posting in &transaction.postings {
+                    let node = realization.get_or_create(&posting.account);
+                    node.postings.push(TxnPosting {
+                        transaction,
+                        posting,
+                    });
+                    if let Some(position) = posting.position() {
+                        node.balance.add_position(position);
+                    }
+                }
+            }
+            _ => {
+                for account in directive.accounts() {
+                    realization
+                        .get_or_create(account)
+                        .directives
+                        .push(directive);
+                }
+            }
+        }
+    }
+    for root in &mut realization.roots {
+        root.compute_totals();
+    }
+    realization
+}
@@ -0,0 +1,372 @@
+//! Export of directives as [JSON Lines](https://jsonlines.org), enabled by the `json` feature.
+//!
+//! Each directive is written as a JSON object on its own line, with the field names of the
+//! `beancount.core.data` namedtuples of Beancount, so that scripts written against the Python
+//! data model, or `jq` pipelines, can consume ledgers parsed in Rust:
+//!
+//! - `_type` is the name of the namedtuple, e.g. `"Transaction"` or `"Open"`. Its leading
+//!   underscore keeps it apart from the `type` field of `Event` and `Custom`.
+//! - `meta` holds the metadata of the directive together with the `filename` and `lineno` it was
+//!   parsed from. Postings carry their own `meta` the same way.
+//! - The remaining fields are those of the namedtuple: `date`, `account`, `currencies`,
+//!   `booking`, `flag`, `payee`, `narration`, `tags`, `links`, `postings`, `units`, `cost`,
+//!   `price`, `amount`, ...
+//!
+//! Numbers are written as strings holding the exact decimal number, as a Python `Decimal` would
+//! be, and amounts as `{"number": ..., "currency": ...}` objects. Missing numbers and currencies
+//! of incomplete amounts are `null`. Costs are cost specs with the fields `number_per`,
+//! `number_total`, `currency`, `date`, `label` and `merge`, and a total price `@@` is converted
+//! to a per-unit price as the Beancount parser does. The values of a `Custom` directive are
+//! `{"value": ..., "dtype": ...}` objects, with the name of the Python type of the value.
+//!
+//! `option`, `plugin` and `include` directives have no namedtuple, as Beancount keeps them in its
+//! options map rather than in its list of entries, and are not written.
+//!
+//! # Example
+//! ```rust
+//! use beancount_core::render::json::JsonLinesRenderer;
+//! use beancount_core::render::Renderer;
+//! use beancount_core::{Close, Directive, Location};
+//!
+//! let close = Close::builder()
+//!     .date("2016-11-28".parse().unwrap())
+//!     .account("Liabilities:CreditCard".parse().unwrap())
+//!     .location(Some(Location { line: 3, ..Location::default() }))
+//!     .build();
+//! let mut out = Vec::new();
+//! JsonLinesRenderer::with_filenames(["main.beancount"])
+//!     .render(&Directive::Close(close), &mut out)
+//!     .unwrap();
+//! assert_eq!(
+//!     String::from_utf8(out).unwrap(),
+//!     r#"{"_type":"Close","account":"Liabilities:CreditCard","date":"2016-11-28","meta":{"filename":"main.beancount","lineno":3}}"#
+//!         .to_owned()
+//!         + "\n"
+//! );
+//! ```
+
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::path::Path;
+
+use rust_decimal::Decimal;
+use serde_json::{json, Map, Value};
+
+use super::Renderer;
+use crate::metadata::{Meta, MetaValue};
+use crate::*;
+
+/// Renders directives as JSON Lines, see the [module documentation](index.html).
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct JsonLinesRenderer {
+    filenames: Vec<String>,
+}
+
+impl JsonLinesRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A renderer naming the file of each directive in its `filename` metadata by looking up the
+    /// [file](../../location/struct.Location.html#structfield.file) of its location in
+    /// `filenames`, e.g. the files of a ledger loaded with `beancount_parser::loader`. Without
+    /// it, directives parsed from a string are in the file `<string>`, as in Beancount.
+    pub fn with_filenames<I, P>(filenames: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        JsonLinesRenderer {
+            filenames: filenames
+                .into_iter()
+                .map(|path| path.as_ref().to_string_lossy().into_owned())
+                .collect(),
+        }
+    }
+
+    /// The JSON object of a directive, or `None` for directives which have no Beancount
+    /// namedtuple.
+    pub fn to_json(&self, directive: &Directive) -> Option<Value> {
+        let (ty, meta, location, mut fields) = match directive {
+            Directive::Open(open) => (
+                "Open",
+                &open.meta,
+                &open.location,
+                json!({
+                    "date": date(&open.date),
+                    "account": account(&open.account),
+                    "currencies": if open.currencies.is_empty() {
+                        Value::Null
+                    } else {
+                        json!(open.currencies)
+                    },
+                    "booking": open.booking.map(booking),
+                }),
+            ),
+            Directive::Close(close) => (
+                "Close",
+                &close.meta,
+                &close.location,
+                json!({
+                    "date": date(&close.date),
+                    "account": account(&close.account),
+                }),
+            ),
+            Directive::Commodity(commodity) => (
+                "Commodity",
+                &commodity.meta,
+                &commodity.location,
+                json!({
+                    "date": date(&commodity.date),
+                    "currency": commodity.name,
+                }),
+            ),
+            Directive::Pad(pad) => (
+                "Pad",
+                &pad.meta,
+                &pad.location,
+                json!({
+                    "date": date(&pad.date),
+                    "account": account(&pad.pad_to_account),
+                    "source_account": account(&pad.pad_from_account),
+                }),
+            ),
+            Directive::Balance(balance) => (
+                "Balance",
+                &balance.meta,
+                &balance.location,
+                json!({
+                    "date": date(&balance.date),
+                    "account": account(&balance.account),
+                    "amount": amount(Some(&balance.amount.num), Some(&balance.amount.currency)),
+                    "tolerance": balance.tolerance.as_ref().map(number),
+                    "diff_amount": null,
+                }),
+            ),
+            Directive::Transaction(txn) => (
+                "Transaction",
+                &txn.meta,
+                &txn.location,
+                json!({
+                    "date": date(&txn.date),
+                    "flag": txn.flag.to_string(),
+                    "payee": txn.payee,
+                    "narration": txn.narration,
+                    "tags": sorted(&txn.tags),
+                    "links": sorted(&txn.links),
+                    "postings": txn
+                        .postings
+                        .iter()
+                        .map(|posting| self.posting(posting))
+                        .collect::<Vec<_>>(),
+                }),
+            ),
+            Directive::Note(note) => (
+                "Note",
+                &note.meta,
+                &note.location,
+                json!({
+                    "date": date(&note.date),
+                    "account": account(&note.account),
+                    "comment": note.comment,
+                }),
+            ),
+            Directive::Event(event) => (
+                "Event",
+                &event.meta,
+                &event.location,
+                json!({
+                    "date": date(&event.date),
+                    "type": event.name,
+                    "description": event.description,
+                }),
+            ),
+            Directive::Query(query) => (
+                "Query",
+                &query.meta,
+                &query.location,
+                json!({
+                    "date": date(&query.date),
+                    "name": query.name,
+                    "query_string": query.query_string,
+                }),
+            ),
+            Directive::Price(price) => (
+                "Price",
+                &price.meta,
+                &price.location,
+                json!({
+                    "date": date(&price.date),
+                    "currency": price.currency,
+                    "amount": amount(Some(&price.amount.num), Some(&price.amount.currency)),
+                }),
+            ),
+            Directive::Document(document) => (
+                "Document",
+                &document.meta,
+                &document.location,
+                json!({
+                    "date": date(&document.date),
+                    "account": account(&document.account),
+                    "filename": document.path,
+                    "tags": sorted(&document.tags),
+                    "links": sorted(&document.links),
+                }),
+            ),
+            Directive::Custom(custom) => (
+                "Custom",
+                &custom.meta,
+                &custom.location,
+                json!({
+                    "date": date(&custom.date),
+                    "type": custom.name,
+                    "values": custom.args.iter().map(custom_value).collect::<Vec<_>>(),
+                }),
+            ),
+            Directive::Option(_)
+            | Directive::Plugin(_)
+            | Directive::Include(_)
+            | Directive::Unsupported => return None,
+        };
+        let object = fields.as_object_mut()?;
+        object.insert("_type".to_owned(), json!(ty));
+        object.insert("meta".to_owned(), self.meta(meta, location.as_ref()));
+        Some(fields)
+    }
+
+    fn posting(&self, posting: &Posting) -> Value {
+        let units = &posting.units;
+        let units = if units.num.is_none() && units.currency.is_none() {
+            Value::Null
+        } else {
+            amount(units.num.as_ref(), units.currency.as_ref())
+        };
+        let cost = posting.cost.as_ref().map(|cost| {
+            json!({
+                "number_per": cost.number_per.as_ref().map(number),
+                "number_total": cost.number_total.as_ref().map(number),
+                "currency": cost.currency,
+                "date": cost.date.as_ref().map(date),
+                "label": cost.label,
+                "merge": cost.merge_cost,
+            })
+        });
+        let price = posting.price.as_ref().map(|price| match price {
+            PriceSpec::PerUnit(price) => amount(price.num.as_ref(), price.currency.as_ref()),
+            PriceSpec::Total(price) => {
+                // As in the Beancount parser, a total price is divided by the number of units.
+                let per_unit = match (price.num, posting.units.num) {
+                    (_, Some(units)) if units.is_zero() => Some(Decimal::ZERO),
+                    (Some(total), Some(units)) => total.checked_div(units.abs()),
+                    (total, None) => total,
+                    (None, _) => None,
+                };
+                amount(per_unit.as_ref(), price.currency.as_ref())
+            }
+        });
+        json!({
+            "account": account(&posting.account),
+            "units": units,
+            "cost": cost,
+            "price": price,
+            "flag": posting.flag.as_ref().map(ToString::to_string),
+            "meta": self.meta(&posting.meta, posting.location.as_ref()),
+        })
+    }
+
+    /// The metadata of a directive or posting, with the `filename` and `lineno` it was parsed
+    /// from.
+    fn meta(&self, meta: &Meta, location: Option<&Location>) -> Value {
+        let (filename, lineno) = match location {
+            Some(location) => (
+                self.filenames
+                    .get(location.file)
+                    .map_or("<string>", String::as_str),
+                location.line,
+            ),
+            None => ("<unknown>", 0),
+        };
+        let mut object = Map::new();
+        object.insert("filename".to_owned(), json!(filename));
+        object.insert("lineno".to_owned(), json!(lineno));
+        for (key, value) in meta {
+            object.insert(key.clone(), meta_value(value));
+        }
+        Value::Object(object)
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Ledger, W> for JsonLinesRenderer {
+    fn render(&self, ledger: &'a Ledger, w: &mut W) -> io::Result<()> {
+        for directive in &ledger.directives {
+            self.render(directive, w)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Directive, W> for JsonLinesRenderer {
+    fn render(&self, directive: &'a Directive, w: &mut W) -> io::Result<()> {
+        if let Some(value) = self.to_json(directive) {
+            serde_json::to_writer(&mut *w, &value)?;
+            writeln!(w)?;
+        }
+        Ok(())
+    }
+}
+
+fn number(num: &Decimal) -> Value {
+    json!(num.to_string())
+}
+
+fn date(date: &Date) -> Value {
+    json!(date.to_string())
+}
+
+fn account(account: &Account) -> Value {
+    json!(account.to_string())
+}
+
+fn booking(booking: Booking) -> Value {
+    serde_json::to_value(booking).unwrap_or(Value::Null)
+}
+
+fn amount(num: Option<&Decimal>, currency: Option<&Currency>) -> Value {
+    json!({
+        "number": num.map(number),
+        "currency": currency,
+    })
+}
+
+fn sorted(set: &HashSet<String>) -> Vec<&String> {
+    let mut values: Vec<_> = set.iter().collect();
+    values.sort();
+    values
+}
+
+fn meta_value(value: &MetaValue) -> Value {
+    match value {
+        MetaValue::Text(text) => json!(text),
+        MetaValue::Account(a) => account(a),
+        MetaValue::Date(d) => date(d),
+        MetaValue::Currency(currency) => json!(currency),
+        MetaValue::Tag(tag) => json!(tag),
+        MetaValue::Bool(b) => json!(b),
+        MetaValue::Amount(a) => amount(Some(&a.num), Some(&a.currency)),
+        MetaValue::Number(num) => number(num),
+    }
+}
+
+/// A value of a `Custom` directive, with the name of its Python type. Accounts are strings in
+/// Python, told apart by the dummy type Beancount gives them.
+fn custom_value(value: &MetaValue) -> Value {
+    let dtype = match value {
+        MetaValue::Text(_) | MetaValue::Currency(_) | MetaValue::Tag(_) => "str",
+        MetaValue::Account(_) => "<AccountDummy>",
+        MetaValue::Date(_) => "date",
+        MetaValue::Bool(_) => "bool",
+        MetaValue::Amount(_) => "Amount",
+        MetaValue::Number(_) => "Decimal",
+    };
+    json!({"value": meta_value(value), "dtype": dtype})
+}
@@ -1,22 +1,229 @@
+use crate::account_types::RootNames;
 use crate::*;
-use metadata::MetaValue;
-use std::collections::HashMap;
-use std::{io, io::Write};
+use metadata::{Meta, MetaValue};
+use rust_decimal::Decimal;
+use std::{fmt, io, io::Write};
 use thiserror::Error;
 
-// I don't understand why this is all implemented on an empty struct. Wouldn't it be more
-// useful to just implement Display for on the actual struct?
-
-// Maybe there are copying concerns? Like this can make it super speedy to write out?
-// I'm skeptical tho
-
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
-pub struct BasicRenderer {}
+#[cfg(feature = "json")]
+pub mod json;
+
+/// Renders directives back to the Beancount syntax, such that parsing the output of a rendered
+/// [Ledger](../struct.Ledger.html) gives back the same directives.
+///
+/// Rendering lives on a renderer rather than in `Display` impls because the output depends on
+/// state: the root account names, which the `name_*` options of a ledger change, and the layout.
+/// The core types implement `Display` with the default renderer, using the
+/// [aligned](#method.aligned) layout for the alternate flag:
+///
+/// ```rust
+/// use beancount_core::{Amount, Posting};
+///
+/// let posting = Posting::builder()
+///     .account("Assets:Cash".parse().unwrap())
+///     .units(Amount { num: (-5).into(), currency: "USD".into() }.into())
+///     .build();
+/// assert_eq!(posting.to_string(), "\tAssets:Cash\t-5 USD\n");
+/// assert_eq!(format!("{:#}", posting), "  Assets:Cash  -5 USD\n");
+/// ```
+#[derive(Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub struct BasicRenderer {
+    root_names: RootNames,
+    aligned: bool,
+}
 
 impl BasicRenderer {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// A renderer writing accounts with the given root account names. When rendering a ledger,
+    /// the names are also updated by its `name_*` options.
+    pub fn with_root_names(root_names: RootNames) -> Self {
+        BasicRenderer {
+            root_names,
+            ..Self::default()
+        }
+    }
+
+    /// Whether to use the aligned layout, as written by `bean-format`: lines are indented with two
+    /// spaces instead of tabs and the units of the postings of a transaction are aligned on their
+    /// decimal point.
+    pub fn aligned(mut self, aligned: bool) -> Self {
+        self.aligned = aligned;
+        self
+    }
+
+    /// The indentation of a line at `depth`.
+    fn indent(&self, depth: usize) -> String {
+        if self.aligned {
+            "  ".repeat(depth)
+        } else {
+            "\t".repeat(depth)
+        }
+    }
+
+    /// The flag and account of a posting.
+    fn posting_account(&self, posting: &Posting) -> String {
+        let account = posting.account.display_with_root_names(&self.root_names);
+        match &posting.flag {
+            Some(flag) => format!("{} {}", flag, account),
+            None => account,
+        }
+    }
+
+    fn render_posting<W: Write>(
+        &self,
+        posting: &Posting,
+        layout: &PostingLayout,
+        w: &mut W,
+    ) -> std::io::Result<()> {
+        write!(w, "{}", self.indent(1))?;
+        let account = self.posting_account(posting);
+        if posting.units.num.is_none() && posting.units.currency.is_none() {
+            write!(w, "{}", account)?;
+        } else if self.aligned {
+            let (integer, fraction) = match &posting.units.num {
+                Some(num) => split_number(num),
+                None => (String::new(), String::new()),
+            };
+            let mut units = format!(
+                "{:<account_width$}  {:>integer_width$}{:<fraction_width$}",
+                account,
+                integer,
+                fraction,
+                account_width = layout.account,
+                integer_width = layout.integer,
+                fraction_width = layout.fraction,
+            );
+            match &posting.units.currency {
+                Some(currency) => {
+                    units.push(' ');
+                    units.push_str(currency);
+                }
+                None => units.truncate(units.trim_end().len()),
+            }
+            write!(w, "{}", units)?;
+        } else {
+            write!(w, "{}\t", account)?;
+            self.render(&posting.units, w)?;
+        }
+        if let Some(cost) = &posting.cost {
+            write!(w, " ")?;
+            self.render(cost, w)?;
+        }
+        if let Some(price) = &posting.price {
+            write!(w, " ")?;
+            self.render(price, w)?;
+        }
+        writeln!(w)?;
+        render_key_value(self, w, &posting.meta, 2)
+    }
+}
+
+/// Column widths aligning postings on the decimal point of their units.
+#[derive(Default)]
+struct PostingLayout {
+    account: usize,
+    integer: usize,
+    fraction: usize,
+}
+
+impl PostingLayout {
+    fn new(renderer: &BasicRenderer, postings: &[Posting]) -> Self {
+        let mut layout = PostingLayout::default();
+        for posting in postings {
+            let account = renderer.posting_account(posting).chars().count();
+            layout.account = layout.account.max(account);
+            if let Some(num) = &posting.units.num {
+                let (integer, fraction) = split_number(num);
+                layout.integer = layout.integer.max(integer.len());
+                layout.fraction = layout.fraction.max(fraction.len());
+            }
+        }
+        layout
+    }
+}
+
+/// Split a number into its integer part and its fractional part starting with the decimal point.
+fn split_number(num: &Decimal) -> (String, String) {
+    let mut integer = num.to_string();
+    let fraction = match integer.find('.') {
+        Some(point) => integer.split_off(point),
+        None => String::new(),
+    };
+    (integer, fraction)
+}
+
+/// Write the rendering of `item` with the default renderer to a formatter, using the aligned
+/// layout for the alternate flag. Rendering to a buffer does not fail, so this only returns an
+/// error if the formatter does.
+fn fmt_rendered<T>(item: T, f: &mut fmt::Formatter<'_>) -> fmt::Result
+where
+    BasicRenderer: Renderer<T, Vec<u8>>,
+{
+    let renderer = BasicRenderer::default().aligned(f.alternate());
+    let mut buffer = Vec::new();
+    renderer.render(item, &mut buffer).map_err(|_| fmt::Error)?;
+    f.write_str(std::str::from_utf8(&buffer).map_err(|_| fmt::Error)?)
+}
+
+macro_rules! impl_display {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl fmt::Display for $ty {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    fmt_rendered(self, f)
+                }
+            }
+        )*
+    };
+}
+
+// `Account` implements `Display` itself, with the default root names.
+impl_display!(
+    Ledger,
+    Directive,
+    Open,
+    Close,
+    Balance,
+    BcOption,
+    Commodity,
+    Custom,
+    Document,
+    Event,
+    Include,
+    Note,
+    Pad,
+    Plugin,
+    Price,
+    Query,
+    Transaction,
+    Posting,
+    Amount,
+    IncompleteAmount,
+    CostSpec,
+    PriceSpec,
+    MetaValue,
+);
+
+/// Quote a string, escaping the backslashes and double quotes it contains.
+///
+/// ```rust
+/// use beancount_core::render::quote;
+/// assert_eq!(quote(r#"Say "hi" \ bye"#), r#""Say \"hi\" \\ bye""#);
+/// ```
+pub fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
 }
 
 pub fn render<W: Write>(w: &mut W, ledger: &Ledger) -> std::io::Result<()> {
@@ -37,7 +244,8 @@ where
 pub enum BasicRendererError {
     #[error("an io error occurred")]
     Io(#[from] io::Error),
-    // y tho. Doesn't this just convert compile time failures into runtime failures?
+    /// An `Unsupported` directive has no syntax. [BasicRenderer] does not return this error: it
+    /// renders such directives as nothing.
     #[error("could not render unsupported directive")]
     Unsupported,
 }
@@ -48,9 +256,20 @@ pub trait Renderer<T, W: Write> {
 
 impl<'a, W: Write> Renderer<&'a Ledger, W> for BasicRenderer {
     fn render(&self, ledger: &'a Ledger, write: &mut W) -> std::io::Result<()> {
+        // Root account names are changed by options from the point they appear on, as in the
+        // parser.
+        let mut renderer = self.clone();
         for directive in &ledger.directives {
-            self.render(directive, write)?;
+            if let Directive::Unsupported = directive {
+                continue;
+            }
+            renderer.render(directive, write)?;
             writeln!(write)?;
+            if let Directive::Option(option) = directive {
+                if let Some((account_type, name)) = option.root_name_change() {
+                    renderer.root_names.set(account_type, name);
+                }
+            }
         }
         Ok(())
     }
@@ -59,11 +278,12 @@ impl<'a, W: Write> Renderer<&'a Ledger, W> for BasicRenderer {
 impl<'a, W: Write> Renderer<&'a Document, W> for BasicRenderer {
 
     fn render(&self, document: &'a Document, write: &mut W) -> std::io::Result<()> {
-        // TODO: Tags? Links?
         write!(write, "{} document ", document.date)?;
         self.render(&document.account, write)?;
-        writeln!(write, " \"{}\"", document.path)?;
-        render_key_value(self, write, &document.meta)?;
+        write!(write, " {}", quote(&document.path))?;
+        render_tags_links(write, &document.tags, &document.links)?;
+        writeln!(write)?;
+        render_key_value(self, write, &document.meta, 1)?;
         Ok(())
     }
 }
@@ -88,36 +308,59 @@ impl<'a, W: Write> Renderer<&'a Directive, W> for BasicRenderer {
             Price(price) => self.render(price, write),
             Query(query) => self.render(query, write),
             Transaction(transaction) => self.render(transaction, write),
-            Unsupported => panic!("unsupported"),
+            // Nothing to render, e.g. for the org-mode headings skipped by the parser.
+            Unsupported => Ok(()),
         }
     }
 }
 
+/// Render metadata entries, sorted by key, each on its own line indented with `depth` tabs.
 fn render_key_value<W: Write>(
     renderer: &BasicRenderer,
     w: &mut W,
-    kv: &HashMap<String, MetaValue>,
+    kv: &Meta,
+    depth: usize,
 ) -> std::io::Result<()> {
-    for (key, value) in kv {
-        write!(w, "\t{}: ", key)?;
+    let mut entries: Vec<_> = kv.iter().collect();
+    entries.sort_by_key(|(key, _)| *key);
+    for (key, value) in entries {
+        write!(w, "{}{}: ", renderer.indent(depth), key)?;
         renderer.render(value, w)?;
         writeln!(w)?;
     }
     Ok(())
 }
 
+fn render_tags_links<W: Write>(
+    w: &mut W,
+    tags: &std::collections::HashSet<metadata::Tag>,
+    links: &std::collections::HashSet<metadata::Link>,
+) -> std::io::Result<()> {
+    let mut tags: Vec<_> = tags.iter().collect();
+    tags.sort();
+    let mut links: Vec<_> = links.iter().collect();
+    links.sort();
+    for tag in tags {
+        write!(w, " #{}", tag)?;
+    }
+    for link in links {
+        write!(w, " ^{}", link)?;
+    }
+    Ok(())
+}
+
 impl<'a, W: Write> Renderer<&'a MetaValue, W> for BasicRenderer {
 
     fn render(&self, mv: &'a MetaValue, w: &mut W) -> std::io::Result<()> {
         match mv {
             MetaValue::Account(account) => self.render(account, w)?,
             MetaValue::Amount(amount) => self.render(amount, w)?,
-            MetaValue::Bool(b) => write!(w, "{}", if *b { "true" } else { "false" })?,
+            MetaValue::Bool(b) => write!(w, "{}", if *b { "TRUE" } else { "FALSE" })?,
             MetaValue::Currency(curr) => write!(w, "{}", curr)?,
             MetaValue::Date(date) => write!(w, "{}", date)?,
             MetaValue::Number(num) => write!(w, "{}", num)?,
-            MetaValue::Tag(t) => write!(w, "{}", t)?,
-            MetaValue::Text(t) => write!(w, "{}", t)?,
+            MetaValue::Tag(t) => write!(w, "#{}", t)?,
+            MetaValue::Text(t) => write!(w, "{}", quote(t))?,
         }
         Ok(())
     }
@@ -128,8 +371,8 @@ impl<'a, W: Write> Renderer<&'a Open, W> for BasicRenderer {
     fn render(&self, open: &'a Open, write: &mut W) -> std::io::Result<()> {
         write!(write, "{} open ", open.date)?;
         self.render(&open.account, write)?;
-        for currency in open.currencies.iter() {
-            write!(write, " {}", currency)?;
+        if !open.currencies.is_empty() {
+            write!(write, " {}", open.currencies.join(","))?;
         }
         match open.booking {
             Some(Booking::Strict) => write!(write, r#" "STRICT""#)?,
@@ -141,7 +384,7 @@ impl<'a, W: Write> Renderer<&'a Open, W> for BasicRenderer {
             None => {}
         };
         writeln!(write)?;
-        render_key_value(self, write, &open.meta)?;
+        render_key_value(self, write, &open.meta, 1)?;
         Ok(())
     }
 }
@@ -152,7 +395,7 @@ impl<'a, W: Write> Renderer<&'a Close, W> for BasicRenderer {
         write!(write, "{} close ", close.date)?;
         self.render(&close.account, write)?;
         writeln!(write)?;
-        render_key_value(self, write, &close.meta)?;
+        render_key_value(self, write, &close.meta, 1)?;
         Ok(())
     }
 }
@@ -162,9 +405,8 @@ impl<'a, W: Write> Renderer<&'a Account, W> for BasicRenderer {
     fn render(&self, account: &'a Account, write: &mut W) -> std::io::Result<()> {
         write!(
             write,
-            "{}:{}",
-            account.ty.default_name(),
-            account.parts.join(":")
+            "{}",
+            account.display_with_root_names(&self.root_names)
         )?;
         Ok(())
     }
@@ -175,18 +417,18 @@ impl<'a, W: Write> Renderer<&'a Balance, W> for BasicRenderer {
     fn render(&self, balance: &'a Balance, w: &mut W) -> std::io::Result<()> {
         write!(w, "{} balance ", balance.date)?;
         self.render(&balance.account, w)?;
-        write!(w, "\t")?;
-        write!(w, "{}", balance.amount.num)?;
+        let separator = if self.aligned { " " } else { "\t" };
+        write!(w, "{}{}", separator, balance.amount.num)?;
         if let Some(tol) = balance.tolerance {
             write!(w, " ~ {}", tol)?;
         }
-        writeln!(w, "{}", balance.amount.currency)?;
-        render_key_value(self, w, &balance.meta)?;
+        writeln!(w, " {}", balance.amount.currency)?;
+        render_key_value(self, w, &balance.meta, 1)?;
         Ok(())
     }
 }
 
-impl<'a, W: Write> Renderer<&Amount, W> for BasicRenderer {
+impl<W: Write> Renderer<&Amount, W> for BasicRenderer {
 
     fn render(&self, amount: &Amount, w: &mut W) -> std::io::Result<()> {
         write!(w, "{} {}", amount.num, amount.currency)?;
@@ -197,7 +439,7 @@ impl<'a, W: Write> Renderer<&Amount, W> for BasicRenderer {
 impl<'a, W: Write> Renderer<&'a BcOption, W> for BasicRenderer {
 
     fn render(&self, option: &'a BcOption, w: &mut W) -> std::io::Result<()> {
-        writeln!(w, "option \"{}\" \"{}\"", option.name, option.val)?;
+        writeln!(w, "option {} {}", quote(&option.name), quote(&option.val))?;
         Ok(())
     }
 }
@@ -206,22 +448,30 @@ impl<'a, W: Write> Renderer<&'a Commodity, W> for BasicRenderer {
 
     fn render(&self, commodity: &'a Commodity, w: &mut W) -> std::io::Result<()> {
         writeln!(w, "{} commodity {}", commodity.date, commodity.name)?;
-        render_key_value(self, w, &commodity.meta)
+        render_key_value(self, w, &commodity.meta, 1)
     }
 }
 
 impl<'a, W: Write> Renderer<&'a Custom, W> for BasicRenderer {
 
     fn render(&self, custom: &'a Custom, w: &mut W) -> std::io::Result<()> {
-        write!(
-            w,
-            "{} custom \"{}\" {}",
-            custom.date,
-            custom.name,
-            custom.args.join(" ")
-        )?;
+        write!(w, "{} custom {}", custom.date, quote(&custom.name))?;
+        for arg in &custom.args {
+            // Negative numbers are wrapped in parentheses, or `1 -2` would be read back as a
+            // single argument, `-1`.
+            match arg {
+                MetaValue::Number(num) if num.is_sign_negative() => write!(w, " ({})", num)?,
+                MetaValue::Amount(amount) if amount.num.is_sign_negative() => {
+                    write!(w, " ({}) {}", amount.num, amount.currency)?
+                }
+                _ => {
+                    write!(w, " ")?;
+                    self.render(arg, w)?;
+                }
+            }
+        }
         writeln!(w)?;
-        render_key_value(self, w, &custom.meta)
+        render_key_value(self, w, &custom.meta, 1)
     }
 }
 
@@ -230,17 +480,19 @@ impl<'a, W: Write> Renderer<&'a Event, W> for BasicRenderer {
     fn render(&self, event: &'a Event, w: &mut W) -> std::io::Result<()> {
         writeln!(
             w,
-            "{} event \"{}\" \"{}\"",
-            event.date, event.name, event.description
+            "{} event {} {}",
+            event.date,
+            quote(&event.name),
+            quote(&event.description)
         )?;
-        render_key_value(self, w, &event.meta)
+        render_key_value(self, w, &event.meta, 1)
     }
 }
 
 impl<'a, W: Write> Renderer<&'a Include, W> for BasicRenderer {
 
     fn render(&self, include: &'a Include, w: &mut W) -> std::io::Result<()> {
-        writeln!(w, "include \"{}\'", include.filename)?;
+        writeln!(w, "include {}", quote(&include.filename))?;
         Ok(())
     }
 }
@@ -250,8 +502,8 @@ impl<'a, W: Write> Renderer<&'a Note, W> for BasicRenderer {
     fn render(&self, note: &'a Note, w: &mut W) -> std::io::Result<()> {
         write!(w, "{} note ", note.date)?;
         self.render(&note.account, w)?;
-        writeln!(w, " {}", note.comment)?;
-        render_key_value(self, w, &note.meta)
+        writeln!(w, " {}", quote(&note.comment))?;
+        render_key_value(self, w, &note.meta, 1)
     }
 }
 
@@ -263,16 +515,16 @@ impl<'a, W: Write> Renderer<&'a Pad, W> for BasicRenderer {
         write!(w, " ")?;
         self.render(&pad.pad_from_account, w)?;
         writeln!(w)?;
-        render_key_value(self, w, &pad.meta)
+        render_key_value(self, w, &pad.meta, 1)
     }
 }
 
 impl<'a, W: Write> Renderer<&'a Plugin, W> for BasicRenderer {
 
     fn render(&self, plugin: &'a Plugin, w: &mut W) -> std::io::Result<()> {
-        write!(w, "plugin \"{}\"", plugin.module)?;
+        write!(w, "plugin {}", quote(&plugin.module))?;
         if let Some(config) = &plugin.config {
-            write!(w, " \"{}\"", config)?;
+            write!(w, " {}", quote(config))?;
         }
         writeln!(w)?;
         Ok(())
@@ -285,7 +537,7 @@ impl<'a, W: Write> Renderer<&'a Price, W> for BasicRenderer {
         write!(w, "{} price {} ", price.date, price.currency)?;
         self.render(&price.amount, w)?;
         writeln!(w)?;
-        render_key_value(self, w, &price.meta)
+        render_key_value(self, w, &price.meta, 1)
     }
 }
 
@@ -294,10 +546,12 @@ impl<'a, W: Write> Renderer<&'a Query, W> for BasicRenderer {
     fn render(&self, query: &'a Query, w: &mut W) -> std::io::Result<()> {
         writeln!(
             w,
-            "{} query \"{}\" \"{}\"",
-            query.date, query.name, query.query_string
+            "{} query {} {}",
+            query.date,
+            quote(&query.name),
+            quote(&query.query_string)
         )?;
-        render_key_value(self, w, &query.meta)
+        render_key_value(self, w, &query.meta, 1)
     }
 }
 
@@ -306,85 +560,64 @@ impl<'a, W: Write> Renderer<&'a Transaction, W> for BasicRenderer {
     fn render(&self, transaction: &'a Transaction, w: &mut W) -> std::io::Result<()> {
         write!(w, "{} {}", transaction.date, transaction.flag)?;
         if let Some(payee) = &transaction.payee {
-            write!(w, " \"{}\"", payee)?;
-        }
-        write!(w, " \"{}\"", &transaction.narration)?;
-        for tag in &transaction.tags {
-            write!(w, " {}", tag)?;
-        }
-        for link in &transaction.links {
-            write!(w, " {}", link)?;
+            write!(w, " {}", quote(payee))?;
         }
+        write!(w, " {}", quote(&transaction.narration))?;
+        render_tags_links(w, &transaction.tags, &transaction.links)?;
         writeln!(w)?;
+        // The metadata of the transaction has to come before its postings, otherwise it would be
+        // attached to the last posting.
+        render_key_value(self, w, &transaction.meta, 1)?;
+        let layout = PostingLayout::new(self, &transaction.postings);
         for posting in &transaction.postings {
-            self.render(posting, w)?;
+            self.render_posting(posting, &layout, w)?;
         }
-        render_key_value(self, w, &transaction.meta)
+        Ok(())
     }
 }
 
 impl<'a, W: Write> Renderer<&'a Posting, W> for BasicRenderer {
 
     fn render(&self, posting: &'a Posting, w: &mut W) -> std::io::Result<()> {
-        write!(w, "\t")?;
-        if let Some(flag) = &posting.flag {
-            write!(w, "{} ", flag)?;
-        }
-        self.render(&posting.account, w)?;
-        write!(w, "\t")?;
-        self.render(&posting.units, w)?;
-        if let Some(cost) = &posting.cost {
-            write!(w, " ")?;
-            self.render(cost, w)?;
-        }
-        if let Some(price) = &posting.price {
-            write!(w, " ")?;
-            self.render(price, w)?;
-        }
-        writeln!(w)?;
-        render_key_value(self, w, &posting.meta)
+        let layout = PostingLayout::new(self, std::slice::from_ref(posting));
+        self.render_posting(posting, &layout, w)
     }
 }
 
 impl<'a, W: Write> Renderer<&'a CostSpec, W> for BasicRenderer {
 
     fn render(&self, cost: &'a CostSpec, w: &mut W) -> std::io::Result<()> {
-        let double_brackets = cost.number_total.is_some();
-        if double_brackets {
-            write!(w, "{{{{")?;
-        } else {
-            write!(w, "{{")?;
-        }
-        let mut first = true;
-
-        if let (Some(cost), Some(currency)) =
-            (&cost.number_total.or(cost.number_per), &cost.currency)
-        {
-            write!(w, "{} {}", cost, currency)?;
-            first = false;
+        // A total cost alone is written with double braces, a per-unit cost together with a
+        // total cost with `#`.
+        let total_only = cost.number_per.is_none() && cost.number_total.is_some();
+        let mut components = Vec::new();
+        let currency = cost.currency.as_deref();
+        let amount = match (cost.number_per, cost.number_total) {
+            (Some(per), Some(total)) => Some(format!("{} # {}", per, total)),
+            (Some(number), None) | (None, Some(number)) => Some(number.to_string()),
+            (None, None) => None,
+        };
+        match (amount, currency) {
+            (Some(amount), Some(currency)) => components.push(format!("{} {}", amount, currency)),
+            (Some(amount), None) => components.push(amount),
+            (None, Some(currency)) => components.push(currency.to_string()),
+            (None, None) => {}
         }
-
         if let Some(date) = &cost.date {
-            if !first {
-                write!(w, ", ")?;
-            }
-            write!(w, "{}", date)?;
-            first = false;
+            components.push(date.to_string());
         }
-
         if let Some(label) = &cost.label {
-            if !first {
-                write!(w, ", ")?;
-            }
-            write!(w, "{}", label)?;
+            components.push(quote(label));
+        }
+        if cost.merge_cost {
+            components.push("*".to_string());
         }
 
-        if double_brackets {
-            write!(w, "}}}}")?;
+        if total_only {
+            write!(w, "{{{{{}}}}}", components.join(", "))
         } else {
-            write!(w, "}}")?;
+            write!(w, "{{{}}}", components.join(", "))
         }
-        Ok(())
     }
 }
 
@@ -421,3 +654,134 @@ impl<'a, W: Write> Renderer<&'a IncompleteAmount, W> for BasicRenderer {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn posting(account: &str, num: Option<i64>, currency: Option<&str>) -> Posting {
+        Posting::builder()
+            .account(account.parse().unwrap())
+            .units(IncompleteAmount {
+                num: num.map(|n| Decimal::new(n, 2)),
+                currency: currency.map(str::to_owned),
+            })
+            .build()
+    }
+
+    fn transaction() -> Transaction {
+        let mut meta = Meta::new();
+        meta.insert("note".to_owned(), MetaValue::Text("Say \"hi\"".to_owned()));
+        Transaction::builder()
+            .date("2014-05-05".parse().unwrap())
+            .payee(Some("Cafe".to_owned()))
+            .narration("Dinner".to_owned())
+            .meta(meta)
+            .postings(vec![
+                posting("Expenses:Food", Some(3745), Some("USD")),
+                posting("Assets:Cash", Some(-100000), Some("USD")),
+                posting("Equity:Rounding", None, None),
+            ])
+            .build()
+    }
+
+    #[test]
+    fn display_values() {
+        let amount = Amount {
+            num: Decimal::new(-1050, 2),
+            currency: "USD".to_owned(),
+        };
+        assert_eq!(amount.to_string(), "-10.50 USD");
+        assert_eq!(MetaValue::Bool(true).to_string(), "TRUE");
+        assert_eq!(MetaValue::Tag("trip".to_owned()).to_string(), "#trip");
+        assert_eq!(MetaValue::Text("a\\b".to_owned()).to_string(), r#""a\\b""#);
+        assert_eq!(
+            PriceSpec::Total(amount.clone().into()).to_string(),
+            "@@ -10.50 USD"
+        );
+        let cost = CostSpec::builder()
+            .number_per(Some(Decimal::new(502, 0)))
+            .currency(Some("USD".to_owned()))
+            .label(Some("lot".to_owned()))
+            .build();
+        assert_eq!(cost.to_string(), r#"{502 USD, "lot"}"#);
+        assert_eq!(format!("{:#}", amount), amount.to_string());
+    }
+
+    #[test]
+    fn display_transaction() {
+        let txn = transaction();
+        assert_eq!(
+            txn.to_string(),
+            "2014-05-05 * \"Cafe\" \"Dinner\"\n\
+             \tnote: \"Say \\\"hi\\\"\"\n\
+             \tExpenses:Food\t37.45 USD\n\
+             \tAssets:Cash\t-1000.00 USD\n\
+             \tEquity:Rounding\n"
+        );
+        assert_eq!(
+            format!("{:#}", txn),
+            "2014-05-05 * \"Cafe\" \"Dinner\"\n  \
+             note: \"Say \\\"hi\\\"\"\n  \
+             Expenses:Food       37.45 USD\n  \
+             Assets:Cash      -1000.00 USD\n  \
+             Equity:Rounding\n"
+        );
+        assert_eq!(
+            Directive::Transaction(txn.clone()).to_string(),
+            txn.to_string()
+        );
+    }
+
+    #[test]
+    fn display_ledger() {
+        let option = BcOption::builder()
+            .name("name_assets".to_owned())
+            .val("Activa".to_owned())
+            .build();
+        let ledger = Ledger {
+            directives: vec![
+                Directive::Option(option),
+                Directive::Transaction(transaction()),
+            ],
+        };
+        let rendered = ledger.to_string();
+        assert!(rendered.starts_with("option \"name_assets\" \"Activa\"\n\n"));
+        assert!(rendered.contains("\tActiva:Cash\t-1000.00 USD\n"));
+        assert!(format!("{:#}", ledger).contains("  Activa:Cash      -1000.00 USD\n"));
+    }
+
+    #[test]
+    fn custom_negative_numbers() {
+        let custom = Custom::builder()
+            .date("2014-07-09".parse().unwrap())
+            .name("budget".to_owned())
+            .args(vec![
+                MetaValue::Number(Decimal::new(1, 0)),
+                MetaValue::Number(Decimal::new(-2, 0)),
+                MetaValue::Amount(Amount {
+                    num: Decimal::new(-350, 2),
+                    currency: "USD".to_owned(),
+                }),
+                MetaValue::Number(Decimal::new(4, 0)),
+                MetaValue::Bool(false),
+            ])
+            .build();
+        assert_eq!(
+            custom.to_string(),
+            "2014-07-09 custom \"budget\" 1 (-2) (-3.50) USD 4 FALSE\n"
+        );
+    }
+
+    #[test]
+    fn unsupported_directives_are_skipped() {
+        let ledger = Ledger {
+            directives: vec![Directive::Unsupported, Directive::Unsupported],
+        };
+        let mut rendered = Vec::new();
+        render(&mut rendered, &ledger).unwrap();
+        assert!(rendered.is_empty());
+        assert_eq!(ledger.to_string(), "");
+        assert_eq!(format!("{:#}", Directive::Unsupported), "");
+    }
+}
@@ -0,0 +1,145 @@
+//! Serialization of the ledger model with [serde](https://serde.rs), enabled by the `serde`
+//! feature.
+//!
+//! [Ledger](../struct.Ledger.html), [Directive](../enum.Directive.html) and every type nested in
+//! them implement `Serialize` and `Deserialize`. The shape of the serialized data, shown here as
+//! JSON, is part of the public API of the crate:
+//!
+//! - Structs are objects with the fields of the struct, named as in Rust. Optional fields are
+//!   always present, with `null` for `None`.
+//! - A directive is the object of its struct, tagged with a `type` field holding the name of the
+//!   variant in snake case: `"open"`, `"option"`, `"transaction"`, ..., or `"unsupported"` for an
+//!   `Unsupported` directive, which has no other field.
+//! - Numbers are strings holding the exact decimal number, e.g. `"-37.450"`, so that no precision
+//!   is lost to floating point numbers. Deserializing a JSON number instead of a string fails.
+//! - Dates are ISO 8601 strings, e.g. `"2014-05-05"`.
+//! - Accounts are their colon-separated names with the default root names, e.g.
+//!   `"Assets:Cash"`, even if the ledger renames its root accounts with `name_*` options.
+//! - Flags are their character, e.g. `"*"`, `"!"` or `"P"`.
+//! - Tags and links are arrays of names without `#` or `^`, sorted by name.
+//! - Metadata is an object mapping keys to values tagged with their type: `{"type": "text",
+//!   "value": "..."}`, with the types `text`, `account`, `date`, `currency`, `tag`, `bool`,
+//!   `amount` and `number`.
+//! - Prices are amounts tagged with `"type": "per_unit"` or `"type": "total"`.
+//! - Booking methods are their Beancount names, e.g. `"STRICT"` or `"FIFO"`.
+//! - Locations hold the byte range of the item as an object `{"start": 0, "end": 25}`.
+//!
+//! # Example
+//! ```rust
+//! use beancount_core::metadata::MetaValue;
+//! use beancount_core::{Amount, Directive, Ledger, Posting, Transaction};
+//! use serde_json::json;
+//!
+//! let posting = Posting::builder()
+//!     .account("Expenses:Food".parse().unwrap())
+//!     .units(Amount::builder().num("37.45".parse().unwrap()).currency("USD".into()).build().into())
+//!     .build();
+//! let txn = Transaction::builder()
+//!     .date("2014-05-05".parse().unwrap())
+//!     .narration("Lamb tagine".into())
+//!     .tags(["trip".to_owned()].into())
+//!     .meta([("receipt".to_owned(), MetaValue::Text("r-1.pdf".into()))].into())
+//!     .postings(vec![posting])
+//!     .build();
+//! let ledger = Ledger::builder().directives(vec![Directive::Transaction(txn)]).build();
+//!
+//! let value = serde_json::to_value(&ledger).unwrap();
+//! assert_eq!(
+//!     value,
+//!     json!({
+//!         "directives": [{
+//!             "type": "transaction",
+//!             "date": "2014-05-05",
+//!             "flag": "*",
+//!             "payee": null,
+//!             "narration": "Lamb tagine",
+//!             "tags": ["trip"],
+//!             "links": [],
+//!             "postings": [{
+//!                 "account": "Expenses:Food",
+//!                 "units": {"num": "37.45", "currency": "USD"},
+//!                 "cost": null,
+//!                 "price": null,
+//!                 "flag": null,
+//!                 "meta": {},
+//!                 "location": null
+//!             }],
+//!             "meta": {"receipt": {"type": "text", "value": "r-1.pdf"}},
+//!             "source": null,
+//!             "location": null
+//!         }]
+//!     })
+//! );
+//! assert_eq!(serde_json::from_value::<Ledger>(value).unwrap(), ledger);
+//! ```
+
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{Account, Date, Flag};
+
+/// Serialize a set as an array sorted by value, so that the output does not depend on the hash
+/// order of the set.
+pub(crate) fn sorted<T: Ord + Serialize, S: Serializer>(
+    set: &HashSet<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut values: Vec<_> = set.iter().collect();
+    values.sort();
+    values.serialize(serializer)
+}
+
+fn from_str<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(D::Error::custom)
+}
+
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Date {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        from_str(deserializer)
+    }
+}
+
+impl Serialize for Account {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Account {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        from_str(deserializer)
+    }
+}
+
+impl Serialize for Flag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Flag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let flag = String::deserialize(deserializer)?;
+        if flag.is_empty() {
+            return Err(D::Error::custom("empty flag"));
+        }
+        Ok(Flag::from(flag.as_str()))
+    }
+}
@@ -0,0 +1,369 @@
+//! A lossless concrete syntax tree of a Beancount file.
+//!
+//! The [Cst](struct.Cst.html) of a file is a list of top-level nodes, each holding the exact text
+//! it was parsed from: directives together with the indented lines of their postings and
+//! metadata, comment lines, blank lines, org-mode headings and the `pushtag`/`pushmeta` family
+//! of lines. Writing the nodes back in order reproduces the input byte for byte, so tools can
+//! edit some directives of a file without reformatting the rest of it.
+//!
+//! # Example
+//! ```rust
+//! use beancount_parser::cst;
+//!
+//! let input = "* Accounts\n\n2014-05-01 open Assets:Cash   ; wallet\n";
+//! let mut tree = cst::parse(input).unwrap();
+//! assert_eq!(tree.to_string(), input);
+//!
+//! let mut open = tree.nodes()[2].directive().unwrap().clone();
+//! if let beancount_core::Directive::Open(ref mut open) = open {
+//!     open.currencies.push("EUR".into());
+//! }
+//! tree.set_directive(2, open);
+//! assert_eq!(
+//!     tree.to_string(),
+//!     "* Accounts\n\n2014-05-01 open Assets:Cash EUR   ; wallet\n"
+//! );
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+
+use pest::Parser;
+
+use beancount_core as bc;
+
+use super::error::{ParseError, ParseResult};
+use super::{directives, BeancountParser, Rule};
+
+/// The lossless syntax tree of a file: its top-level nodes, in order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Cst {
+    nodes: Vec<Node>,
+}
+
+/// A top-level element of a file, with its source text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    kind: NodeKind,
+    text: String,
+}
+
+/// The type of a [Node](struct.Node.html).
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeKind {
+    /// A directive, including the indented lines of its postings, metadata and comments.
+    Directive(Box<bc::Directive>),
+
+    /// A `pushtag`, `poptag`, `pushmeta` or `popmeta` line, which changes the tags or metadata of
+    /// the directives after it.
+    Pragma,
+
+    /// An org-mode heading, i.e. a line starting with `*`.
+    OrgModeTitle,
+
+    /// A line holding only a comment, possibly indented.
+    Comment,
+
+    /// A line holding only whitespace, or nothing at all.
+    Blank,
+}
+
+/// A line of the text of a node, see [Node::lines](struct.Node.html#method.lines).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Line<'a> {
+    text: &'a str,
+    code_end: usize,
+    comment: Option<usize>,
+    eol: usize,
+}
+
+/// Parse a file into its lossless syntax tree. The directives of the tree are those returned by
+/// [parse](../fn.parse.html), without the `Unsupported` placeholders of org-mode headings.
+pub fn parse(input: &str) -> ParseResult<Cst> {
+    let parsed = BeancountParser::parse(Rule::file, input)?
+        .next()
+        .ok_or_else(|| ParseError::invalid_state("non-empty parse result"))?;
+    let mut by_start: HashMap<usize, bc::Directive> = directives(parsed.clone(), 0, Err)?
+        .into_iter()
+        .filter_map(|directive| Some((directive.location()?.span.start, directive)))
+        .collect();
+
+    let mut nodes = Vec::new();
+    let mut offset = 0;
+    for pair in parsed.into_inner() {
+        let span = pair.as_span();
+        let kind = match pair.as_rule() {
+            Rule::EOI => continue,
+            Rule::org_mode_title => NodeKind::OrgModeTitle,
+            Rule::pushtag | Rule::poptag | Rule::pushmeta | Rule::popmeta => NodeKind::Pragma,
+            _ => match by_start.remove(&span.start()) {
+                Some(directive) => NodeKind::Directive(Box::new(directive)),
+                None => return Err(ParseError::invalid_state_with_span("directive", span)),
+            },
+        };
+        let indent = push_trivia(&mut nodes, &input[offset..span.start()]);
+        nodes.push(Node {
+            kind,
+            text: format!("{}{}", indent, span.as_str()),
+        });
+        offset = span.end();
+    }
+    let rest = push_trivia(&mut nodes, &input[offset..]);
+    if !rest.is_empty() {
+        nodes.push(Node::trivia(rest));
+    }
+    Ok(Cst { nodes })
+}
+
+/// Add a node for every complete line of `text`, which holds no directive. Returns the
+/// incomplete last line, i.e. the whitespace indenting the node after `text`.
+fn push_trivia<'a>(nodes: &mut Vec<Node>, text: &'a str) -> &'a str {
+    let mut lines = text.split_inclusive('\n').peekable();
+    while let Some(line) = lines.next() {
+        if lines.peek().is_none() && !line.ends_with('\n') {
+            return line;
+        }
+        nodes.push(Node::trivia(line));
+    }
+    ""
+}
+
+impl Cst {
+    /// The nodes of the file, in order.
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// The directives of the file, in order.
+    pub fn directives(&self) -> impl Iterator<Item = &bc::Directive> {
+        self.nodes.iter().filter_map(Node::directive)
+    }
+
+    /// Convert the tree to a ledger of its directives.
+    pub fn to_ledger(&self) -> bc::Ledger {
+        bc::Ledger::builder()
+            .directives(self.directives().cloned().collect())
+            .build()
+    }
+
+    /// Insert a node at position `index`, shifting the nodes after it.
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the number of nodes.
+    pub fn insert(&mut self, index: usize, node: Node) {
+        self.nodes.insert(index, node);
+    }
+
+    /// Remove the node at position `index`, shifting the nodes after it.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Node {
+        self.nodes.remove(index)
+    }
+
+    /// Replace the node at position `index` with a directive, rendered with the
+    /// [BasicRenderer](../../beancount_core/render/struct.BasicRenderer.html).
+    ///
+    /// If the node was a directive, its comments are kept: the trailing comments of its first
+    /// line, of its postings (matched by account) and of its metadata (matched by key) are put
+    /// back on the same lines of the new text, and each comment line is put back after the line
+    /// it followed, or at the end if that line is gone. Tags pushed with `pushtag` become explicit
+    /// tags if they are part of the new directive.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn set_directive(&mut self, index: usize, directive: bc::Directive) {
+        let node = &mut self.nodes[index];
+        let rendered = Node::from_directive(directive);
+        node.text = match node.kind {
+            NodeKind::Directive(_) => keep_comments(node, &rendered.text),
+            _ => rendered.text,
+        };
+        node.kind = rendered.kind;
+    }
+}
+
+impl fmt::Display for Cst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for node in &self.nodes {
+            f.write_str(&node.text)?;
+        }
+        Ok(())
+    }
+}
+
+impl Node {
+    /// A directive node, rendered with the
+    /// [BasicRenderer](../../beancount_core/render/struct.BasicRenderer.html).
+    pub fn from_directive(directive: bc::Directive) -> Self {
+        Node {
+            text: bc::render::to_journal_string(&directive),
+            kind: NodeKind::Directive(Box::new(directive)),
+        }
+    }
+
+    /// A node for a line outside of any directive.
+    fn trivia(text: &str) -> Self {
+        let kind = if text.trim().is_empty() {
+            NodeKind::Blank
+        } else {
+            NodeKind::Comment
+        };
+        Node {
+            kind,
+            text: text.to_owned(),
+        }
+    }
+
+    /// A line holding only a comment, e.g. `Node::comment("; Bank accounts")`.
+    pub fn comment(text: &str) -> Self {
+        Node {
+            kind: NodeKind::Comment,
+            text: with_eol(text),
+        }
+    }
+
+    /// A blank line.
+    pub fn blank() -> Self {
+        Node {
+            kind: NodeKind::Blank,
+            text: "\n".to_owned(),
+        }
+    }
+
+    pub fn kind(&self) -> &NodeKind {
+        &self.kind
+    }
+
+    /// The directive of a directive node.
+    pub fn directive(&self) -> Option<&bc::Directive> {
+        match &self.kind {
+            NodeKind::Directive(directive) => Some(directive),
+            _ => None,
+        }
+    }
+
+    /// The source text of the node, including its line endings.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The lines of the text of the node.
+    pub fn lines(&self) -> impl Iterator<Item = Line<'_>> {
+        self.text.split_inclusive('\n').map(Line::new)
+    }
+}
+
+impl<'a> Line<'a> {
+    fn new(text: &'a str) -> Self {
+        let eol = text.trim_end_matches(['\r', '\n']).len();
+        let mut comment = None;
+        let mut in_string = false;
+        let mut escaped = false;
+        for (i, c) in text[..eol].char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' if in_string => escaped = true,
+                '"' => in_string = !in_string,
+                ';' if !in_string => {
+                    comment = Some(i);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let code_end = text[..comment.unwrap_or(eol)].trim_end().len();
+        Line {
+            text,
+            code_end,
+            comment,
+            eol,
+        }
+    }
+
+    /// The whole line, including its line ending.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// The line without its comment, trailing whitespace and line ending.
+    pub fn code(&self) -> &'a str {
+        &self.text[..self.code_end]
+    }
+
+    /// The comment at the end of the line, starting with `;`, if any.
+    pub fn comment(&self) -> Option<&'a str> {
+        self.comment.map(|start| &self.text[start..self.eol])
+    }
+
+    /// The line ending, empty for the last line of a file without a final newline.
+    pub fn eol(&self) -> &'a str {
+        &self.text[self.eol..]
+    }
+
+    /// Identifies the line within its directive: the first line, a posting by its account or a
+    /// metadata entry by its key.
+    fn key(&self, first: bool) -> &'a str {
+        if first {
+            return "";
+        }
+        let code = self.code().trim();
+        code.split_whitespace()
+            .find(|token| token.contains(':'))
+            .unwrap_or(code)
+    }
+}
+
+/// The text of a directive `rendered` in place of the directive node `old`, with the comments of
+/// `old` put back, see [Cst::set_directive](struct.Cst.html#method.set_directive).
+fn keep_comments(old: &Node, rendered: &str) -> String {
+    let mut trailing: HashMap<(&str, usize), &str> = HashMap::new();
+    let mut following: Vec<((&str, usize), Vec<&str>)> = Vec::new();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (i, line) in old.lines().enumerate() {
+        if i > 0 && line.code().trim().is_empty() {
+            if let Some((_, lines)) = following.last_mut() {
+                lines.push(line.text());
+            }
+            continue;
+        }
+        let key = line.key(i == 0);
+        let count = counts.entry(key).or_insert(0);
+        *count += 1;
+        if line.comment().is_some() {
+            trailing.insert((key, *count), &line.text()[line.code_end..line.eol]);
+        }
+        following.push(((key, *count), Vec::new()));
+    }
+
+    let mut text = String::new();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (i, line) in rendered.split_inclusive('\n').map(Line::new).enumerate() {
+        let key = line.key(i == 0);
+        let count = counts.entry(key).or_insert(0);
+        *count += 1;
+        text.push_str(line.code());
+        if let Some(comment) = trailing.get(&(key, *count)) {
+            text.push_str(comment);
+        }
+        text.push_str(line.eol());
+        if let Some(position) = following.iter().position(|(k, _)| *k == (key, *count)) {
+            for comment_line in following.remove(position).1 {
+                text.push_str(&with_eol(comment_line));
+            }
+        }
+    }
+    for comment_line in following.into_iter().flat_map(|(_, lines)| lines) {
+        text.push_str(&with_eol(comment_line));
+    }
+    text
+}
+
+fn with_eol(text: &str) -> String {
+    if text.ends_with('\n') {
+        text.to_owned()
+    } else {
+        format!("{}\n", text)
+    }
+}
@@ -86,7 +86,7 @@ impl ParseError {
         let message = format!("error while parsing number: {}", err);
         let pest_error = pest::error::Error::new_from_span(
             pest::error::ErrorVariant::<Rule>::CustomError { message },
-            span.clone(),
+            span,
         );
         ParseError {
             kind: ParseErrorKind::DecimalError {
@@ -167,6 +167,8 @@ impl From<pest::error::Error<Rule>> for ParseError {
                 Rule::query => "query directive",
                 Rule::pushtag => "pushtag",
                 Rule::poptag => "poptag",
+                Rule::pushmeta => "pushmeta",
+                Rule::popmeta => "popmeta",
                 Rule::transaction => "transaction directive",
                 Rule::txn_flag => "transaction flag",
                 Rule::flag_okay => "'txn' or '*'",
@@ -0,0 +1,412 @@
+//! Formatting of Beancount files in the style of `bean-format`.
+//!
+//! The postings of every transaction are laid out in columns: the accounts are padded to a common
+//! width and the amounts are aligned on their decimal point, or so that their currencies start at
+//! a fixed column. The spacing of costs and prices is normalized, and the lines of transactions
+//! are indented consistently. Everything else, including comments, is kept as written.
+//!
+//! # Example
+//! ```rust
+//! use beancount_parser::format::{format, FormatOptions};
+//!
+//! let input = "2014-05-05 * \"Cafe\"\n  Expenses:Food   37.5 USD  ; lunch\n  Assets:Cash  -137.45 USD\n";
+//! let formatted = format(input, &FormatOptions::default()).unwrap();
+//! assert_eq!(
+//!     formatted,
+//!     "2014-05-05 * \"Cafe\"\n  Expenses:Food    37.5  USD  ; lunch\n  Assets:Cash    -137.45 USD\n"
+//! );
+//! ```
+
+use beancount_core as bc;
+
+use super::cst::{self, Line, Node};
+use super::error::ParseResult;
+
+/// How the amounts of postings are aligned.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub enum Alignment {
+    /// Align the decimal points of the numbers, after the widest account.
+    #[default]
+    DecimalPoint,
+
+    /// Right-align the numbers so that their currencies start after the given number of
+    /// characters, like the `--currency-column` option of `bean-format`. Lines whose account is
+    /// too long are laid out as if their account was as wide as needed.
+    CurrencyColumn(usize),
+}
+
+/// The layout of formatted files.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FormatOptions {
+    /// How the amounts of postings are aligned.
+    pub alignment: Alignment,
+
+    /// The number of spaces indenting the postings, metadata and comments of a transaction. The
+    /// metadata of postings is indented twice as much.
+    pub indent: usize,
+
+    /// The minimum width of the indentation, flag and account of postings. The widest of these
+    /// is used by default.
+    pub prefix_width: Option<usize>,
+
+    /// The minimum width of the numbers of postings. The widest number is used by default.
+    pub num_width: Option<usize>,
+
+    /// Whether the integer part of the numbers of postings is written with a comma every three
+    /// digits. When `false`, separators are removed. Numbers given as arithmetic expressions are
+    /// kept as written.
+    pub thousands_separator: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            alignment: Alignment::DecimalPoint,
+            indent: 2,
+            prefix_width: None,
+            num_width: None,
+            thousands_separator: false,
+        }
+    }
+}
+
+/// Format a file, see the [module documentation](index.html).
+pub fn format(input: &str, options: &FormatOptions) -> ParseResult<String> {
+    let tree = cst::parse(input)?;
+    let indent = " ".repeat(options.indent);
+    let nodes: Vec<_> = tree
+        .nodes()
+        .iter()
+        .map(|node| NodeLayout::new(node, &indent, options))
+        .collect();
+
+    let postings = nodes
+        .iter()
+        .flat_map(|node| &node.lines)
+        .filter_map(|line| match line {
+            LineLayout::Posting { posting, .. } => Some(posting),
+            LineLayout::Text(_) => None,
+        });
+    let mut widths = Widths::default();
+    for posting in postings.filter(|posting| posting.number.is_some()) {
+        widths.update(posting);
+    }
+    widths.prefix = widths.prefix.max(options.prefix_width.unwrap_or(0));
+    if let Some(num_width) = options.num_width {
+        widths.int = widths.int.max(num_width.saturating_sub(widths.fraction()));
+    }
+
+    let mut formatted = String::with_capacity(input.len());
+    for node in nodes {
+        for line in node.lines {
+            match line {
+                LineLayout::Text(text) => formatted.push_str(&text),
+                LineLayout::Posting {
+                    posting,
+                    comment,
+                    eol,
+                } => {
+                    let mut text = posting.layout(&widths, options.alignment);
+                    if let Some(comment) = comment {
+                        text.push_str("  ");
+                        text.push_str(comment);
+                    }
+                    formatted.push_str(&text);
+                    formatted.push_str(eol);
+                }
+            }
+        }
+    }
+    Ok(formatted)
+}
+
+/// The lines of a node, with its posting lines split into columns.
+struct NodeLayout<'a> {
+    lines: Vec<LineLayout<'a>>,
+}
+
+enum LineLayout<'a> {
+    /// A line written as is.
+    Text(String),
+
+    /// A posting line, laid out once the widths of all postings are known.
+    Posting {
+        posting: PostingColumns,
+        comment: Option<&'a str>,
+        eol: &'a str,
+    },
+}
+
+impl<'a> NodeLayout<'a> {
+    fn new(node: &'a Node, indent: &str, options: &FormatOptions) -> Self {
+        let txn = match node.directive() {
+            Some(bc::Directive::Transaction(txn)) => txn,
+            _ => {
+                return NodeLayout {
+                    lines: vec![LineLayout::Text(node.text().to_owned())],
+                }
+            }
+        };
+        let first_line = txn.location.as_ref().map_or(0, |location| location.line);
+        let posting_lines: Vec<_> = txn
+            .postings
+            .iter()
+            .map(|posting| {
+                let location = posting.location.as_ref()?;
+                Some((location.line.checked_sub(first_line)?, posting))
+            })
+            .collect::<Option<_>>()
+            .unwrap_or_default();
+
+        let mut in_posting = false;
+        let lines = node
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                if i == 0 {
+                    return LineLayout::Text(line.text().to_owned());
+                }
+                match posting_lines.iter().find(|(n, _)| *n == i) {
+                    Some((_, posting)) => {
+                        in_posting = true;
+                        LineLayout::Posting {
+                            posting: PostingColumns::new(posting, &line, indent, options),
+                            comment: line.comment(),
+                            eol: line.eol(),
+                        }
+                    }
+                    None => {
+                        let is_meta = line
+                            .code()
+                            .trim_start()
+                            .starts_with(|c: char| c.is_ascii_lowercase());
+                        let depth = if in_posting && is_meta { 2 } else { 1 };
+                        LineLayout::Text(reindent(&line, &indent.repeat(depth)))
+                    }
+                }
+            })
+            .collect();
+        NodeLayout { lines }
+    }
+}
+
+fn reindent(line: &Line<'_>, indent: &str) -> String {
+    let content = line.text().trim_start_matches([' ', '\t']);
+    format!("{}{}", indent, content)
+}
+
+/// A posting line split into columns.
+struct PostingColumns {
+    /// The indentation, flag and account.
+    prefix: String,
+
+    /// The number of the units, as the text before and after its decimal point.
+    number: Option<(String, Option<String>)>,
+
+    /// The currency of the units, the cost and the price.
+    rest: String,
+}
+
+impl PostingColumns {
+    fn new(posting: &bc::Posting, line: &Line<'_>, indent: &str, options: &FormatOptions) -> Self {
+        let code = line.code().trim();
+        let account_start = code.find(':').map_or(0, |colon| {
+            code[..colon]
+                .rfind(|c: char| c.is_whitespace() || !(c.is_alphanumeric() || c == '-'))
+                .map_or(0, |i| i + 1)
+        });
+        let account_end = code[account_start..]
+            .find(char::is_whitespace)
+            .map_or(code.len(), |i| account_start + i);
+        let mut prefix = indent.to_owned();
+        if let Some(flag) = &posting.flag {
+            prefix.push_str(&format!("{} ", flag));
+        }
+        prefix.push_str(&code[account_start..account_end]);
+
+        let remainder = code[account_end..].trim();
+        let (number, rest) = match (&posting.units.num, &posting.units.currency) {
+            (None, _) => (None, remainder),
+            (Some(_), Some(_)) => {
+                let end = token_starts(remainder)
+                    .find(|&i| remainder[i..].starts_with(|c: char| c.is_ascii_uppercase()))
+                    .unwrap_or(remainder.len());
+                (Some(remainder[..end].trim()), &remainder[end..])
+            }
+            (Some(_), None) => {
+                let end = remainder.find(['{', '@']).unwrap_or(remainder.len());
+                (Some(remainder[..end].trim()), &remainder[end..])
+            }
+        };
+        PostingColumns {
+            prefix,
+            number: number.map(|number| split_number(number, options.thousands_separator)),
+            rest: normalize_spacing(rest),
+        }
+    }
+
+    /// The posting laid out with the given column widths.
+    fn layout(&self, widths: &Widths, alignment: Alignment) -> String {
+        let mut text = self.prefix.clone();
+        let (int, fraction) = match &self.number {
+            Some(number) => number,
+            None => {
+                if !self.rest.is_empty() {
+                    text.push_str("  ");
+                    text.push_str(&self.rest);
+                }
+                return text;
+            }
+        };
+        let mut number = int.clone();
+        if let Some(fraction) = fraction {
+            number.push('.');
+            number.push_str(fraction);
+        }
+        let padding = match alignment {
+            Alignment::DecimalPoint => {
+                let prefix = widths.prefix - width(&self.prefix) + 2;
+                let int = widths.int - width(int);
+                let number_width = width(&number);
+                number.push_str(&" ".repeat(widths.int + widths.fraction() - int - number_width));
+                prefix + int
+            }
+            Alignment::CurrencyColumn(column) => column
+                .saturating_sub(width(&self.prefix) + width(&number) + 1)
+                .max(2),
+        };
+        text.push_str(&" ".repeat(padding));
+        text.push_str(&number);
+        if !self.rest.is_empty() {
+            text.push(' ');
+            text.push_str(&self.rest);
+        }
+        text.truncate(text.trim_end().len());
+        text
+    }
+}
+
+/// The widest prefix, integer part and fractional part of the numbers of postings.
+#[derive(Default)]
+struct Widths {
+    prefix: usize,
+    int: usize,
+    fraction: Option<usize>,
+}
+
+impl Widths {
+    fn update(&mut self, posting: &PostingColumns) {
+        self.prefix = self.prefix.max(width(&posting.prefix));
+        if let Some((int, fraction)) = &posting.number {
+            self.int = self.int.max(width(int));
+            if let Some(fraction) = fraction {
+                self.fraction = Some(self.fraction.unwrap_or(0).max(width(fraction)));
+            }
+        }
+    }
+
+    /// The width of the decimal point and the digits after it.
+    fn fraction(&self) -> usize {
+        self.fraction.map_or(0, |fraction| fraction + 1)
+    }
+}
+
+fn width(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// The byte offsets of the whitespace-separated tokens of `text`.
+fn token_starts(text: &str) -> impl Iterator<Item = usize> + '_ {
+    text.char_indices()
+        .filter(move |&(i, c)| {
+            !c.is_whitespace()
+                && text[..i]
+                    .chars()
+                    .next_back()
+                    .is_none_or(char::is_whitespace)
+        })
+        .map(|(i, _)| i)
+}
+
+/// Split a number into the text before and after its decimal point, adding or removing thousands
+/// separators. Arithmetic expressions are kept whole, as an integer part.
+fn split_number(number: &str, thousands_separator: bool) -> (String, Option<String>) {
+    let is_plain = number
+        .strip_prefix(['-', '+'])
+        .unwrap_or(number)
+        .chars()
+        .all(|c| c.is_ascii_digit() || c == ',' || c == '.');
+    if !is_plain {
+        return (
+            number.split_whitespace().collect::<Vec<_>>().join(" "),
+            None,
+        );
+    }
+    let (int, fraction) = match number.split_once('.') {
+        Some((int, fraction)) => (int, Some(fraction.to_owned())),
+        None => (number, None),
+    };
+    let (sign, digits) = int.split_at(int.len() - int.trim_start_matches(['-', '+']).len());
+    let digits: String = digits.chars().filter(|&c| c != ',').collect();
+    let mut int = sign.to_owned();
+    for (i, digit) in digits.chars().enumerate() {
+        if thousands_separator && i > 0 && (digits.len() - i).is_multiple_of(3) {
+            int.push(',');
+        }
+        int.push(digit);
+    }
+    (int, fraction)
+}
+
+/// Normalize the spacing of the currency, cost and price of a posting: single spaces between
+/// tokens, none inside braces or before commas, and spaces around `@` and `@@`.
+fn normalize_spacing(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    let mut chars = text.trim().chars().peekable();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut space = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            normalized.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            c if c.is_whitespace() => {
+                space = true;
+                continue;
+            }
+            '}' | ',' => {}
+            '{' | '@' => {
+                if !normalized.is_empty() && !normalized.ends_with(['{', '@']) {
+                    normalized.push(' ');
+                }
+            }
+            _ => {
+                let after_open = normalized.ends_with('{');
+                let after_at = normalized.ends_with('@');
+                if after_at || (space && !after_open && !normalized.is_empty()) {
+                    normalized.push(' ');
+                }
+            }
+        }
+        if c == '"' {
+            in_string = true;
+        }
+        normalized.push(c);
+        if c == ',' && chars.peek().is_some() {
+            normalized.push(' ');
+            while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                chars.next();
+            }
+        }
+        space = false;
+    }
+    normalized
+}
@@ -6,15 +6,19 @@ use std::str::FromStr;
 use lazy_static::lazy_static;
 use pest::iterators::{Pair, Pairs};
 use pest::pratt_parser::{Assoc, Op, PrattParser};
-use pest::Parser;
+use pest::{Parser, Span};
 use pest_derive::Parser as PestParser;
 use rust_decimal::Decimal;
 
+use bc::location::FileId;
 use beancount_core as bc;
 
 use error::{ParseError, ParseResult};
 
+pub mod cst;
 pub mod error;
+pub mod format;
+pub mod loader;
 
 macro_rules! construct {
     ( @fields, $builder:ident, $span:ident, $pairs:ident, ) => {};
@@ -93,27 +97,61 @@ pub struct BeancountParser;
 
 #[derive(Debug)]
 struct ParseState<'i> {
-    root_names: HashMap<bc::AccountType, String>,
+    root_names: bc::account_types::RootNames,
 
     // Track pushed tag count with HashMap<&str, u64> instead of only tracking
     // tags with HashSet<&str> because the spec allows pushing multiple of the
     // same tag, and conformance with bean-check requires an equal number of
     // pops.
     pushed_tags: HashMap<&'i str, u16>,
+
+    // Metadata pushed with pushmeta, as a stack of values per key: the most recently pushed value
+    // of a key is the one applied to directives.
+    pushed_meta: HashMap<String, Vec<bc::metadata::MetaValue>>,
+
+    // The file being parsed, recorded in the location of every directive.
+    file: FileId,
 }
 
 impl<'i> ParseState<'i> {
-    fn new() -> Self {
-        use bc::AccountType::*;
+    fn new(file: FileId) -> Self {
         ParseState {
-            root_names: [Assets, Liabilities, Equity, Income, Expenses]
-                .iter()
-                .map(|ty| (*ty, ty.default_name().to_string()))
-                .collect(),
+            root_names: Default::default(),
             pushed_tags: HashMap::new(),
+            pushed_meta: HashMap::new(),
+            file,
+        }
+    }
+
+    fn location(&self, span: Span<'_>) -> bc::Location {
+        let (line, column) = span.start_pos().line_col();
+        bc::Location {
+            file: self.file,
+            line,
+            column,
+            span: span.start()..span.end(),
+            meta: HashMap::new(),
         }
     }
 
+    /// The location of a directive, along with the locations of the metadata entries of its
+    /// key-value list.
+    fn directive_location(&self, directive: &Pair<'_, Rule>) -> bc::Location {
+        let mut location = self.location(directive.as_span());
+        let entries = directive
+            .clone()
+            .into_inner()
+            .filter(|p| p.as_rule() == Rule::eol_kv_list)
+            .flat_map(|p| p.into_inner());
+        for entry in entries {
+            if let Some(key) = entry.clone().into_inner().next() {
+                let key = key.as_str().to_owned();
+                location.meta.insert(key, self.location(entry.as_span()));
+            }
+        }
+        location
+    }
+
     fn push_tag(&mut self, tag: &'i str) {
         *self.pushed_tags.entry(tag).or_insert(0) += 1;
     }
@@ -135,6 +173,37 @@ impl<'i> ParseState<'i> {
     fn get_pushed_tags(&self) -> impl Iterator<Item = &&str> {
         self.pushed_tags.keys()
     }
+
+    fn push_meta(&mut self, key: String, value: bc::metadata::MetaValue) {
+        self.pushed_meta.entry(key).or_default().push(value);
+    }
+
+    fn pop_meta(&mut self, key: &str) -> Result<(), String> {
+        match self.pushed_meta.get_mut(key) {
+            Some(values) => {
+                values.pop();
+                if values.is_empty() {
+                    self.pushed_meta.remove(key);
+                }
+                Ok(())
+            }
+            _ => Err(format!("Attempting to pop absent metadata key: '{}'", key)),
+        }
+    }
+
+    fn get_pushed_meta(&self) -> impl Iterator<Item = (&String, &bc::metadata::MetaValue)> {
+        self.pushed_meta
+            .iter()
+            .filter_map(|(key, values)| Some((key, values.last()?)))
+    }
+
+    /// Add the pushed metadata to the metadata of a directive, unless the directive sets the same
+    /// key itself.
+    fn apply_pushed_meta(&self, meta: &mut bc::metadata::Meta) {
+        for (key, value) in self.get_pushed_meta() {
+            meta.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
 }
 
 fn optional_rule<'i>(rule: Rule, pairs: &mut Pairs<'i, Rule>) -> Option<Pair<'i, Rule>> {
@@ -144,16 +213,113 @@ fn optional_rule<'i>(rule: Rule, pairs: &mut Pairs<'i, Rule>) -> Option<Pair<'i,
     }
 }
 
-pub fn parse<'i>(input: &'i str) -> ParseResult<bc::Ledger> {
-    let parsed = BeancountParser::parse(Rule::file, &input)?
+pub fn parse(input: &str) -> ParseResult<bc::Ledger> {
+    parse_file(input, 0)
+}
+
+/// Parse the contents of the file `file`, which is recorded in the location of every directive.
+/// See [parse](fn.parse.html).
+pub fn parse_file(input: &str, file: FileId) -> ParseResult<bc::Ledger> {
+    let parsed = BeancountParser::parse(Rule::file, input)?
         .next()
         .ok_or_else(|| ParseError::invalid_state("non-empty parse result"))?;
+    let directives = directives(parsed, file, Err)?;
+    Ok(bc::Ledger::builder().directives(directives).build())
+}
 
-    let mut state = ParseState::new();
+/// Parse the input, recovering from errors instead of stopping at the first one.
+///
+/// A directive which cannot be parsed is skipped up to the next line starting at the first
+/// column, and the error is collected. The returned ledger holds every directive which could be
+/// parsed, and the errors are sorted by their location in the input.
+pub fn parse_recovering(input: &str) -> (bc::Ledger, Vec<ParseError>) {
+    parse_file_recovering(input, 0)
+}
+
+/// Parse the contents of the file `file`, recovering from errors. See
+/// [parse_recovering](fn.parse_recovering.html) and [parse_file](fn.parse_file.html).
+pub fn parse_file_recovering(input: &str, file: FileId) -> (bc::Ledger, Vec<ParseError>) {
+    let mut errors = Vec::new();
+    // Syntax errors are skipped by blanking out the offending directive and parsing again, which
+    // keeps the lines and byte offsets of the remaining directives intact.
+    let mut input = Cow::Borrowed(input);
+    let parsed = loop {
+        match BeancountParser::parse(Rule::file, &input) {
+            Ok(mut pairs) => break pairs.next(),
+            Err(err) => {
+                let err = ParseError::from(err);
+                let skipped = blank_directive_at(input.to_mut(), err.location.0);
+                errors.push(err);
+                if !skipped {
+                    break None;
+                }
+            }
+        }
+    };
+    let directives = match parsed {
+        Some(parsed) => directives(parsed, file, |err| {
+            errors.push(err);
+            Ok(())
+        })
+        .expect("errors are collected"),
+        None => Vec::new(),
+    };
+    errors.sort_by_key(|err| err.location);
+    (bc::Ledger::builder().directives(directives).build(), errors)
+}
+
+/// Replace the top-level directive containing the given (1-based) line with whitespace: from the
+/// last line at or before it which does not start with whitespace, up to the next such line.
+/// Returns whether anything was replaced.
+fn blank_directive_at(input: &mut String, line: usize) -> bool {
+    let starts_directive = |l: &str| !l.is_empty() && !l.starts_with([' ', '\t', '\r', '\n']);
+    let lines: Vec<(usize, &str)> = input
+        .split_inclusive('\n')
+        .scan(0, |offset, l| {
+            let start = *offset;
+            *offset += l.len();
+            Some((start, l))
+        })
+        .collect();
+    let last = match lines.len().checked_sub(1) {
+        Some(last) => last,
+        None => return false,
+    };
+    let line = line.saturating_sub(1).min(last);
+    let first = match (0..=line).rev().find(|&i| starts_directive(lines[i].1)) {
+        Some(first) => first,
+        None => return false,
+    };
+    let end = lines[first + 1..]
+        .iter()
+        .find(|(_, l)| starts_directive(l))
+        .map_or(input.len(), |(start, _)| *start);
+    let start = lines[first].0;
+    // Every character is replaced by as many spaces as it has bytes, so that the byte offsets of
+    // the following directives are unchanged.
+    let blanked: String = input[start..end]
+        .chars()
+        .map(|c| match c {
+            '\n' => "\n".to_owned(),
+            c => " ".repeat(c.len_utf8()),
+        })
+        .collect();
+    input.replace_range(start..end, &blanked);
+    true
+}
+
+/// Convert the parsed top-level pairs of a file into directives. Errors are passed to `on_error`,
+/// which either returns them to stop parsing or swallows them to skip the offending pair.
+fn directives<'i>(
+    parsed: Pair<'i, Rule>,
+    file: FileId,
+    mut on_error: impl FnMut(ParseError) -> ParseResult<()>,
+) -> ParseResult<Vec<bc::Directive>> {
+    let mut state = ParseState::new(file);
     let mut directives = Vec::new();
 
     for directive_pair in parsed.into_inner() {
-        match directive_pair.as_rule() {
+        let result = match directive_pair.as_rule() {
             Rule::EOI => {
                 let pushed_tags = state
                     .get_pushed_tags()
@@ -161,39 +327,72 @@ pub fn parse<'i>(input: &'i str) -> ParseResult<bc::Ledger> {
                     .collect::<Vec<String>>()
                     .join(", ");
                 if !pushed_tags.is_empty() {
-                    return Err(ParseError::invalid_input_with_span(
+                    on_error(ParseError::invalid_input_with_span(
                         format!("Unbalanced pushed tag(s): {}", pushed_tags),
                         directive_pair.as_span(),
-                    ));
+                    ))?;
+                }
+                let mut pushed_meta = state
+                    .get_pushed_meta()
+                    .map(|(key, _)| format!("'{}'", key))
+                    .collect::<Vec<String>>();
+                pushed_meta.sort();
+                if !pushed_meta.is_empty() {
+                    on_error(ParseError::invalid_input_with_span(
+                        format!("Unbalanced pushed metadata: {}", pushed_meta.join(", ")),
+                        directive_pair.as_span(),
+                    ))?;
                 }
                 break;
             }
-            Rule::pushtag => {
-                state.push_tag(extract_tag(directive_pair)?);
-            }
+            Rule::pushtag => extract_tag(directive_pair).map(|tag| state.push_tag(tag)),
             Rule::poptag => {
                 let span = directive_pair.as_span();
-                if let Err(msg) = state.pop_tag(extract_tag(directive_pair)?) {
-                    return Err(ParseError::invalid_input_with_span(msg, span));
-                }
+                extract_tag(directive_pair).and_then(|tag| {
+                    state
+                        .pop_tag(tag)
+                        .map_err(|msg| ParseError::invalid_input_with_span(msg, span))
+                })
             }
-            _ => {
-                let dir = directive(directive_pair, &state)?;
-
+            Rule::pushmeta => {
+                let span = directive_pair.as_span();
+                directive_pair
+                    .into_inner()
+                    .next()
+                    .ok_or_else(|| ParseError::invalid_state_with_span("metadata", span))
+                    .and_then(|p| meta_kv_pair(p, &state))
+                    .map(|(key, value)| state.push_meta(key, value))
+            }
+            Rule::popmeta => {
+                let span = directive_pair.as_span();
+                directive_pair
+                    .into_inner()
+                    .next()
+                    .ok_or_else(|| ParseError::invalid_state_with_span("metadata key", span))
+                    .and_then(|key| {
+                        state
+                            .pop_meta(key.as_str())
+                            .map_err(|msg| ParseError::invalid_input_with_span(msg, span))
+                    })
+            }
+            _ => directive(directive_pair, &state).map(|dir| {
                 // Change the root account names on such an option:
                 // option "name_assets" "Assets"
                 if let bc::Directive::Option(ref opt) = dir {
                     if let Some((account_type, account_name)) = opt.root_name_change() {
-                        state.root_names.insert(account_type, account_name);
+                        state.root_names.set(account_type, account_name);
                     }
                 }
 
                 directives.push(dir);
-            }
+            }),
+        };
+        if let Err(err) = result {
+            on_error(err)?;
         }
     }
 
-    Ok(bc::Ledger::builder().directives(directives).build())
+    Ok(directives)
 }
 
 fn extract_tag<'i>(pair: Pair<'i, Rule>) -> ParseResult<&'i str> {
@@ -206,10 +405,10 @@ fn extract_tag<'i>(pair: Pair<'i, Rule>) -> ParseResult<&'i str> {
 
 fn directive<'i>(directive: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::Directive> {
     let dir = match directive.as_rule() {
-        Rule::option => option_directive(directive)?,
-        Rule::plugin => plugin_directive(directive)?,
+        Rule::option => option_directive(directive, state)?,
+        Rule::plugin => plugin_directive(directive, state)?,
         Rule::custom => custom_directive(directive, state)?,
-        Rule::include => include_directive(directive)?,
+        Rule::include => include_directive(directive, state)?,
         Rule::open => open_directive(directive, state)?,
         Rule::close => close_directive(directive, state)?,
         Rule::commodity_directive => commodity_directive(directive, state)?,
@@ -226,24 +425,34 @@ fn directive<'i>(directive: Pair<'i, Rule>, state: &ParseState) -> ParseResult<b
     Ok(dir)
 }
 
-fn option_directive<'i>(directive: Pair<'i, Rule>) -> ParseResult<bc::Directive> {
+fn option_directive<'i>(
+    directive: Pair<'i, Rule>,
+    state: &ParseState,
+) -> ParseResult<bc::Directive> {
     let source = directive.as_str();
+    let location = state.directive_location(&directive);
     Ok(bc::Directive::Option(construct! {
         bc::BcOption: directive => {
             name = get_quoted_str;
             val = get_quoted_str;
             source := Some(source.to_owned());
+            location := Some(location);
         }
     }))
 }
 
-fn plugin_directive<'i>(directive: Pair<'i, Rule>) -> ParseResult<bc::Directive> {
+fn plugin_directive<'i>(
+    directive: Pair<'i, Rule>,
+    state: &ParseState,
+) -> ParseResult<bc::Directive> {
     let source = directive.as_str();
+    let location = state.directive_location(&directive);
     Ok(bc::Directive::Plugin(construct! {
         bc::Plugin: directive => {
             module = get_quoted_str;
             config ?= get_quoted_str;
             source := Some(source.to_owned());
+            location := Some(location);
         }
     }))
 }
@@ -253,29 +462,36 @@ fn custom_directive<'i>(
     state: &ParseState,
 ) -> ParseResult<bc::Directive> {
     let source = directive.as_str();
+    let location = state.directive_location(&directive);
     Ok(bc::Directive::Custom(construct! {
         bc::Custom: directive => {
             date = date;
             name = get_quoted_str;
             args = if Rule::custom_value_list {
-                |p: Pair<'i, _>| -> ParseResult<Vec<String>> {
-                    p.into_inner().map(get_quoted_str).collect()
+                |p: Pair<'i, _>| -> ParseResult<Vec<_>> {
+                    p.into_inner().map(|p| meta_value(p, state)).collect()
                 }
             } else {
                 Vec::new()
             };
             meta = |p| meta_kv(p, state);
             source := Some(source.to_owned());
+            location := Some(location);
         }
     }))
 }
 
-fn include_directive<'i>(directive: Pair<'i, Rule>) -> ParseResult<bc::Directive> {
+fn include_directive<'i>(
+    directive: Pair<'i, Rule>,
+    state: &ParseState,
+) -> ParseResult<bc::Directive> {
     let source = directive.as_str();
+    let location = state.directive_location(&directive);
     Ok(bc::Directive::Include(construct! {
         bc::Include: directive => {
             filename = get_quoted_str;
             source := Some(source.to_owned());
+            location := Some(location);
         }
     }))
 }
@@ -285,6 +501,7 @@ fn open_directive<'i>(
     state: &ParseState,
 ) -> ParseResult<bc::Directive> {
     let source = directive.as_str();
+    let location = state.directive_location(&directive);
     Ok(bc::Directive::Open(construct! {
         bc::Open: directive => {
             date = date;
@@ -310,6 +527,7 @@ fn open_directive<'i>(
             };
             meta = |p| meta_kv(p, state);
             source := Some(source.to_owned());
+            location := Some(location);
         }
     }))
 }
@@ -319,12 +537,14 @@ fn close_directive<'i>(
     state: &ParseState,
 ) -> ParseResult<bc::Directive> {
     let source = directive.as_str();
+    let location = state.directive_location(&directive);
     Ok(bc::Directive::Close(construct! {
         bc::Close: directive => {
             date = date;
             account = |p| account(p, state);
             meta = |p| meta_kv(p, state);
             source := Some(source.to_owned());
+            location := Some(location);
         }
     }))
 }
@@ -334,6 +554,7 @@ fn balance_directive<'i>(
     state: &ParseState,
 ) -> ParseResult<bc::Directive> {
     let source = directive.as_str();
+    let location = state.directive_location(&directive);
     Ok(bc::Directive::Balance(construct! {
         bc::Balance: directive => {
             date = date;
@@ -345,6 +566,7 @@ fn balance_directive<'i>(
             tolerance := tol;
             meta = |p| meta_kv(p, state);
             source := Some(source.to_owned());
+            location := Some(location);
         }
     }))
 }
@@ -354,12 +576,14 @@ fn commodity_directive<'i>(
     state: &ParseState,
 ) -> ParseResult<bc::Directive> {
     let source = directive.as_str();
+    let location = state.directive_location(&directive);
     Ok(bc::Directive::Commodity(construct! {
         bc::Commodity: directive => {
             date = date;
             name = as_str;
             meta = |p| meta_kv(p, state);
             source := Some(source.to_owned());
+            location := Some(location);
         }
     }))
 }
@@ -369,13 +593,15 @@ fn note_directive<'i>(
     state: &ParseState,
 ) -> ParseResult<bc::Directive> {
     let source = directive.as_str();
+    let location = state.directive_location(&directive);
     Ok(bc::Directive::Note(construct! {
         bc::Note: directive => {
             date = date;
             account = |p| account(p, state);
-            comment = as_str;
+            comment = get_quoted_str;
             meta = |p| meta_kv(p, state);
             source := Some(source.to_owned());
+            location := Some(location);
         }
     }))
 }
@@ -385,6 +611,7 @@ fn pad_directive<'i>(
     state: &ParseState,
 ) -> ParseResult<bc::Directive> {
     let source = directive.as_str();
+    let location = state.directive_location(&directive);
     Ok(bc::Directive::Pad(construct! {
         bc::Pad: directive => {
             date = date;
@@ -392,6 +619,7 @@ fn pad_directive<'i>(
             pad_from_account = |p| account(p, state);
             meta = |p| meta_kv(p, state);
             source := Some(source.to_owned());
+            location := Some(location);
         }
     }))
 }
@@ -401,6 +629,7 @@ fn query_directive<'i>(
     state: &ParseState,
 ) -> ParseResult<bc::Directive> {
     let source = directive.as_str();
+    let location = state.directive_location(&directive);
     Ok(bc::Directive::Query(construct! {
         bc::Query: directive => {
             date = date;
@@ -408,6 +637,7 @@ fn query_directive<'i>(
             query_string = get_quoted_str;
             meta = |p| meta_kv(p, state);
             source := Some(source.to_owned());
+            location := Some(location);
         }
     }))
 }
@@ -417,6 +647,7 @@ fn event_directive<'i>(
     state: &ParseState,
 ) -> ParseResult<bc::Directive> {
     let source = directive.as_str();
+    let location = state.directive_location(&directive);
     Ok(bc::Directive::Event(construct! {
         bc::Event: directive => {
             date = date;
@@ -424,6 +655,7 @@ fn event_directive<'i>(
             description = get_quoted_str;
             meta = |p| meta_kv(p, state);
             source := Some(source.to_owned());
+            location := Some(location);
         }
     }))
 }
@@ -433,6 +665,7 @@ fn document_directive<'i>(
     state: &ParseState,
 ) -> ParseResult<bc::Directive> {
     let source = directive.as_str();
+    let location = state.directive_location(&directive);
     Ok(bc::Directive::Document(construct! {
         bc::Document: directive => {
             date = date;
@@ -447,6 +680,7 @@ fn document_directive<'i>(
             links := links;
             meta = |p| meta_kv(p, state);
             source := Some(source.to_owned());
+            location := Some(location);
         }
     }))
 }
@@ -456,6 +690,7 @@ fn price_directive<'i>(
     state: &ParseState,
 ) -> ParseResult<bc::Directive> {
     let source = directive.as_str();
+    let location = state.directive_location(&directive);
     Ok(bc::Directive::Price(construct! {
         bc::Price: directive => {
             date = date;
@@ -463,6 +698,7 @@ fn price_directive<'i>(
             amount = amount;
             meta = |p| meta_kv(p, state);
             source := Some(source.to_owned());
+            location := Some(location);
         }
     }))
 }
@@ -472,6 +708,7 @@ fn transaction_directive<'i>(
     state: &ParseState,
 ) -> ParseResult<bc::Directive> {
     let source = directive.as_str();
+    let mut location = state.directive_location(&directive);
     Ok(bc::Directive::Transaction(construct! {
         bc::Transaction: directive => {
             date = date;
@@ -505,10 +742,15 @@ fn transaction_directive<'i>(
                             postings.push(posting(p, state)?);
                         }
                         Rule::key_value => {
+                            let entry_location = state.location(p.as_span());
                             let (k, v) = meta_kv_pair(p, state)?;
                             if let Some(last) = postings.last_mut() {
+                                if let Some(posting_location) = &mut last.location {
+                                    posting_location.meta.insert(k.to_string(), entry_location);
+                                }
                                 last.meta.insert(k.to_string(), v);
                             } else {
+                                location.meta.insert(k.to_string(), entry_location);
                                 tx_meta.insert(k.to_string(), v);
                             }
                         }
@@ -528,6 +770,7 @@ fn transaction_directive<'i>(
                 for tag in state.get_pushed_tags() {
                   tags.insert(tag.to_string());
                 }
+                state.apply_pushed_meta(&mut tx_meta);
                 (tx_meta, postings)
             };
             postings := postings;
@@ -535,12 +778,14 @@ fn transaction_directive<'i>(
             tags := tags;
             links := links;
             source := Some(source.to_owned());
+            location := Some(location);
         }
     }))
 }
 
 fn posting<'i>(pair: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::Posting> {
     let span = pair.as_span();
+    let location = state.location(span);
     let mut inner = pair.into_inner();
     let flag = optional_rule(Rule::txn_flag, &mut inner)
         .map(flag)
@@ -572,6 +817,7 @@ fn posting<'i>(pair: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::Post
         cost,
         price,
         meta: bc::metadata::Meta::new(),
+        location: Some(location),
     })
 }
 
@@ -624,16 +870,17 @@ fn amount_tolerance<'i>(pair: Pair<'i, Rule>) -> ParseResult<(bc::Amount, Option
     debug_assert!(pair.as_rule() == Rule::amount_tolerance);
     let span = pair.as_span();
     let mut inner = pair.into_inner();
-    let num_val =
-        inner.next().map(num_expr).transpose()?.ok_or_else(|| {
-            ParseError::invalid_state_with_span("numeric expression", span.clone())
-        })?;
+    let num_val = inner
+        .next()
+        .map(num_expr)
+        .transpose()?
+        .ok_or_else(|| ParseError::invalid_state_with_span("numeric expression", span))?;
     let tolerance = optional_rule(Rule::num, &mut inner).map(num).transpose()?;
     let currency = inner
         .next()
         .map(as_str)
         .transpose()?
-        .ok_or_else(|| ParseError::invalid_state_with_span("currency", span.clone()))?
+        .ok_or_else(|| ParseError::invalid_state_with_span("currency", span))?
         .into();
     Ok((
         bc::Amount {
@@ -709,7 +956,7 @@ fn price_annotation<'i>(pair: Pair<'i, Rule>) -> ParseResult<(bool, bc::Incomple
     let inner = pair
         .into_inner()
         .next()
-        .ok_or_else(|| ParseError::invalid_state_with_span("price annotation", span.clone()))?;
+        .ok_or_else(|| ParseError::invalid_state_with_span("price annotation", span))?;
     let is_total = inner.as_rule() == Rule::price_annotation_total;
     let amount = incomplete_amount(
         inner
@@ -728,20 +975,14 @@ fn account<'i>(pair: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::Acco
         .next()
         .ok_or_else(|| ParseError::invalid_state_with_span("first part of account name", span))?;
     let first = first_pair.as_str();
-    let account_type = state
-        .root_names
-        .iter()
-        .filter(|(_, ref v)| *v == first)
-        .map(|(k, _)| *k)
-        .next()
-        .ok_or_else(|| {
-            pest::error::Error::new_from_span(
-                pest::error::ErrorVariant::CustomError {
-                    message: "Invalid root account".to_string(),
-                },
-                first_pair.as_span(),
-            )
-        })?;
+    let account_type = state.root_names.account_type(first).ok_or_else(|| {
+        pest::error::Error::new_from_span(
+            pest::error::ErrorVariant::CustomError {
+                message: "Invalid root account".to_string(),
+            },
+            first_pair.as_span(),
+        )
+    })?;
     let parts: Vec<_> = inner.map(|p| Cow::Borrowed(&p.as_str()[1..])).collect();
     Ok(bc::Account::builder().ty(account_type).parts(parts.into_iter().map(|cow| cow.to_string()).collect()).build())
 }
@@ -751,12 +992,19 @@ fn as_str<'i>(pair: Pair<'i, Rule>) -> ParseResult<&'i str> {
 }
 
 fn date<'i>(pair: Pair<'i, Rule>) -> ParseResult<bc::Date> {
-    Ok(bc::Date::from_str_unchecked(pair.as_str()))
+    pair.as_str()
+        .parse()
+        .map_err(|e| ParseError::invalid_input_with_span(e, pair.as_span()))
 }
 
 fn meta_kv<'i>(pair: Pair<'i, Rule>, state: &ParseState) -> ParseResult<bc::metadata::Meta> {
     debug_assert!(pair.as_rule() == Rule::eol_kv_list);
-    pair.into_inner().map(|p| meta_kv_pair(p, state)).collect()
+    let mut meta = pair
+        .into_inner()
+        .map(|p| meta_kv_pair(p, state))
+        .collect::<ParseResult<bc::metadata::Meta>>()?;
+    state.apply_pushed_meta(&mut meta);
+    Ok(meta)
 }
 
 fn tags_links<'i>(
@@ -793,35 +1041,60 @@ fn meta_kv_pair<'i>(
     let mut inner = pair.into_inner();
     let key = inner
         .next()
-        .ok_or_else(|| ParseError::invalid_state_with_span("metadata key", span.clone()))?
+        .ok_or_else(|| ParseError::invalid_state_with_span("metadata key", span))?
         .as_str();
     let value_pair = inner
         .next()
         .and_then(|p| p.into_inner().next())
         .ok_or_else(|| ParseError::invalid_state_with_span("metadata value", span))?;
+    Ok((key.into(), meta_value(value_pair, state)?))
+}
+
+/// Convert the value of a metadata entry or an argument of a `custom` directive.
+fn meta_value<'i>(
+    value_pair: Pair<'i, Rule>,
+    state: &ParseState,
+) -> ParseResult<bc::metadata::MetaValue> {
     let value = match value_pair.as_rule() {
         Rule::quoted_str => bc::metadata::MetaValue::Text(get_quoted_str(value_pair)?),
         Rule::account => bc::metadata::MetaValue::Account(account(value_pair, state)?),
         Rule::date => bc::metadata::MetaValue::Date(date(value_pair)?),
         Rule::commodity => bc::metadata::MetaValue::Currency(value_pair.as_str().into()),
         Rule::tag => bc::metadata::MetaValue::Tag((&value_pair.as_str()[1..]).into()),
-        Rule::bool => bc::metadata::MetaValue::Bool(value_pair.as_str() == "true"),
+        Rule::bool => {
+            bc::metadata::MetaValue::Bool(value_pair.as_str().eq_ignore_ascii_case("true"))
+        }
         Rule::amount => bc::metadata::MetaValue::Amount(amount(value_pair)?),
         Rule::num_expr => bc::metadata::MetaValue::Number(num_expr(value_pair)?),
         _ => unimplemented!(),
     };
-    Ok((key.into(), value))
+    Ok(value)
 }
 
+/// The contents of a quoted string, with its escape sequences replaced: `\n` and `\t` by a newline
+/// and a tab, and a backslash followed by any other character by that character.
 fn get_quoted_str<'i>(pair: Pair<'i, Rule>) -> ParseResult<String> {
     debug_assert!(pair.as_rule() == Rule::quoted_str);
     let span = pair.as_span();
-    Ok(pair
+    let raw = pair
         .into_inner()
         .next()
         .ok_or_else(|| ParseError::invalid_state_with_span("quoted string", span))?
-        .as_str()
-        .into())
+        .as_str();
+    let mut unescaped = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('t') => unescaped.push('\t'),
+                Some(c) => unescaped.push(c),
+                None => unescaped.push(c),
+            },
+            c => unescaped.push(c),
+        }
+    }
+    Ok(unescaped)
 }
 
 fn flag(pair: Pair<'_, Rule>) -> ParseResult<bc::Flag> {
@@ -880,6 +1153,16 @@ mod tests {
         };
     }
 
+    fn loc(line: usize, column: usize, span: std::ops::Range<usize>) -> Option<bc::Location> {
+        Some(bc::Location {
+            file: 0,
+            line,
+            column,
+            span,
+            meta: HashMap::new(),
+        })
+    }
+
     macro_rules! parse_fail {
         ( $rule:ident, $input:expr ) => {
             assert!(BeancountParser::parse(Rule::$rule, $input).is_err());
@@ -975,6 +1258,44 @@ mod tests {
         parse_ok!(amount_tolerance, "1.2 ~ 0.002 EUR");
     }
 
+    #[test]
+    fn bool() {
+        parse_ok!(bool, "TRUE");
+        parse_ok!(bool, "FALSE");
+        parse_ok!(bool, "True");
+        parse_ok!(bool, "false");
+
+        parse_fail!(bool, "TRUEX");
+        parse_fail!(bool, "FALSE_1");
+        parse_fail!(bool, "TRUE.X");
+        parse_fail!(bool, "yes");
+    }
+
+    #[test]
+    fn bool_metadata() {
+        let source = indoc!(
+            "
+            2014-01-01 open Assets:Cash
+              upper: TRUE
+              lower: false
+              mixed: True
+              currency: TRUEX
+            "
+        );
+        let ledger = parse(source).unwrap();
+        let meta = match &ledger.directives[0] {
+            bc::Directive::Open(open) => &open.meta,
+            other => panic!("unexpected directive {:?}", other),
+        };
+        assert_eq!(meta["upper"], bc::metadata::MetaValue::Bool(true));
+        assert_eq!(meta["lower"], bc::metadata::MetaValue::Bool(false));
+        assert_eq!(meta["mixed"], bc::metadata::MetaValue::Bool(true));
+        assert_eq!(
+            meta["currency"],
+            bc::metadata::MetaValue::Currency("TRUEX".into())
+        );
+    }
+
     #[test]
     fn commodity() {
         parse_ok!(commodity, "AAA");
@@ -1000,6 +1321,9 @@ mod tests {
         parse_ok!(commodity, "FOO\"123", "FOO");
         parse_fail!(commodity, "123");
         parse_fail!(commodity, "foo");
+        parse_fail!(commodity, "TRUE");
+        parse_fail!(commodity, "FALSE");
+        parse_ok!(commodity, "TRUEX");
     }
 
     #[test]
@@ -1072,6 +1396,33 @@ mod tests {
         parse_ok!(custom, "2014-07-09 custom \"budget\" \"some_config_opt_for_custom_directive\" TRUE 45.30 USD\n");
     }
 
+    #[test]
+    fn custom_args() {
+        use bc::metadata::MetaValue;
+
+        let source = "2014-07-09 custom \"budget\" \"a \\\"b\\\"\" 2014-01-01 TRUE 45.30 USD 12 Assets:Cash\n";
+        let ledger = parse(source).unwrap();
+        let custom = match &ledger.directives[0] {
+            bc::Directive::Custom(custom) => custom,
+            other => panic!("unexpected directive {:?}", other),
+        };
+        assert_eq!(custom.name, "budget");
+        assert_eq!(
+            custom.args,
+            vec![
+                MetaValue::Text("a \"b\"".into()),
+                MetaValue::Date("2014-01-01".parse().unwrap()),
+                MetaValue::Bool(true),
+                MetaValue::Amount(bc::Amount {
+                    num: Decimal::new(4530, 2),
+                    currency: "USD".into(),
+                }),
+                MetaValue::Number(Decimal::new(12, 0)),
+                MetaValue::Account("Assets:Cash".parse().unwrap()),
+            ]
+        );
+    }
+
     #[test]
     fn document() {
         parse_ok!(
@@ -1111,6 +1462,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn note_comment() {
+        let source =
+            "2013-11-03 note Liabilities:CreditCard \"Called \\\"them\\\"\\nagain\\tlater\"\n";
+        let ledger = parse(source).unwrap();
+        match &ledger.directives[0] {
+            bc::Directive::Note(note) => {
+                assert_eq!(note.comment, "Called \"them\"\nagain\tlater")
+            }
+            other => panic!("unexpected directive {:?}", other),
+        }
+    }
+
     #[test]
     fn open() {
         parse_ok!(
@@ -1151,7 +1515,7 @@ mod tests {
             "
         );
         assert_eq!(
-            parse(&source).unwrap(),
+            parse(source).unwrap(),
             bc::Ledger {
                 directives: vec![
                     bc::Directive::Plugin(
@@ -1159,6 +1523,7 @@ mod tests {
                             .module("beancount.plugins.module_name".into())
                             .config(None)
                             .source(Some("plugin \"beancount.plugins.module_name\"\n".to_owned()))
+                            .location(loc(1, 1, 0..39))
                             .build()
                     ),
                     bc::Directive::Plugin(
@@ -1168,6 +1533,7 @@ mod tests {
                             .source(Some(
                                 "plugin \"beancount.plugins.module_name2\" \"config\"\n".to_owned()
                             ))
+                            .location(loc(2, 1, 39..88))
                             .build()
                     )
                 ]
@@ -1220,9 +1586,25 @@ mod tests {
         parse_fail!(poptag, "poptag #goodtag #badtag\n");
     }
 
+    #[test]
+    fn pushmeta() {
+        parse_ok!(pushmeta, "pushmeta location: \"Lausanne\"\n");
+        parse_ok!(pushmeta, "pushmeta   trip: 2020-01-01  \n");
+        parse_fail!(pushmeta, "pushmeta\n");
+        parse_fail!(pushmeta, "pushmeta location:\n");
+    }
+
+    #[test]
+    fn popmeta() {
+        parse_ok!(popmeta, "popmeta location:\n");
+        parse_ok!(popmeta, "popmeta   location:  \n");
+        parse_fail!(popmeta, "popmeta\n");
+        parse_fail!(popmeta, "popmeta location\n");
+    }
+
     #[test]
     fn test_push() {
-        let mut state = ParseState::new();
+        let mut state = ParseState::new(0);
         state.push_tag("sometag");
         assert_eq!(1, state.pushed_tags.len());
         assert_eq!(Some(&1), state.pushed_tags.get("sometag"));
@@ -1237,7 +1619,7 @@ mod tests {
 
     #[test]
     fn test_pop() {
-        let mut state = ParseState::new();
+        let mut state = ParseState::new(0);
         assert!(state.pop_tag("sometag").is_err());
         state.push_tag("sometag");
         state.push_tag("sometag");
@@ -1252,17 +1634,14 @@ mod tests {
     }
 
     fn get_sorted_tags<'a>(state: &'a ParseState) -> Vec<&'a str> {
-        let mut tags = state
-            .get_pushed_tags()
-            .map(|a| *a)
-            .collect::<Vec<&'a str>>();
+        let mut tags = state.get_pushed_tags().copied().collect::<Vec<&'a str>>();
         tags.sort();
         tags
     }
 
     #[test]
     fn test_iter() {
-        let mut state = ParseState::new();
+        let mut state = ParseState::new(0);
 
         assert!(get_sorted_tags(&state).is_empty());
         state.push_tag("sometag");
@@ -1287,14 +1666,14 @@ mod tests {
             pushtag #social
             "
         );
-        assert!(parse(&source).is_err());
+        assert!(parse(source).is_err());
 
         let source = indoc!(
             "
             poptag #social
             "
         );
-        assert!(parse(&source).is_err());
+        assert!(parse(source).is_err());
 
         let source = indoc!(
             "
@@ -1302,7 +1681,7 @@ mod tests {
             poptag #social
             "
         );
-        assert!(parse(&source).is_ok());
+        assert!(parse(source).is_ok());
 
         let source = indoc!(
             "
@@ -1317,7 +1696,7 @@ mod tests {
             poptag #social
             "
         );
-        assert!(parse(&source).is_ok());
+        assert!(parse(source).is_ok());
         let source = indoc!(
             "
             pushtag #rust-is-cool
@@ -1325,7 +1704,118 @@ mod tests {
             poptag #social
             "
         );
-        assert!(parse(&source).is_err());
+        assert!(parse(source).is_err());
+    }
+
+    #[test]
+    fn test_push_and_pop_meta() {
+        use bc::metadata::MetaValue;
+
+        let mut state = ParseState::new(0);
+        state.push_meta("location".into(), MetaValue::Text("Lausanne".into()));
+        state.push_meta("location".into(), MetaValue::Text("Paris".into()));
+        let pushed: Vec<_> = state.get_pushed_meta().collect();
+        assert_eq!(
+            pushed,
+            vec![(&"location".to_string(), &MetaValue::Text("Paris".into()))]
+        );
+
+        assert!(state.pop_meta("location").is_ok());
+        let pushed: Vec<_> = state.get_pushed_meta().collect();
+        assert_eq!(
+            pushed,
+            vec![(&"location".to_string(), &MetaValue::Text("Lausanne".into()))]
+        );
+        assert!(state.pop_meta("location").is_ok());
+        assert_eq!(state.get_pushed_meta().count(), 0);
+        assert!(state.pop_meta("location").is_err());
+    }
+
+    #[test]
+    fn test_pushed_meta_added_to_directives() {
+        use bc::metadata::MetaValue;
+
+        let source = indoc!(
+            "
+            pushmeta location: \"Lausanne\"
+            2014-05-05 open Assets:Cash
+            2014-05-05 txn \"Cafe\"
+                location: \"Geneva\"
+                Assets:Cash         -10 CHF
+                Expenses:Food
+            popmeta location:
+            2014-05-06 close Assets:Cash
+            "
+        );
+        let ledger = parse(source).unwrap();
+        let metas: Vec<_> = ledger
+            .directives
+            .iter()
+            .map(|d| match d {
+                bc::Directive::Open(d) => &d.meta,
+                bc::Directive::Transaction(d) => &d.meta,
+                bc::Directive::Close(d) => &d.meta,
+                other => panic!("unexpected directive {:?}", other),
+            })
+            .map(|meta| meta.get("location"))
+            .collect();
+        assert_eq!(
+            metas,
+            vec![
+                Some(&MetaValue::Text("Lausanne".into())),
+                Some(&MetaValue::Text("Geneva".into())),
+                None
+            ]
+        );
+        match &ledger.directives[1] {
+            bc::Directive::Transaction(txn) => {
+                assert!(txn.postings.iter().all(|p| p.meta.is_empty()))
+            }
+            other => panic!("unexpected directive {:?}", other),
+        }
+
+        let source = indoc!(
+            "
+            pushmeta location: \"Lausanne\"
+            pushmeta trip: \"France\"
+            popmeta trip:
+            "
+        );
+        let err = parse(source).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unbalanced pushed metadata: 'location'"));
+
+        let source = indoc!(
+            "
+            popmeta location:
+            "
+        );
+        assert!(parse(source).is_err());
+    }
+
+    #[test]
+    fn test_dates_are_validated() {
+        let source = indoc!(
+            "
+            2020/02/29 open Assets:Cash
+            2021-02-29 open Assets:Bank
+            "
+        );
+        let err = parse(source).unwrap_err();
+        assert_eq!(err.location, (2, 1));
+        assert_eq!(
+            err.kind,
+            error::ParseErrorKind::InvalidInput {
+                message: "invalid date '2021-02-29'".into()
+            }
+        );
+
+        let ledger = parse(&source[..28]).unwrap();
+        assert_eq!(
+            ledger.directives[0].date().map(|d| d.to_string()),
+            Some("2020-02-29".into())
+        );
     }
 
     #[test]
@@ -1362,7 +1852,7 @@ mod tests {
             bc::Ledger {
                 directives: vec![bc::Directive::Transaction(
                     bc::Transaction::builder()
-                        .date(bc::Date::from_str_unchecked("2014-05-05"))
+                        .date("2014-05-05".parse().unwrap())
                         .payee(Some("Cafe Mogador".into()))
                         .narration("Lamb tagine with wine".into())
                         .postings(vec![bc::Posting::builder()
@@ -1391,14 +1881,16 @@ mod tests {
                                     .currency(Some("GBP".into()))
                                     .build()
                             )))
+                            .location(loc(6, 5, 134..205))
                             .build()])
                         .tags(
-                            vec!["social", "alcohol"]
+                            ["social", "alcohol"]
                                 .iter()
                                 .map(|a| a.to_string())
                                 .collect::<HashSet<Tag>>()
                         )
                         .source(Some(txn_source.to_string()))
+                        .location(loc(5, 1, 76..206))
                         .build()
                 )]
             }
@@ -1477,21 +1969,21 @@ mod tests {
             "
         );
         assert_eq!(
-            parse(&source).unwrap(),
+            parse(source).unwrap(),
             bc::Ledger {
                 directives: vec![bc::Directive::Transaction(
                     bc::Transaction::builder()
-                        .date(bc::Date::from_str_unchecked("2014-05-05"))
+                        .date("2014-05-05".parse().unwrap())
                         .payee(Some("Cafe Mogador".into()))
                         .narration("Lamb tagine with wine".into())
                         .tags(
-                            vec!["tag"]
+                            ["tag"]
                                 .iter()
                                 .map(|a| a.to_string())
                                 .collect::<HashSet<Tag>>()
                         )
                         .links(
-                            vec!["link"]
+                            ["link"]
                                 .iter()
                                 .map(|a| a.to_string())
                                 .collect::<HashSet<Tag>>()
@@ -1522,8 +2014,10 @@ mod tests {
                                     .currency(Some("GBP".into()))
                                     .build()
                             )))
+                            .location(loc(2, 5, 69..140))
                             .build()])
                         .source(Some(source.to_owned()))
+                        .location(loc(1, 1, 0..141))
                         .build()
                 )]
             }
@@ -1536,21 +2030,21 @@ mod tests {
             "
         );
         assert_eq!(
-            parse(&source).unwrap(),
+            parse(source).unwrap(),
             bc::Ledger {
                 directives: vec![bc::Directive::Transaction(
                     bc::Transaction::builder()
-                        .date(bc::Date::from_str_unchecked("2014-05-05"))
+                        .date("2014-05-05".parse().unwrap())
                         .payee(Some("Cafe Mogador".into()))
                         .narration("Lamb tagine with wine".into())
                         .tags(
-                            vec!["tag"]
+                            ["tag"]
                                 .iter()
                                 .map(|a| a.to_string())
                                 .collect::<HashSet<Tag>>()
                         )
                         .links(
-                            vec!["link"]
+                            ["link"]
                                 .iter()
                                 .map(|a| a.to_string())
                                 .collect::<HashSet<Tag>>()
@@ -1581,8 +2075,10 @@ mod tests {
                                     .currency(Some("GBP".into()))
                                     .build()
                             )))
+                            .location(loc(2, 5, 69..141))
                             .build()])
                         .source(Some(source.to_owned()))
+                        .location(loc(1, 1, 0..142))
                         .build()
                 )]
             }
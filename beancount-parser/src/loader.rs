@@ -0,0 +1,240 @@
+//! Loading of ledgers split across several files with `include` directives.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bc::location::FileId;
+use bc::options::{OptionError, Options};
+use bc::plugins::{PluginError, PluginRegistry};
+use beancount_core as bc;
+
+use super::error::ParseError;
+use super::parse_file;
+
+/// The directives of a ledger and of all the files it includes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadedLedger {
+    /// The loaded files, in the order they were loaded. The root file comes first. The
+    /// [file](../../beancount_core/location/struct.Location.html#structfield.file) of the
+    /// location of each directive is an index into this list.
+    pub files: Vec<PathBuf>,
+
+    /// The directives of all the files. The `include` directives are replaced by the directives
+    /// of the files they include.
+    pub ledger: bc::Ledger,
+}
+
+impl LoadedLedger {
+    /// The path of the file with the given id.
+    pub fn path(&self, file: FileId) -> &Path {
+        &self.files[file]
+    }
+
+    /// Iterate over the directives of the ledger together with the path of the file they were
+    /// parsed from.
+    pub fn directives_with_paths(&self) -> impl Iterator<Item = (&bc::Directive, &Path)> {
+        self.ledger.directives.iter().filter_map(move |directive| {
+            let location = directive.location()?;
+            Some((directive, self.path(location.file)))
+        })
+    }
+
+    /// The options set by the `option` directives of all the loaded files, together with the
+    /// options which could not be applied, see
+    /// [Options::from_directives](../../beancount_core/options/struct.Options.html#method.from_directives).
+    pub fn options(&self) -> (Options, Vec<OptionError>) {
+        Options::from_directives(&self.ledger.directives)
+    }
+
+    /// Run the plugins named by the `plugin` directives of all the loaded files, in the order
+    /// they appear in, see
+    /// [PluginRegistry::run](../../beancount_core/plugins/struct.PluginRegistry.html#method.run).
+    pub fn run_plugins(&mut self, registry: &PluginRegistry) -> Vec<PluginError> {
+        let directives = std::mem::take(&mut self.ledger.directives);
+        let (directives, errors) = registry.run(directives);
+        self.ledger.directives = directives;
+        errors
+    }
+}
+
+/// An error encountered while loading a ledger.
+#[derive(Debug)]
+pub enum LoadError {
+    /// A file could not be read.
+    Io { path: PathBuf, source: io::Error },
+    /// A file could not be parsed.
+    Parse { path: PathBuf, source: ParseError },
+    /// The filename of an `include` directive is not a valid glob pattern.
+    InvalidPattern {
+        path: PathBuf,
+        pattern: String,
+        source: glob::PatternError,
+    },
+    /// The glob pattern of an `include` directive does not match any file.
+    NoMatch { path: PathBuf, pattern: String },
+    /// A file includes itself, directly or through other files. The chain of includes is given
+    /// from the first file of the cycle back to itself.
+    IncludeCycle { cycle: Vec<PathBuf> },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io { path, source } => {
+                write!(f, "{}: {}", path.display(), source)
+            }
+            LoadError::Parse { path, source } => {
+                write!(f, "{}: {}", path.display(), source)
+            }
+            LoadError::InvalidPattern {
+                path,
+                pattern,
+                source,
+            } => {
+                write!(
+                    f,
+                    "{}: invalid include pattern '{}': {}",
+                    path.display(),
+                    pattern,
+                    source
+                )
+            }
+            LoadError::NoMatch { path, pattern } => {
+                write!(
+                    f,
+                    "{}: include pattern '{}' does not match any file",
+                    path.display(),
+                    pattern
+                )
+            }
+            LoadError::IncludeCycle { cycle } => {
+                let cycle: Vec<_> = cycle.iter().map(|p| p.display().to_string()).collect();
+                write!(f, "include cycle: {}", cycle.join(" -> "))
+            }
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io { source, .. } => Some(source),
+            LoadError::Parse { source, .. } => Some(source),
+            LoadError::InvalidPattern { source, .. } => Some(source),
+            LoadError::NoMatch { .. } | LoadError::IncludeCycle { .. } => None,
+        }
+    }
+}
+
+/// Load the ledger in the file at `path`, recursively resolving its `include` directives.
+///
+/// The filename of an `include` directive is resolved relative to the directory of the file
+/// containing it, and may be a glob pattern such as `"years/*.beancount"`, in which case every
+/// matching file is included in alphabetical order. The directives of an included file take the
+/// place of the `include` directive. A file which is included more than once is only loaded the
+/// first time, and a file which includes itself, directly or indirectly, is reported as an
+/// [IncludeCycle](enum.LoadError.html#variant.IncludeCycle).
+pub fn load<P: AsRef<Path>>(path: P) -> Result<LoadedLedger, LoadError> {
+    let mut loader = Loader {
+        files: Vec::new(),
+        directives: Vec::new(),
+        stack: Vec::new(),
+    };
+    let path = canonicalize(path.as_ref())?;
+    loader.load_file(path)?;
+    Ok(LoadedLedger {
+        files: loader.files,
+        ledger: bc::Ledger::builder().directives(loader.directives).build(),
+    })
+}
+
+struct Loader {
+    files: Vec<PathBuf>,
+    directives: Vec<bc::Directive>,
+    /// The files currently being loaded, from the root file down to the innermost include.
+    stack: Vec<PathBuf>,
+}
+
+impl Loader {
+    fn load_file(&mut self, path: PathBuf) -> Result<(), LoadError> {
+        if let Some(start) = self.stack.iter().position(|p| *p == path) {
+            let mut cycle = self.stack[start..].to_vec();
+            cycle.push(path);
+            return Err(LoadError::IncludeCycle { cycle });
+        }
+        if self.files.contains(&path) {
+            return Ok(());
+        }
+
+        let source = fs::read_to_string(&path).map_err(|source| LoadError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        let ledger = parse_file(&source, self.files.len()).map_err(|source| LoadError::Parse {
+            path: path.clone(),
+            source,
+        })?;
+        self.files.push(path.clone());
+        self.stack.push(path);
+
+        for directive in ledger.directives {
+            match directive {
+                bc::Directive::Include(include) => {
+                    for included in self.resolve(&include.filename)? {
+                        self.load_file(included)?;
+                    }
+                }
+                directive => self.directives.push(directive),
+            }
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+
+    /// Resolve the filename of an `include` directive in the file currently being loaded.
+    fn resolve(&self, filename: &str) -> Result<Vec<PathBuf>, LoadError> {
+        let including = self
+            .stack
+            .last()
+            .expect("an include is always inside a file");
+        let base = including.parent().unwrap_or_else(|| Path::new(""));
+        let joined = base.join(filename);
+
+        if !filename.contains(['*', '?', '[']) {
+            return Ok(vec![canonicalize(&joined)?]);
+        }
+
+        let pattern = joined.to_string_lossy();
+        let paths = glob::glob(&pattern).map_err(|source| LoadError::InvalidPattern {
+            path: including.clone(),
+            pattern: filename.to_owned(),
+            source,
+        })?;
+        let mut resolved = Vec::new();
+        for path in paths {
+            let path = path.map_err(|err| LoadError::Io {
+                path: err.path().to_owned(),
+                source: err.into(),
+            })?;
+            resolved.push(canonicalize(&path)?);
+        }
+        if resolved.is_empty() {
+            return Err(LoadError::NoMatch {
+                path: including.clone(),
+                pattern: filename.to_owned(),
+            });
+        }
+        Ok(resolved)
+    }
+}
+
+fn canonicalize(path: &Path) -> Result<PathBuf, LoadError> {
+    path.canonicalize().map_err(|source| LoadError::Io {
+        path: path.to_owned(),
+        source,
+    })
+}
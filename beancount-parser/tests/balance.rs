@@ -0,0 +1,87 @@
+use beancount_core::booking::book;
+use beancount_core::interpolate::ToleranceOptions;
+use beancount_core::ops::balance::{validate_balances, BalanceError};
+use beancount_parser::parse;
+use indoc::indoc;
+use rust_decimal::Decimal;
+
+const LEDGER: &str = indoc! {r#"
+    2020-01-01 open Assets:Bank:Checking
+    2020-01-01 open Assets:Bank:Savings
+    2020-01-01 open Equity:Opening
+
+    2020-01-02 * "Opening balances"
+      Assets:Bank:Checking    100.00 USD
+      Assets:Bank:Savings     250.00 USD
+      Equity:Opening
+
+    2020-01-10 * "Coffee"
+      Assets:Bank:Checking     -3.50 USD
+      Equity:Opening
+    "#};
+
+fn check(source: &str) -> Vec<BalanceError> {
+    let directives = parse(source).unwrap().directives;
+    let options = ToleranceOptions::from_directives(&directives);
+    let (directives, errors) = book(directives, Default::default());
+    assert!(errors.is_empty(), "{:?}", errors);
+    validate_balances(&directives, &options)
+}
+
+fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+#[test]
+fn passing_assertions() {
+    let source = LEDGER.to_owned()
+        + indoc! {r#"
+        2020-01-10 balance Assets:Bank:Checking   100.00 USD
+        2020-01-11 balance Assets:Bank:Checking    96.50 USD
+        2020-01-11 balance Assets:Bank            346.50 USD
+        2020-01-11 balance Assets:Bank:Savings      0 EUR
+    "#};
+    let errors = check(&source);
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn failing_assertion_reports_difference() {
+    let source = LEDGER.to_owned()
+        + indoc! {r#"
+        2020-01-11 balance Assets:Bank:Checking    97.00 USD
+    "#};
+    let errors = check(&source);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].actual.num, dec("96.50"));
+    assert_eq!(errors[0].difference.num, dec("-0.50"));
+    assert_eq!(
+        errors[0].to_string(),
+        "balance failed for Assets:Bank:Checking: expected 97.00 USD, \
+         accumulated 96.50 USD (0.50 USD too little)"
+    );
+}
+
+#[test]
+fn assertions_apply_at_start_of_day() {
+    let source = LEDGER.to_owned()
+        + indoc! {r#"
+        2020-01-10 balance Assets:Bank:Checking    96.50 USD
+    "#};
+    assert_eq!(check(&source).len(), 1);
+}
+
+#[test]
+fn tolerances() {
+    let source = LEDGER.to_owned()
+        + indoc! {r#"
+        2020-01-11 balance Assets:Bank:Checking    96.51 USD
+        2020-01-11 balance Assets:Bank:Checking    96.52 USD
+        2020-01-11 balance Assets:Bank:Checking    96.4 USD
+        2020-01-11 balance Assets:Bank:Checking    97 ~ 0.50 USD
+        2020-01-11 balance Assets:Bank:Checking    97 ~ 0.49 USD
+    "#};
+    let errors = check(&source);
+    let failed: Vec<_> = errors.iter().map(|e| e.balance.amount.num).collect();
+    assert_eq!(failed, vec![dec("96.52"), dec("97")]);
+}
@@ -0,0 +1,226 @@
+use beancount_core::booking::{book, BookingErrorKind};
+use beancount_core::{Booking, Directive, Position};
+use beancount_parser::parse;
+use indoc::indoc;
+use rust_decimal::Decimal;
+
+const LOTS: &str = indoc! {r#"
+    2020-01-01 open Assets:Brokerage
+    2020-01-01 open Assets:Cash
+
+    2020-01-05 * "Buy"
+      Assets:Brokerage    10 HOOL {500.00 USD}
+      Assets:Cash     -5000.00 USD
+
+    2020-02-05 * "Buy"
+      Assets:Brokerage     5 HOOL {520.00 USD, "second"}
+      Assets:Cash     -2600.00 USD
+    "#};
+
+fn book_str(
+    s: &str,
+    method: Booking,
+) -> (Vec<Directive>, Vec<beancount_core::booking::BookingError>) {
+    book(parse(s).unwrap().directives, method)
+}
+
+fn booked_positions(directives: &[Directive], narration: &str) -> Vec<Position> {
+    directives
+        .iter()
+        .filter_map(|d| match d {
+            Directive::Transaction(txn) if txn.narration == narration => Some(txn),
+            _ => None,
+        })
+        .flat_map(|txn| txn.postings.iter())
+        .filter(|p| p.cost.is_some())
+        .map(|p| p.position().expect("fully booked posting"))
+        .collect()
+}
+
+fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+#[test]
+fn augmentation_fills_in_date() {
+    let (directives, errors) = book_str(LOTS, Booking::Strict);
+    assert!(errors.is_empty());
+    let buys = booked_positions(&directives, "Buy");
+    assert_eq!(buys.len(), 2);
+    let cost = buys[0].cost.as_ref().unwrap();
+    assert_eq!(cost.date.to_string(), "2020-01-05");
+    assert_eq!(cost.number, dec("500.00"));
+    assert_eq!(
+        buys[1].cost.as_ref().unwrap().label.as_deref(),
+        Some("second")
+    );
+}
+
+#[test]
+fn total_cost_is_converted_to_per_unit() {
+    let source = indoc! {r#"
+        2020-01-05 * "Buy"
+          Assets:Brokerage    10 HOOL {{5000.00 USD}}
+          Assets:Cash     -5000.00 USD
+    "#};
+    let (directives, errors) = book_str(source, Booking::Strict);
+    assert!(errors.is_empty());
+    let buys = booked_positions(&directives, "Buy");
+    assert_eq!(buys[0].cost.as_ref().unwrap().number, dec("500"));
+}
+
+#[test]
+fn strict_rejects_ambiguous_reduction() {
+    let source = LOTS.to_owned()
+        + indoc! {r#"
+        2020-03-01 * "Sell"
+          Assets:Brokerage    -3 HOOL {}
+          Assets:Cash
+    "#};
+    let (directives, errors) = book_str(&source, Booking::Strict);
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        &errors[0].kind,
+        BookingErrorKind::Ambiguous { matches } if matches.len() == 2
+    ));
+    assert!(booked_positions(&directives, "Sell").is_empty());
+}
+
+#[test]
+fn strict_accepts_unique_or_total_match() {
+    let source = LOTS.to_owned()
+        + indoc! {r#"
+        2020-03-01 * "Sell"
+          Assets:Brokerage    -3 HOOL {"second"}
+          Assets:Cash
+
+        2020-03-02 * "Sell all"
+          Assets:Brokerage   -12 HOOL {}
+          Assets:Cash
+    "#};
+    let (directives, errors) = book_str(&source, Booking::Strict);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let sold = booked_positions(&directives, "Sell");
+    assert_eq!(sold.len(), 1);
+    assert_eq!(sold[0].units.num, dec("-3"));
+    assert_eq!(sold[0].cost.as_ref().unwrap().number, dec("520.00"));
+
+    let sold = booked_positions(&directives, "Sell all");
+    assert_eq!(sold.len(), 2);
+    assert_eq!(sold[0].units.num + sold[1].units.num, dec("-12"));
+}
+
+#[test]
+fn strict_with_size_picks_exact_lot() {
+    let source = LOTS.to_owned()
+        + indoc! {r#"
+        2020-03-01 * "Sell"
+          Assets:Brokerage    -5 HOOL {}
+          Assets:Cash
+    "#};
+    let (_, errors) = book_str(&source, Booking::Strict);
+    assert_eq!(errors.len(), 1);
+
+    let (directives, errors) = book_str(&source, Booking::StrictWithSize);
+    assert!(errors.is_empty());
+    let sold = booked_positions(&directives, "Sell");
+    assert_eq!(sold.len(), 1);
+    assert_eq!(sold[0].cost.as_ref().unwrap().number, dec("520.00"));
+}
+
+#[test]
+fn fifo_and_lifo() {
+    let source = LOTS.to_owned()
+        + indoc! {r#"
+        2020-03-01 * "Sell"
+          Assets:Brokerage   -11 HOOL {}
+          Assets:Cash
+    "#};
+
+    let (directives, errors) = book_str(&source, Booking::Fifo);
+    assert!(errors.is_empty());
+    let sold = booked_positions(&directives, "Sell");
+    assert_eq!(sold.len(), 2);
+    assert_eq!(sold[0].units.num, dec("-10"));
+    assert_eq!(sold[0].cost.as_ref().unwrap().number, dec("500.00"));
+    assert_eq!(sold[1].units.num, dec("-1"));
+
+    let (directives, errors) = book_str(&source, Booking::Lifo);
+    assert!(errors.is_empty());
+    let sold = booked_positions(&directives, "Sell");
+    assert_eq!(sold[0].units.num, dec("-5"));
+    assert_eq!(sold[0].cost.as_ref().unwrap().number, dec("520.00"));
+    assert_eq!(sold[1].units.num, dec("-6"));
+}
+
+#[test]
+fn booking_method_from_open_directive() {
+    let source = LOTS.replace(
+        "2020-01-01 open Assets:Brokerage",
+        "2020-01-01 open Assets:Brokerage \"FIFO\"",
+    ) + indoc! {r#"
+        2020-03-01 * "Sell"
+          Assets:Brokerage    -3 HOOL {}
+          Assets:Cash
+    "#};
+    let (directives, errors) = book_str(&source, Booking::Strict);
+    assert!(errors.is_empty());
+    let sold = booked_positions(&directives, "Sell");
+    assert_eq!(sold[0].cost.as_ref().unwrap().number, dec("500.00"));
+}
+
+#[test]
+fn average_merges_lots() {
+    let source = LOTS.to_owned()
+        + indoc! {r#"
+        2020-03-01 * "Sell"
+          Assets:Brokerage    -3 HOOL {*}
+          Assets:Cash
+    "#};
+    let (directives, errors) = book_str(&source, Booking::Strict);
+    assert!(errors.is_empty());
+    let sold = booked_positions(&directives, "Sell");
+    assert_eq!(sold.len(), 1);
+    assert_eq!(
+        sold[0].cost.as_ref().unwrap().number.round_dp(4),
+        dec("506.6667")
+    );
+}
+
+#[test]
+fn reduction_errors() {
+    let source = LOTS.to_owned()
+        + indoc! {r#"
+        2020-03-01 * "Sell"
+          Assets:Brokerage    -3 HOOL {600.00 USD}
+          Assets:Cash
+
+        2020-03-02 * "Sell"
+          Assets:Brokerage   -20 HOOL {}
+          Assets:Cash
+    "#};
+    let (_, errors) = book_str(&source, Booking::Fifo);
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].kind, BookingErrorKind::NoMatch);
+    assert_eq!(errors[0].posting, Some(0));
+    assert_eq!(
+        errors[1].kind,
+        BookingErrorKind::InsufficientUnits {
+            available: 15.into()
+        }
+    );
+}
+
+#[test]
+fn none_booking_allows_mixed_inventories() {
+    let source = LOTS.to_owned()
+        + indoc! {r#"
+        2020-03-01 * "Sell"
+          Assets:Brokerage    -3 HOOL {600.00 USD}
+          Assets:Cash
+    "#};
+    let (directives, errors) = book_str(&source, Booking::None);
+    assert!(errors.is_empty());
+    let sold = booked_positions(&directives, "Sell");
+    assert_eq!(sold[0].cost.as_ref().unwrap().number, dec("600.00"));
+}
@@ -0,0 +1,129 @@
+use beancount_core::booking::book;
+use beancount_core::convert::{convert, convert_amount, cost, market_value, units};
+use beancount_core::prices::PriceMap;
+use beancount_core::realization::realize;
+use beancount_core::{Amount, Date, Inventory, Ledger};
+use beancount_parser::parse;
+use indoc::indoc;
+use rust_decimal::Decimal;
+
+const LEDGER: &str = indoc! {r#"
+    2021-01-01 open Assets:Broker
+    2021-01-01 open Assets:Cash
+    2021-01-01 open Equity:Opening
+
+    2021-01-10 * "Buy"
+      Assets:Broker     10 HOOL {100.00 USD}
+      Assets:Broker      3 XYZ {7.00 USD}
+      Assets:Cash
+
+    2021-01-10 * "Buy in Zurich"
+      Assets:Broker      5 ACME {20.00 CHF}
+      Assets:Cash
+
+    2021-01-12 * "Deposit"
+      Assets:Cash       80.00 EUR
+      Equity:Opening
+
+    2021-02-01 price HOOL  120.00 USD
+    2021-03-01 price HOOL  150.00 USD
+    2021-02-01 price ACME   25.00 CHF
+    2021-02-01 price CHF     1.10 USD
+    2021-02-01 price EUR     1.20 USD
+    "#};
+
+/// The balance of all assets, and the prices of the ledger.
+fn assets_and_prices() -> (Inventory, PriceMap) {
+    let (directives, errors) = book(parse(LEDGER).unwrap().directives, Default::default());
+    assert!(errors.is_empty(), "{:?}", errors);
+    let prices = PriceMap::from_directives(&directives);
+    let ledger = Ledger { directives };
+    let realization = realize(&ledger);
+    let assets = realization.get(&"Assets".parse().unwrap()).unwrap();
+    (assets.total.clone(), prices)
+}
+
+fn amounts(inventory: &Inventory) -> Vec<String> {
+    let mut amounts: Vec<_> = inventory
+        .iter()
+        .map(|p| format!("{} {}", p.units.num, p.units.currency))
+        .collect();
+    amounts.sort();
+    amounts
+}
+
+fn date(s: &str) -> Date {
+    s.parse().unwrap()
+}
+
+fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+#[test]
+fn units_and_cost() {
+    let (assets, _) = assets_and_prices();
+    assert_eq!(
+        amounts(&units(&assets)),
+        [
+            "-100.00 CHF",
+            "-1021.00 USD",
+            "10 HOOL",
+            "3 XYZ",
+            "5 ACME",
+            "80.00 EUR"
+        ]
+    );
+    assert_eq!(amounts(&cost(&assets)), ["80.00 EUR"]);
+}
+
+#[test]
+fn market_value_in_cost_currency() {
+    let (assets, prices) = assets_and_prices();
+
+    let value = market_value(&assets, &prices, Some(date("2021-02-15")));
+    assert_eq!(
+        amounts(&value.inventory),
+        ["179.00 USD", "25.00 CHF", "3 XYZ", "80.00 EUR"]
+    );
+    let unpriced: Vec<_> = value.unpriced.iter().map(|p| &p.units).collect();
+    assert_eq!(unpriced.len(), 1);
+    assert_eq!(unpriced[0].currency, "XYZ");
+
+    let latest = market_value(&assets, &prices, None);
+    assert_eq!(latest.inventory.units_of("USD"), dec("479.00"));
+}
+
+#[test]
+fn convert_to_target_currency() {
+    let (assets, prices) = assets_and_prices();
+
+    let converted = convert(&assets, "USD", &prices, Some(date("2021-03-31")));
+    let unpriced: Vec<_> = converted
+        .unpriced
+        .iter()
+        .map(|p| p.units.currency.as_str())
+        .collect();
+    assert_eq!(unpriced, ["XYZ"]);
+    // 10 HOOL at 150, 5 ACME at 25 CHF at 1.10, -100 CHF, 80 EUR at 1.20 and the cash spent.
+    assert_eq!(converted.inventory.units_of("USD"), dec("602.5"));
+    assert_eq!(converted.inventory.units_of("XYZ"), dec("3"));
+    assert_eq!(converted.inventory.len(), 2);
+
+    let early = convert(&assets, "USD", &prices, Some(date("2021-01-31")));
+    assert_eq!(early.unpriced.len(), 5);
+    assert_eq!(early.inventory, units(&assets));
+}
+
+#[test]
+fn overflowing_conversion_has_no_result() {
+    let directives = parse("2021-01-01 price AAA  10000000000000000000 BBB\n")
+        .unwrap()
+        .directives;
+    let prices = PriceMap::from_directives(&directives);
+    let amount = Amount {
+        num: dec("10000000000000000000"),
+        currency: "AAA".to_owned(),
+    };
+    assert_eq!(convert_amount(&amount, "BBB", &prices, None), None);
+}
@@ -0,0 +1,150 @@
+use beancount_core::Directive;
+use beancount_parser::cst::{self, Node, NodeKind};
+use beancount_parser::parse;
+use indoc::indoc;
+
+const LEDGER: &str = indoc! {r#"
+    ;; -*- mode: beancount -*-
+    option "title" "Example"   ; the title
+
+    * Accounts
+    ** Bank
+
+    2014-05-01 open Assets:Cash      USD  ; wallet
+    2014-05-01 open Expenses:Food
+      description: "Food; and drinks"
+
+    pushtag #trip
+    2014-05-05 * "Cafe Mogador" "Lamb tagine"   ; dinner
+      ; paid cash
+      Expenses:Food                  37.45 USD   ; tip included
+      Assets:Cash                   -37.45 USD
+    poptag #trip
+
+    ; end of file
+"#};
+
+fn kinds(tree: &cst::Cst) -> Vec<&'static str> {
+    tree.nodes()
+        .iter()
+        .map(|node| match node.kind() {
+            NodeKind::Directive(_) => "directive",
+            NodeKind::Pragma => "pragma",
+            NodeKind::OrgModeTitle => "title",
+            NodeKind::Comment => "comment",
+            NodeKind::Blank => "blank",
+        })
+        .collect()
+}
+
+#[test]
+fn round_trip_is_lossless() {
+    let tree = cst::parse(LEDGER).unwrap();
+    assert_eq!(tree.to_string(), LEDGER);
+    assert_eq!(
+        kinds(&tree),
+        [
+            "comment",
+            "directive",
+            "blank",
+            "title",
+            "title",
+            "blank",
+            "directive",
+            "directive",
+            "blank",
+            "pragma",
+            "directive",
+            "pragma",
+            "blank",
+            "comment"
+        ]
+    );
+
+    for input in [
+        "",
+        "\n\n",
+        "2014-05-01 open Assets:Cash\r\n\r\n; windows\r\n",
+        "2014-05-01 open Assets:Cash\n; no final newline",
+    ] {
+        assert_eq!(cst::parse(input).unwrap().to_string(), input);
+    }
+}
+
+#[test]
+fn converts_to_ledger() {
+    let tree = cst::parse(LEDGER).unwrap();
+    let mut ledger = parse(LEDGER).unwrap();
+    ledger
+        .directives
+        .retain(|directive| !matches!(directive, Directive::Unsupported));
+    assert_eq!(tree.to_ledger(), ledger);
+}
+
+#[test]
+fn lines_and_comments() {
+    let tree = cst::parse(LEDGER).unwrap();
+    let txn = &tree.nodes()[10];
+    let comments: Vec<_> = txn.lines().filter_map(|line| line.comment()).collect();
+    assert_eq!(comments, ["; dinner", "; paid cash", "; tip included"]);
+    let posting = txn.lines().nth(2).unwrap();
+    assert_eq!(posting.code(), "  Expenses:Food                  37.45 USD");
+    assert_eq!(posting.eol(), "\n");
+
+    let open = &tree.nodes()[7];
+    assert_eq!(open.lines().nth(1).unwrap().comment(), None);
+}
+
+#[test]
+fn edited_directives_keep_comments() {
+    let mut tree = cst::parse(LEDGER).unwrap();
+    let mut txn = match tree.nodes()[10].directive() {
+        Some(Directive::Transaction(txn)) => txn.clone(),
+        other => panic!("unexpected node {:?}", other),
+    };
+    txn.narration = "Couscous".to_string();
+    txn.postings.reverse();
+    tree.set_directive(10, Directive::Transaction(txn));
+
+    let text = tree.nodes()[10].text();
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].contains("\"Couscous\""));
+    assert!(lines[0].ends_with("   ; dinner"));
+    assert_eq!(lines[1], "  ; paid cash");
+    assert!(lines[2].contains("Assets:Cash"));
+    assert!(!lines[2].contains(';'));
+    assert!(lines[3].contains("Expenses:Food"));
+    assert!(lines[3].ends_with("   ; tip included"));
+
+    // The rest of the file is untouched.
+    let before = cst::parse(LEDGER).unwrap();
+    for (i, (a, b)) in before.nodes().iter().zip(tree.nodes()).enumerate() {
+        if i != 10 {
+            assert_eq!(a, b);
+        }
+    }
+
+    let reparsed = cst::parse(&tree.to_string()).unwrap();
+    match reparsed.nodes()[10].directive() {
+        Some(Directive::Transaction(txn)) => assert_eq!(txn.narration, "Couscous"),
+        other => panic!("unexpected node {:?}", other),
+    }
+}
+
+#[test]
+fn insert_and_remove_nodes() {
+    let mut tree = cst::parse(LEDGER).unwrap();
+    let close = parse("2015-01-01 close Assets:Cash\n")
+        .unwrap()
+        .directives
+        .remove(0);
+    tree.remove(13);
+    tree.insert(13, Node::comment("; closed accounts"));
+    tree.insert(14, Node::from_directive(close));
+    tree.insert(15, Node::blank());
+    assert!(tree
+        .to_string()
+        .ends_with("poptag #trip\n\n; closed accounts\n2015-01-01 close Assets:Cash\n\n"));
+    assert_eq!(tree.directives().count(), 5);
+}
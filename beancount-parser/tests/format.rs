@@ -0,0 +1,87 @@
+use beancount_parser::format::{format, Alignment, FormatOptions};
+use beancount_parser::parse;
+use indoc::indoc;
+
+const LEDGER: &str = indoc! {r#"
+    ; Investments
+    2014-05-01 open Assets:Broker   HOOL
+
+    2014-05-05 * "Buy" #invest
+    	Assets:Broker    10 HOOL {  502.12 USD ,2014-05-05,"lot"}  ; first lot
+       broker: "ACME"
+     Assets:Cash   -5021.20 USD
+    	Expenses:Fees
+      ; fees are elided
+    2014-06-05 ! "Sell"
+      ! Assets:Broker  -2 HOOL {}   @@1200   USD
+      Assets:Cash  (1200 - 5) USD
+      Income:Gains
+    "#};
+
+#[test]
+fn aligns_on_decimal_point() {
+    let formatted = format(LEDGER, &FormatOptions::default()).unwrap();
+    let expected = indoc! {r#"
+        ; Investments
+        2014-05-01 open Assets:Broker   HOOL
+
+        2014-05-05 * "Buy" #invest
+          Assets:Broker            10    HOOL {502.12 USD, 2014-05-05, "lot"}  ; first lot
+            broker: "ACME"
+          Assets:Cash           -5021.20 USD
+          Expenses:Fees
+          ; fees are elided
+        2014-06-05 ! "Sell"
+          ! Assets:Broker          -2    HOOL {} @@ 1200 USD
+          Assets:Cash      (1200 - 5)    USD
+          Income:Gains
+    "#};
+    assert_eq!(formatted, expected);
+    assert_eq!(parse(&formatted).unwrap().directives.len(), 3);
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    let options = FormatOptions::default();
+    let formatted = format(LEDGER, &options).unwrap();
+    assert_eq!(format(&formatted, &options).unwrap(), formatted);
+}
+
+#[test]
+fn currency_column_and_layout_options() {
+    let input = indoc! {r#"
+        2014-05-05 * "Salary"
+          Assets:Checking   1234567.5 USD
+          Income:Salary  -1,234,567.50 USD
+    "#};
+
+    let options = FormatOptions {
+        alignment: Alignment::CurrencyColumn(40),
+        indent: 4,
+        thousands_separator: true,
+        ..Default::default()
+    };
+    let expected = indoc! {r#"
+        2014-05-05 * "Salary"
+            Assets:Checking         1,234,567.5 USD
+            Income:Salary         -1,234,567.50 USD
+    "#};
+    assert_eq!(format(input, &options).unwrap(), expected);
+
+    let options = FormatOptions {
+        prefix_width: Some(24),
+        num_width: Some(14),
+        ..Default::default()
+    };
+    let expected = indoc! {r#"
+        2014-05-05 * "Salary"
+          Assets:Checking             1234567.5  USD
+          Income:Salary              -1234567.50 USD
+    "#};
+    assert_eq!(format(input, &options).unwrap(), expected);
+}
+
+#[test]
+fn syntax_errors_are_reported() {
+    assert!(format("2014-05-05 * \"Buy\n", &FormatOptions::default()).is_err());
+}
@@ -0,0 +1,215 @@
+use beancount_core::booking::book;
+use beancount_core::interpolate::{complete_transaction, InterpolationErrorKind};
+use beancount_core::{Amount, Booking, Directive, PriceSpec, Transaction};
+use beancount_parser::parse;
+use indoc::indoc;
+use rust_decimal::Decimal;
+
+fn transaction(s: &str) -> Transaction {
+    match parse(s).unwrap().directives.into_iter().next() {
+        Some(Directive::Transaction(txn)) => txn,
+        other => panic!("expected a transaction, got {:?}", other),
+    }
+}
+
+fn complete(s: &str) -> Result<Transaction, InterpolationErrorKind> {
+    complete_transaction(&transaction(s))
+}
+
+fn units(txn: &Transaction) -> Vec<(Decimal, String)> {
+    txn.postings
+        .iter()
+        .map(|p| (p.units.num.unwrap(), p.units.currency.clone().unwrap()))
+        .collect()
+}
+
+fn amount(num: &str, currency: &str) -> (Decimal, String) {
+    (num.parse().unwrap(), currency.to_string())
+}
+
+#[test]
+fn fills_in_elided_posting() {
+    let txn = complete(indoc! {r#"
+        2020-01-01 * "Groceries"
+          Expenses:Food      12.34 USD
+          Expenses:Drinks     5.00 USD
+          Assets:Cash
+    "#})
+    .unwrap();
+    assert_eq!(
+        units(&txn),
+        vec![
+            amount("12.34", "USD"),
+            amount("5.00", "USD"),
+            amount("-17.34", "USD")
+        ]
+    );
+}
+
+#[test]
+fn splits_auto_posting_per_currency() {
+    let txn = complete(indoc! {r#"
+        2020-01-01 * "Trip"
+          Expenses:Food      12.00 USD
+          Expenses:Hotel     80.00 EUR
+          Assets:Cash
+    "#})
+    .unwrap();
+    assert_eq!(txn.postings.len(), 4);
+    let mut filled = units(&txn)[2..].to_vec();
+    filled.sort();
+    assert_eq!(
+        filled,
+        vec![amount("-80.00", "EUR"), amount("-12.00", "USD")]
+    );
+    assert!(txn.postings[2..]
+        .iter()
+        .all(|p| p.account.parts == vec!["Cash".to_string()]));
+}
+
+#[test]
+fn interpolates_with_cost_and_price() {
+    let txn = complete(indoc! {r#"
+        2020-01-01 * "Buy"
+          Assets:Brokerage    10 HOOL {500.00 USD}
+          Assets:Cash
+    "#})
+    .unwrap();
+    assert_eq!(units(&txn)[1], amount("-5000.00", "USD"));
+
+    let txn = complete(indoc! {r#"
+        2020-01-01 * "Exchange"
+          Assets:Checking   -400.00 USD @ 1.09 CAD
+          Assets:Savings
+    "#})
+    .unwrap();
+    assert_eq!(units(&txn)[1], amount("436.00", "CAD"));
+
+    let txn = complete(indoc! {r#"
+        2020-01-01 * "Exchange"
+          Assets:Checking   -400.00 USD @@ 436.01 CAD
+          Assets:Savings
+    "#})
+    .unwrap();
+    assert_eq!(units(&txn)[1], amount("436.01", "CAD"));
+}
+
+#[test]
+fn solves_missing_cost_and_price_numbers() {
+    let txn = complete(indoc! {r#"
+        2020-01-01 * "Buy"
+          Assets:Brokerage    10 HOOL {USD}
+          Assets:Cash     -5000.00 USD
+    "#})
+    .unwrap();
+    let cost = txn.postings[0].cost.as_ref().unwrap();
+    assert_eq!(cost.number_per, Some(500.into()));
+
+    let txn = complete(indoc! {r#"
+        2020-01-01 * "Exchange"
+          Assets:Checking   -400.00 USD @ CAD
+          Assets:Savings     436.00 CAD
+    "#})
+    .unwrap();
+    match &txn.postings[0].price {
+        Some(PriceSpec::PerUnit(price)) => {
+            assert_eq!(price.num, Some("1.09".parse().unwrap()));
+            assert_eq!(price.currency.as_deref(), Some("CAD"));
+        }
+        other => panic!("unexpected price {:?}", other),
+    }
+
+    let txn = complete(indoc! {r#"
+        2020-01-01 * "Exchange"
+          Assets:Checking    USD @ 1.25 CAD
+          Assets:Savings     500.00 CAD
+    "#})
+    .unwrap();
+    assert_eq!(units(&txn)[0], amount("-400", "USD"));
+}
+
+#[test]
+fn infers_missing_currencies() {
+    let txn = complete(indoc! {r#"
+        2020-01-01 * "Buy"
+          Assets:Brokerage    10 HOOL {500.00}
+          Assets:Cash     -5000.00 USD
+    "#})
+    .unwrap();
+    assert_eq!(
+        txn.postings[0].cost.as_ref().unwrap().currency.as_deref(),
+        Some("USD")
+    );
+
+    let txn = complete(indoc! {r#"
+        2020-01-01 * "Buy"
+          Assets:Brokerage    10 HOOL {500.00} @ 510.00 USD
+          Assets:Cash
+    "#})
+    .unwrap();
+    assert_eq!(
+        txn.postings[0].cost.as_ref().unwrap().currency.as_deref(),
+        Some("USD")
+    );
+
+    let err = complete(indoc! {r#"
+        2020-01-01 * "Buy"
+          Assets:Brokerage    10 HOOL {500.00}
+          Assets:Cash     -5000.00 USD
+          Assets:Other     -100.00 EUR
+          Assets:Other      100.00 EUR
+    "#})
+    .unwrap_err();
+    assert_eq!(
+        err,
+        InterpolationErrorKind::UnresolvedCurrency { posting: 0 }
+    );
+}
+
+#[test]
+fn rejects_under_determined_transactions() {
+    let err = complete(indoc! {r#"
+        2020-01-01 * "Groceries"
+          Expenses:Food
+          Assets:Cash
+    "#})
+    .unwrap_err();
+    assert_eq!(err, InterpolationErrorKind::MultipleAutoPostings);
+
+    let err = complete(indoc! {r#"
+        2020-01-01 * "Buy"
+          Assets:Brokerage    10 HOOL {USD}
+          Assets:Cash
+    "#})
+    .unwrap_err();
+    assert_eq!(
+        err,
+        InterpolationErrorKind::TooManyMissingNumbers { count: 2 }
+    );
+}
+
+#[test]
+fn booking_completes_reductions() {
+    let source = indoc! {r#"
+        2020-01-05 * "Buy"
+          Assets:Brokerage    10 HOOL {USD}
+          Assets:Cash     -5000.00 USD
+
+        2020-03-01 * "Sell"
+          Assets:Brokerage    -4 HOOL {}
+          Assets:Cash
+    "#};
+    let (directives, errors) = book(parse(source).unwrap().directives, Booking::Strict);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let sell = match &directives[1] {
+        Directive::Transaction(txn) => txn,
+        other => panic!("unexpected directive {:?}", other),
+    };
+    assert_eq!(
+        sell.postings[1].weight(),
+        Some(Amount {
+            num: 2000.into(),
+            currency: "USD".into()
+        })
+    );
+}
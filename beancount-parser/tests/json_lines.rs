@@ -0,0 +1,104 @@
+use beancount_core::render::json::JsonLinesRenderer;
+use beancount_core::render::Renderer;
+use beancount_parser::parse;
+use indoc::indoc;
+use serde_json::{json, Value};
+
+const LEDGER: &str = indoc! {r#"
+    option "title" "Example"
+    2014-01-01 open Assets:Broker HOOL,USD "FIFO"
+    2014-01-01 open Assets:Cash
+    2014-05-05 * "Broker" "Buy" #invest ^trade-1
+      receipt: "r-1.pdf"
+      Assets:Broker      10 HOOL {502.12 USD, "lot"} @@ 5100 USD
+        confirmed: TRUE
+      ! Assets:Cash   -5021.20 USD
+      Expenses:Fees
+    2014-06-01 balance Assets:Broker 10 ~ 0.01 HOOL
+    2014-06-01 event "location" "Paris"
+    2014-06-01 custom "budget" Expenses:Food "monthly" 400.00 USD
+    "#};
+
+fn lines(renderer: &JsonLinesRenderer) -> Vec<Value> {
+    let mut out = Vec::new();
+    renderer.render(&parse(LEDGER).unwrap(), &mut out).unwrap();
+    String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn one_object_per_directive() {
+    let lines = lines(&JsonLinesRenderer::new());
+    let types: Vec<_> = lines.iter().map(|line| line["_type"].clone()).collect();
+    assert_eq!(
+        types,
+        ["Open", "Open", "Transaction", "Balance", "Event", "Custom"]
+    );
+
+    assert_eq!(lines[0]["currencies"], json!(["HOOL", "USD"]));
+    assert_eq!(lines[0]["booking"], "FIFO");
+    assert_eq!(
+        lines[0]["meta"],
+        json!({"filename": "<string>", "lineno": 2})
+    );
+    assert_eq!(lines[1]["currencies"], Value::Null);
+    assert_eq!(lines[1]["booking"], Value::Null);
+
+    assert_eq!(
+        lines[3]["amount"],
+        json!({"number": "10", "currency": "HOOL"})
+    );
+    assert_eq!(lines[3]["tolerance"], "0.01");
+    assert_eq!(lines[4]["type"], "location");
+    assert_eq!(
+        lines[5]["values"],
+        json!([
+            {"value": "Expenses:Food", "dtype": "<AccountDummy>"},
+            {"value": "monthly", "dtype": "str"},
+            {"value": {"number": "400.00", "currency": "USD"}, "dtype": "Amount"}
+        ])
+    );
+}
+
+#[test]
+fn transactions_mirror_the_python_data_model() {
+    let lines = lines(&JsonLinesRenderer::with_filenames(["main.beancount"]));
+    let txn = &lines[2];
+    assert_eq!(txn["date"], "2014-05-05");
+    assert_eq!(txn["flag"], "*");
+    assert_eq!(txn["payee"], "Broker");
+    assert_eq!(txn["narration"], "Buy");
+    assert_eq!(txn["tags"], json!(["invest"]));
+    assert_eq!(txn["links"], json!(["trade-1"]));
+    assert_eq!(
+        txn["meta"],
+        json!({"filename": "main.beancount", "lineno": 4, "receipt": "r-1.pdf"})
+    );
+
+    let postings = txn["postings"].as_array().unwrap();
+    assert_eq!(
+        postings[0],
+        json!({
+            "account": "Assets:Broker",
+            "units": {"number": "10", "currency": "HOOL"},
+            "cost": {
+                "number_per": "502.12",
+                "number_total": null,
+                "currency": "USD",
+                "date": null,
+                "label": "lot",
+                "merge": false
+            },
+            "price": {"number": "510", "currency": "USD"},
+            "flag": null,
+            "meta": {"filename": "main.beancount", "lineno": 6, "confirmed": true}
+        })
+    );
+    assert_eq!(postings[1]["flag"], "!");
+    assert_eq!(postings[1]["units"]["number"], "-5021.20");
+    assert_eq!(postings[2]["units"], Value::Null);
+    assert_eq!(postings[2]["cost"], Value::Null);
+}
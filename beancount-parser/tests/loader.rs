@@ -0,0 +1,98 @@
+use std::path::{Path, PathBuf};
+
+use beancount_core::Directive;
+use beancount_parser::loader::{load, LoadError};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/loader")
+        .join(name)
+}
+
+fn file_name(path: &Path) -> String {
+    let dir = path
+        .parent()
+        .unwrap()
+        .file_name()
+        .unwrap()
+        .to_string_lossy();
+    format!("{}/{}", dir, path.file_name().unwrap().to_string_lossy())
+}
+
+#[test]
+fn resolves_includes_in_place() {
+    let loaded = load(fixture("main.beancount")).unwrap();
+    let files: Vec<_> = loaded.files.iter().map(|p| file_name(p)).collect();
+    assert_eq!(
+        files,
+        vec![
+            "loader/main.beancount",
+            "loader/accounts.beancount",
+            "years/2020.beancount",
+            "years/2021.beancount",
+        ]
+    );
+
+    let directives: Vec<_> = loaded
+        .directives_with_paths()
+        .map(|(directive, path)| {
+            let kind = match directive {
+                Directive::Option(_) => "option".to_string(),
+                Directive::Open(_) => "open".to_string(),
+                Directive::Transaction(txn) => txn.narration.clone(),
+                other => panic!("unexpected directive {:?}", other),
+            };
+            (kind, file_name(path))
+        })
+        .collect();
+    let expected = [
+        ("option", "loader/main.beancount"),
+        ("open", "loader/accounts.beancount"),
+        ("open", "loader/accounts.beancount"),
+        ("Lunch", "years/2020.beancount"),
+        ("Dinner", "years/2021.beancount"),
+        ("After includes", "loader/main.beancount"),
+    ];
+    let expected: Vec<_> = expected
+        .iter()
+        .map(|(kind, file)| (kind.to_string(), file.to_string()))
+        .collect();
+    assert_eq!(directives, expected);
+
+    let last = loaded.ledger.directives.last().unwrap().location().unwrap();
+    assert_eq!(last.file, 0);
+    assert_eq!(last.line, 6);
+}
+
+#[test]
+fn detects_include_cycles() {
+    let err = load(fixture("cycle/a.beancount")).unwrap_err();
+    match &err {
+        LoadError::IncludeCycle { cycle } => {
+            let cycle: Vec<_> = cycle.iter().map(|p| file_name(p)).collect();
+            assert_eq!(
+                cycle,
+                vec![
+                    "cycle/a.beancount",
+                    "cycle/b.beancount",
+                    "cycle/a.beancount"
+                ]
+            );
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert!(err.to_string().starts_with("include cycle: "));
+}
+
+#[test]
+fn reports_missing_files() {
+    let err = load(fixture("missing.beancount")).unwrap_err();
+    assert!(
+        matches!(&err, LoadError::NoMatch { pattern, .. } if pattern == "nothing/*.beancount"),
+        "{:?}",
+        err
+    );
+
+    let err = load(fixture("does-not-exist.beancount")).unwrap_err();
+    assert!(matches!(err, LoadError::Io { .. }), "{:?}", err);
+}
@@ -0,0 +1,68 @@
+use beancount_core::{Directive, Location};
+use beancount_parser::{parse, parse_file};
+use indoc::indoc;
+
+const SOURCE: &str = indoc! {r#"
+    2020-01-01 open Assets:Cash
+      opened-by: "me"
+
+    2020-01-05 * "Groceries"
+      receipt: "1234"
+      Expenses:Food      12.34 USD
+        category: "food"
+      Assets:Cash
+    "#};
+
+fn line_col(location: &Location) -> (usize, usize) {
+    (location.line, location.column)
+}
+
+#[test]
+fn directives_and_metadata() {
+    let ledger = parse(SOURCE).unwrap();
+    let open = ledger.directives[0].location().unwrap();
+    assert_eq!(line_col(open), (1, 1));
+    assert_eq!(
+        &SOURCE[open.span.clone()],
+        "2020-01-01 open Assets:Cash\n  opened-by: \"me\"\n"
+    );
+    let entry = &open.meta["opened-by"];
+    assert_eq!(line_col(entry), (2, 3));
+    assert_eq!(&SOURCE[entry.span.clone()], "opened-by: \"me\"");
+    assert!(entry.meta.is_empty());
+}
+
+#[test]
+fn transactions_and_postings() {
+    let ledger = parse(SOURCE).unwrap();
+    let txn = match &ledger.directives[1] {
+        Directive::Transaction(txn) => txn,
+        other => panic!("unexpected directive {:?}", other),
+    };
+    let location = txn.location.as_ref().unwrap();
+    assert_eq!(line_col(location), (4, 1));
+    assert_eq!(line_col(&location.meta["receipt"]), (5, 3));
+    assert!(!location.meta.contains_key("category"));
+
+    let food = txn.postings[0].location.as_ref().unwrap();
+    assert_eq!(line_col(food), (6, 3));
+    assert_eq!(&SOURCE[food.span.clone()], "Expenses:Food      12.34 USD");
+    assert_eq!(line_col(&food.meta["category"]), (7, 5));
+
+    let cash = txn.postings[1].location.as_ref().unwrap();
+    assert_eq!(line_col(cash), (8, 3));
+    assert!(cash.meta.is_empty());
+}
+
+#[test]
+fn file_ids() {
+    let ledger = parse_file(SOURCE, 3).unwrap();
+    let txn = match &ledger.directives[1] {
+        Directive::Transaction(txn) => txn,
+        other => panic!("unexpected directive {:?}", other),
+    };
+    let location = txn.location.as_ref().unwrap();
+    assert_eq!(location.file, 3);
+    assert_eq!(location.meta["receipt"].file, 3);
+    assert_eq!(txn.postings[0].location.as_ref().unwrap().file, 3);
+}
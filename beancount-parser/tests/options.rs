@@ -0,0 +1,103 @@
+use beancount_core::options::{OptionErrorKind, Options, PluginProcessingMode};
+use beancount_core::{AccountType, Booking};
+use beancount_parser::parse;
+use indoc::indoc;
+use rust_decimal::Decimal;
+
+#[test]
+fn defaults() {
+    let (options, errors) = Options::from_directives(&[]);
+    assert!(errors.is_empty());
+    assert_eq!(options, Options::default());
+    assert_eq!(options.title, "Beancount");
+    assert_eq!(options.booking_method, Booking::Strict);
+    assert_eq!(options.conversion_currency, "NOTHING");
+    assert_eq!(options.account_previous_balances, "Opening-Balances");
+    assert_eq!(options.inferred_tolerance_multiplier, Decimal::new(5, 1));
+    assert_eq!(options.long_string_maxlines, 64);
+}
+
+#[test]
+fn typed_values() {
+    let source = indoc! {r#"
+        option "title" "Personal ledger"
+        option "name_assets" "Activa"
+        option "operating_currency" "USD"
+        option "operating_currency" "EUR"
+        option "booking_method" "FIFO"
+        option "inferred_tolerance_default" "USD:0.003"
+        option "inferred_tolerance_default" "*:0.001"
+        option "inferred_tolerance_multiplier" "0.6"
+        option "infer_tolerance_from_cost" "TRUE"
+        option "render_commas" "1"
+        option "account_previous_balances" "Opening"
+        option "account_rounding" "Rounding"
+        option "conversion_currency" "NOTHING2"
+        option "plugin_processing_mode" "raw"
+        option "documents" "receipts"
+        option "long_string_maxlines" "100"
+    "#};
+    let (options, errors) = Options::from_directives(&parse(source).unwrap().directives);
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(options.title, "Personal ledger");
+    assert_eq!(options.root_names.name(AccountType::Assets), "Activa");
+    assert_eq!(options.operating_currency, ["USD", "EUR"]);
+    assert_eq!(options.booking_method, Booking::Fifo);
+    assert!(options.infer_tolerance_from_cost);
+    assert!(options.render_commas);
+    assert_eq!(options.account_previous_balances, "Opening");
+    assert_eq!(options.account_rounding.as_deref(), Some("Rounding"));
+    assert_eq!(options.conversion_currency, "NOTHING2");
+    assert_eq!(options.plugin_processing_mode, PluginProcessingMode::Raw);
+    assert_eq!(options.documents, ["receipts"]);
+    assert_eq!(options.long_string_maxlines, 100);
+
+    let tolerances = options.tolerance_options();
+    assert_eq!(tolerances.multiplier, Decimal::new(6, 1));
+    assert_eq!(tolerances.default_for("USD"), Decimal::new(3, 3));
+    assert_eq!(tolerances.default_for("EUR"), Decimal::new(1, 3));
+}
+
+#[test]
+fn errors() {
+    let source = indoc! {r#"
+        option "booking_method" "FIFO"
+        option "booking_method" "fifo"
+        option "render_commas" "maybe"
+        option "inferred_tolerance_default" "0.01"
+        option "name_income" "Revenue:Sales"
+        option "colour" "blue"
+        option "tolerance" "0.01"
+        option "filename" "other.beancount"
+    "#};
+    let (options, errors) = Options::from_directives(&parse(source).unwrap().directives);
+    assert_eq!(options.booking_method, Booking::Fifo);
+    assert!(!options.render_commas);
+    assert!(options.inferred_tolerance_default.is_empty());
+    assert_eq!(options.root_names.name(AccountType::Income), "Income");
+
+    let names: Vec<_> = errors.iter().map(|e| e.option.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "booking_method",
+            "render_commas",
+            "inferred_tolerance_default",
+            "name_income",
+            "colour",
+            "tolerance",
+            "filename"
+        ]
+    );
+    assert!(matches!(
+        errors[0].kind,
+        OptionErrorKind::InvalidValue { .. }
+    ));
+    assert_eq!(errors[4].kind, OptionErrorKind::Unknown);
+    assert_eq!(errors[5].kind, OptionErrorKind::Deprecated);
+    assert_eq!(errors[6].kind, OptionErrorKind::ReadOnly);
+    assert_eq!(
+        errors[1].to_string(),
+        "option 'render_commas': invalid value 'maybe': expected TRUE or FALSE"
+    );
+}
@@ -0,0 +1,122 @@
+use beancount_core::booking::book;
+use beancount_core::interpolate::ToleranceOptions;
+use beancount_core::ops::balance::validate_balances;
+use beancount_core::ops::pad::{expand_pads, PadError};
+use beancount_core::{Directive, Flag, Transaction};
+use beancount_parser::parse;
+use indoc::indoc;
+use rust_decimal::Decimal;
+
+fn expand(source: &str) -> (Vec<Directive>, Vec<PadError>) {
+    let directives = parse(source).unwrap().directives;
+    let options = ToleranceOptions::from_directives(&directives);
+    let (directives, errors) = book(directives, Default::default());
+    assert!(errors.is_empty(), "{:?}", errors);
+    let (directives, errors) = expand_pads(directives, &options);
+    assert!(validate_balances(&directives, &options).is_empty());
+    (directives, errors)
+}
+
+fn padding(directives: &[Directive]) -> Vec<&Transaction> {
+    directives
+        .iter()
+        .filter_map(|d| match d {
+            Directive::Transaction(txn) if txn.flag == Flag::Padding => Some(txn),
+            _ => None,
+        })
+        .collect()
+}
+
+fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+#[test]
+fn pads_opening_balance() {
+    let (directives, errors) = expand(indoc! {r#"
+        2020-01-01 open Assets:Checking
+        2020-01-01 open Equity:Opening-Balances
+
+        2020-01-01 pad Assets:Checking Equity:Opening-Balances
+
+        2020-01-05 * "Coffee"
+          Assets:Checking     -3.50 USD
+          Expenses:Coffee
+
+        2020-02-01 balance Assets:Checking   1000.00 USD
+    "#});
+    assert!(errors.is_empty(), "{:?}", errors);
+    let padding = padding(&directives);
+    assert_eq!(padding.len(), 1);
+    let txn = padding[0];
+    assert_eq!(txn.date.to_string(), "2020-01-01");
+    assert_eq!(txn.postings[0].units.num, Some(dec("1003.50")));
+    assert_eq!(txn.postings[1].units.num, Some(dec("-1003.50")));
+    assert_eq!(
+        txn.narration,
+        "(Padding inserted for Balance of 1000.00 USD for difference 1003.50 USD)"
+    );
+    assert!(matches!(directives[3], Directive::Transaction(ref t) if t.flag == Flag::Padding));
+}
+
+#[test]
+fn pads_each_currency_once() {
+    let (directives, errors) = expand(indoc! {r#"
+        2020-01-01 pad Assets:Wallet Equity:Opening-Balances
+
+        2020-02-01 balance Assets:Wallet   100.00 USD
+        2020-02-01 balance Assets:Wallet    50.00 EUR
+        2020-02-01 balance Assets:Wallet    50.00 EUR
+    "#});
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(padding(&directives).len(), 2);
+}
+
+#[test]
+fn holding_balance_does_not_use_up_pad() {
+    // The padding is dated at the pad, so the first assertion fails once it is inserted, as in
+    // Beancount; expand without checking the balances.
+    let directives = parse(indoc! {r#"
+        2020-01-01 pad Assets:Wallet Equity:Opening-Balances
+        2020-01-15 balance Assets:Wallet     0.00 USD
+
+        2020-02-01 balance Assets:Wallet   100.00 USD
+    "#})
+    .unwrap()
+    .directives;
+    let (directives, errors) = expand_pads(directives, &ToleranceOptions::default());
+    assert!(errors.is_empty(), "{:?}", errors);
+    let padding = padding(&directives);
+    assert_eq!(padding.len(), 1);
+    assert_eq!(padding[0].postings[0].units.num, Some(dec("100.00")));
+}
+
+#[test]
+fn later_pad_replaces_earlier_one() {
+    let (directives, errors) = expand(indoc! {r#"
+        2020-01-01 pad Assets:Wallet Equity:Opening-Balances
+        2020-02-01 balance Assets:Wallet   100.00 USD
+
+        2020-03-01 pad Assets:Wallet Expenses:Unknown
+        2020-04-01 balance Assets:Wallet    80.00 USD
+    "#});
+    assert!(errors.is_empty(), "{:?}", errors);
+    let padding = padding(&directives);
+    assert_eq!(padding.len(), 2);
+    assert_eq!(padding[1].postings[0].units.num, Some(dec("-20.00")));
+    assert_eq!(padding[1].date.to_string(), "2020-03-01");
+}
+
+#[test]
+fn reports_unused_pads() {
+    let (directives, errors) = expand(indoc! {r#"
+        2020-01-01 pad Assets:Wallet Equity:Opening-Balances
+        2020-01-02 pad Assets:Wallet Equity:Opening-Balances
+
+        2020-02-01 balance Assets:Wallet   100.00 USD
+    "#});
+    assert_eq!(padding(&directives).len(), 1);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].pad.date.to_string(), "2020-01-01");
+    assert_eq!(errors[0].to_string(), "unused pad entry for Assets:Wallet");
+}
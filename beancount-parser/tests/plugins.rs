@@ -0,0 +1,114 @@
+use std::path::Path;
+
+use beancount_core::plugins::{PluginError, PluginRegistry};
+use beancount_core::{BcOption, Directive};
+use beancount_parser::loader::load;
+use beancount_parser::parse;
+use indoc::indoc;
+
+/// Adds the tag given as configuration to every transaction.
+fn tag_all(
+    directives: Vec<Directive>,
+    _: &[BcOption],
+    config: Option<&str>,
+) -> (Vec<Directive>, Vec<PluginError>) {
+    let tag = match config {
+        Some(tag) => tag.to_string(),
+        None => return (directives, vec![PluginError::new("missing tag")]),
+    };
+    let directives = directives
+        .into_iter()
+        .map(|directive| match directive {
+            Directive::Transaction(mut txn) => {
+                txn.tags.insert(tag.clone());
+                Directive::Transaction(txn)
+            }
+            other => other,
+        })
+        .collect();
+    (directives, Vec::new())
+}
+
+/// Reports the number of directives and options it receives as an error.
+fn count(
+    directives: Vec<Directive>,
+    options: &[BcOption],
+    _: Option<&str>,
+) -> (Vec<Directive>, Vec<PluginError>) {
+    let message = format!("{} directives, {} options", directives.len(), options.len());
+    (directives, vec![PluginError::new(message)])
+}
+
+fn registry() -> PluginRegistry {
+    let mut registry = PluginRegistry::new();
+    registry.register("tag_all", tag_all);
+    registry.register("count", count);
+    registry
+}
+
+fn tags(directives: &[Directive]) -> Vec<Vec<String>> {
+    directives
+        .iter()
+        .filter_map(|d| match d {
+            Directive::Transaction(txn) => {
+                let mut tags: Vec<_> = txn.tags.iter().cloned().collect();
+                tags.sort();
+                Some(tags)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn runs_plugins_in_order() {
+    let source = indoc! {r#"
+        plugin "count"
+        plugin "tag_all" "first"
+        plugin "tag_all"
+        plugin "tag_all" "second"
+
+        2020-01-02 * "Lunch"
+          Expenses:Food      12.00 USD
+          Assets:Cash
+    "#};
+    let (directives, errors) = registry().run(parse(source).unwrap().directives);
+    assert_eq!(tags(&directives), [["first", "second"]]);
+    let messages: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        ["count: 5 directives, 0 options", "tag_all: missing tag"]
+    );
+}
+
+#[test]
+fn reports_unknown_plugins() {
+    let source = indoc! {r#"
+        plugin "beancount.plugins.unknown"
+        plugin "tag_all" "tagged"
+
+        2020-01-02 * "Lunch"
+          Expenses:Food      12.00 USD
+          Assets:Cash
+    "#};
+    let (directives, errors) = registry().run(parse(source).unwrap().directives);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].module, "beancount.plugins.unknown");
+    assert!(matches!(errors[0].directive, Some(Directive::Plugin(_))));
+    assert_eq!(
+        errors[0].to_string(),
+        "beancount.plugins.unknown: no plugin registered for module 'beancount.plugins.unknown'"
+    );
+    assert_eq!(tags(&directives), [["tagged"]]);
+}
+
+#[test]
+fn loader_runs_plugins() {
+    let path =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/loader/plugins.beancount");
+    let mut loaded = load(path).unwrap();
+    let errors = loaded.run_plugins(&registry());
+    let messages: Vec<_> = errors.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(messages, ["6 directives, 1 options"]);
+    assert_eq!(tags(&loaded.ledger.directives), [["imported"]]);
+}
@@ -0,0 +1,115 @@
+use beancount_core::booking::book;
+use beancount_core::prices::PriceMap;
+use beancount_core::{Date, Directive};
+use beancount_parser::parse;
+use indoc::indoc;
+use rust_decimal::Decimal;
+
+const LEDGER: &str = indoc! {r#"
+    2021-01-01 open Assets:Broker
+    2021-01-01 open Assets:Cash
+
+    2021-03-01 price HOOL  500.00 USD
+    2021-03-10 price HOOL  520.00 USD
+    2021-03-20 price HOOL  510.00 USD
+    2021-03-05 price EUR     1.25 USD
+
+    2021-03-12 * "Buy"
+      Assets:Broker     2 HOOL {530.00 USD}
+      Assets:Cash
+
+    2021-03-14 * "Exchange"
+      Assets:Cash     -100.00 USD @@ 90.00 CHF
+      Assets:Cash
+    "#};
+
+fn directives() -> Vec<Directive> {
+    let (directives, errors) = book(parse(LEDGER).unwrap().directives, Default::default());
+    assert!(errors.is_empty(), "{:?}", errors);
+    directives
+}
+
+fn date(s: &str) -> Date {
+    s.parse().unwrap()
+}
+
+fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+#[test]
+fn latest_price_on_or_before() {
+    let prices = PriceMap::from_directives(&directives());
+    let price = |s| prices.get_price("HOOL", "USD", date(s));
+    assert_eq!(price("2021-02-28"), None);
+    assert_eq!(
+        price("2021-03-01"),
+        Some((date("2021-03-01"), dec("500.00")))
+    );
+    assert_eq!(
+        price("2021-03-15"),
+        Some((date("2021-03-10"), dec("520.00")))
+    );
+    assert_eq!(
+        prices.get_latest_price("HOOL", "USD"),
+        Some((date("2021-03-20"), dec("510.00")))
+    );
+    assert_eq!(
+        prices
+            .get_price("HOOL", "HOOL", date("2021-01-01"))
+            .unwrap()
+            .1,
+        Decimal::ONE
+    );
+    assert_eq!(prices.prices("HOOL", "USD").len(), 3);
+}
+
+#[test]
+fn inverse_and_triangulated_prices() {
+    let prices = PriceMap::from_directives(&directives());
+    assert_eq!(
+        prices.get_price("USD", "EUR", date("2021-03-31")),
+        Some((date("2021-03-05"), dec("0.8")))
+    );
+    assert_eq!(
+        prices.get_price("HOOL", "EUR", date("2021-03-31")),
+        Some((date("2021-03-05"), dec("408")))
+    );
+    assert_eq!(prices.get_price("HOOL", "EUR", date("2021-03-04")), None);
+    assert_eq!(prices.get_price("HOOL", "CHF", date("2021-03-31")), None);
+}
+
+#[test]
+fn overflowing_triangulated_prices() {
+    let directives = parse(indoc! {r#"
+        2021-01-01 price AAA  10000000000000000000 BBB
+        2021-01-01 price BBB  10000000000000000000 CCC
+    "#})
+    .unwrap()
+    .directives;
+    let prices = PriceMap::from_directives(&directives);
+    assert_eq!(prices.get_price("AAA", "CCC", date("2021-01-31")), None);
+}
+
+#[test]
+fn implicit_prices() {
+    let directives = directives();
+    let mut prices = PriceMap::from_directives(&directives);
+    prices.add_implicit_prices(&directives);
+    assert_eq!(
+        prices.get_price("HOOL", "USD", date("2021-03-12")),
+        Some((date("2021-03-12"), dec("530.00")))
+    );
+    assert_eq!(
+        prices.get_price("USD", "CHF", date("2021-03-31")),
+        Some((date("2021-03-14"), dec("0.9")))
+    );
+    // Prices recorded later still take precedence.
+    assert_eq!(
+        prices
+            .get_price("HOOL", "USD", date("2021-03-20"))
+            .unwrap()
+            .1,
+        dec("510.00")
+    );
+}
@@ -0,0 +1,129 @@
+use beancount_core::booking::book;
+use beancount_core::realization::realize;
+use beancount_core::{Account, AccountType, Directive, Ledger};
+use beancount_parser::parse;
+use indoc::indoc;
+
+const LEDGER: &str = indoc! {r#"
+    2020-01-01 open Assets:Bank:Checking
+    2020-01-01 open Assets:Bank:Savings
+    2020-01-01 open Equity:Opening
+    2020-01-01 open Expenses:Food
+
+    2020-01-02 * "Opening balances"
+      Assets:Bank:Checking    100.00 USD
+      Assets:Bank:Savings     250.00 USD
+      Assets:Bank              10.00 EUR
+      Equity:Opening         -350.00 USD
+      Equity:Opening          -10.00 EUR
+
+    2020-01-10 * "Coffee"
+      Expenses:Food             3.50 USD
+      Assets:Bank:Checking
+
+    2020-01-11 balance Assets:Bank:Checking    96.50 USD
+    "#};
+
+fn ledger() -> Ledger {
+    let directives = parse(LEDGER).unwrap().directives;
+    let (directives, errors) = book(directives, Default::default());
+    assert!(errors.is_empty(), "{:?}", errors);
+    Ledger { directives }
+}
+
+fn account(s: &str) -> Account {
+    s.parse().unwrap()
+}
+
+fn amounts(inventory: &beancount_core::Inventory) -> Vec<String> {
+    let mut amounts: Vec<_> = inventory
+        .iter()
+        .map(|p| format!("{} {}", p.units.num, p.units.currency))
+        .collect();
+    amounts.sort();
+    amounts
+}
+
+#[test]
+fn builds_tree_of_accounts() {
+    let ledger = ledger();
+    let realization = realize(&ledger);
+    let accounts: Vec<_> = realization.iter().map(|n| n.account.to_string()).collect();
+    assert_eq!(
+        accounts,
+        [
+            "Assets",
+            "Assets:Bank",
+            "Assets:Bank:Checking",
+            "Assets:Bank:Savings",
+            "Equity",
+            "Equity:Opening",
+            "Expenses",
+            "Expenses:Food",
+        ]
+    );
+    let roots: Vec<_> = realization.roots().map(|n| n.account.ty).collect();
+    assert_eq!(
+        roots,
+        [
+            AccountType::Assets,
+            AccountType::Equity,
+            AccountType::Expenses
+        ]
+    );
+
+    let bank = realization.get(&account("Assets:Bank")).unwrap();
+    let children: Vec<_> = bank.children().map(|n| n.account.leaf()).collect();
+    assert_eq!(children, ["Checking", "Savings"]);
+    assert_eq!(
+        bank.get(&account("Assets:Bank:Savings")).unwrap().account,
+        bank.child("Savings").unwrap().account
+    );
+    assert!(bank.get(&account("Assets:Cash")).is_none());
+    assert!(realization.get(&account("Assets:Bank:Other")).is_none());
+    assert!(realization.root(AccountType::Income).is_none());
+}
+
+#[test]
+fn balances_and_totals() {
+    let ledger = ledger();
+    let realization = realize(&ledger);
+
+    let checking = realization.get(&account("Assets:Bank:Checking")).unwrap();
+    assert_eq!(amounts(&checking.balance), ["96.50 USD"]);
+    assert_eq!(checking.total, checking.balance);
+
+    let bank = realization.get(&account("Assets:Bank")).unwrap();
+    assert_eq!(amounts(&bank.balance), ["10.00 EUR"]);
+    assert_eq!(amounts(&bank.total), ["10.00 EUR", "346.50 USD"]);
+
+    let assets = realization.root(AccountType::Assets).unwrap();
+    assert!(assets.balance.is_empty());
+    assert_eq!(assets.total, bank.total);
+}
+
+#[test]
+fn postings_and_directives() {
+    let ledger = ledger();
+    let realization = realize(&ledger);
+
+    let checking = realization.get(&account("Assets:Bank:Checking")).unwrap();
+    let narrations: Vec<_> = checking
+        .postings
+        .iter()
+        .map(|p| p.transaction.narration.as_str())
+        .collect();
+    assert_eq!(narrations, ["Opening balances", "Coffee"]);
+    assert!(checking
+        .postings
+        .iter()
+        .all(|p| p.posting.account == checking.account));
+    assert!(matches!(
+        checking.directives[..],
+        [Directive::Open(_), Directive::Balance(_)]
+    ));
+
+    let bank = realization.get(&account("Assets:Bank")).unwrap();
+    assert_eq!(bank.postings.len(), 1);
+    assert!(bank.directives.is_empty());
+}
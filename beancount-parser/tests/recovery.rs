@@ -0,0 +1,113 @@
+use beancount_core::Directive;
+use beancount_parser::error::ParseErrorKind;
+use beancount_parser::{parse, parse_recovering};
+use indoc::indoc;
+
+fn narrations(directives: &[Directive]) -> Vec<&str> {
+    directives
+        .iter()
+        .filter_map(|d| match d {
+            Directive::Transaction(txn) => Some(txn.narration.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn collects_all_syntax_errors() {
+    let source = indoc! {r#"
+        2020-01-01 open Assets:Cash
+
+        2020-01-02 * "First"
+          Expenses:Food      12.00 USD
+          Assets:Cash
+
+        2020-01-03 * "Broken amount"
+          Expenses:Food      12.00 USD USD
+          Assets:Cash
+
+        2020-01-04 * "Second"
+          Expenses:Food       3.00 USD
+          Assets:Cash
+
+        this line is garbage
+        2020-01-05 opne Assets:Bank
+
+        2020-01-06 * "Third"
+          Expenses:Food       1.00 USD
+          Assets:Cash
+    "#};
+    assert!(parse(source).is_err());
+
+    let (ledger, errors) = parse_recovering(source);
+    assert_eq!(narrations(&ledger.directives), ["First", "Second", "Third"]);
+    assert_eq!(ledger.directives.len(), 4);
+    let lines: Vec<_> = errors.iter().map(|e| e.location.0).collect();
+    assert_eq!(lines, [8, 15, 16]);
+    assert!(errors
+        .iter()
+        .all(|e| matches!(e.kind, ParseErrorKind::InvalidInput { .. })));
+}
+
+#[test]
+fn keeps_locations_of_later_directives() {
+    let source = indoc! {r#"
+        2020-01-01 open Assets:Cash Assets:Bank
+        2020-01-02 open Assets:Bank
+    "#};
+    let (ledger, errors) = parse_recovering(source);
+    assert_eq!(errors.len(), 1);
+    let location = ledger.directives[0].location().unwrap();
+    assert_eq!(location.line, 2);
+    assert_eq!(
+        &source[location.span.clone()],
+        "2020-01-02 open Assets:Bank\n"
+    );
+}
+
+#[test]
+fn keeps_locations_after_non_ascii_directives() {
+    let source = indoc! {r#"
+        2020-01-01 * "Café" "5 €"
+          Expenses:Food      12.00 USD USD
+          Assets:Cash
+        2020-01-02 open Assets:Bank
+    "#};
+    let (ledger, errors) = parse_recovering(source);
+    assert_eq!(errors.len(), 1);
+    let location = ledger.directives[0].location().unwrap();
+    assert_eq!(location.line, 4);
+    assert_eq!(location.span, 78..106);
+    assert_eq!(
+        &source[location.span.clone()],
+        "2020-01-02 open Assets:Bank\n"
+    );
+}
+
+#[test]
+fn collects_semantic_errors() {
+    let source = indoc! {r#"
+        poptag #never-pushed
+        pushtag #trip
+
+        2020-01-02 * "Tagged"
+          Expenses:Food      12.00 USD
+          Assets:Cash
+    "#};
+    let (ledger, errors) = parse_recovering(source);
+    assert_eq!(narrations(&ledger.directives), ["Tagged"]);
+    let messages: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(messages.len(), 2, "{:?}", messages);
+    assert!(messages[0].contains("Attempting to pop absent tag"));
+    assert!(messages[1].contains("Unbalanced pushed tag(s): 'trip'"));
+}
+
+#[test]
+fn valid_input_has_no_errors() {
+    let source = indoc! {r#"
+        2020-01-01 open Assets:Cash
+    "#};
+    let (ledger, errors) = parse_recovering(source);
+    assert!(errors.is_empty());
+    assert_eq!(ledger, parse(source).unwrap());
+}
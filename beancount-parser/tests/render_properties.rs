@@ -0,0 +1,407 @@
+//! Property tests: rendering a ledger and parsing the output gives back the same ledger.
+
+use std::collections::HashSet;
+
+use beancount_core::metadata::{Meta, MetaValue};
+use beancount_core::render::render;
+use beancount_core::*;
+use beancount_parser::parse;
+use proptest::collection::{hash_map, hash_set, vec};
+use proptest::option;
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+
+fn text() -> impl Strategy<Value = String> {
+    prop_oneof![
+        vec(any::<char>(), 0..12).prop_map(|chars| chars.into_iter().collect()),
+        vec(
+            prop_oneof![
+                Just("\""),
+                Just("\\"),
+                Just("\\n"),
+                Just("\n"),
+                Just(";"),
+                Just("a")
+            ],
+            0..6
+        )
+        .prop_map(|parts| parts.concat()),
+    ]
+}
+
+fn date() -> impl Strategy<Value = Date> {
+    (1970..2100, 1u32..=12, 1u32..=28).prop_map(|(y, m, d)| Date::from_ymd(y, m, d).unwrap())
+}
+
+fn number() -> impl Strategy<Value = Decimal> {
+    (-10_000_000_000i64..10_000_000_000, 0u32..6).prop_map(|(n, scale)| Decimal::new(n, scale))
+}
+
+fn unsigned_number() -> impl Strategy<Value = Decimal> {
+    number().prop_map(|n| n.abs())
+}
+
+fn currency() -> impl Strategy<Value = Currency> {
+    "[A-Z][A-Z0-9'._-]{0,6}[A-Z0-9]".prop_filter("currency parsed as a bool", |c| {
+        !c.eq_ignore_ascii_case("true") && !c.eq_ignore_ascii_case("false")
+    })
+}
+
+fn account() -> impl Strategy<Value = Account> {
+    (
+        prop::sample::select(AccountType::ALL.to_vec()),
+        vec(
+            prop_oneof!["[A-Z0-9][A-Za-z0-9-]{0,6}", "[A-ZÉ][a-zé0-9]{0,5}"],
+            1..4,
+        ),
+    )
+        .prop_map(|(ty, parts)| Account { ty, parts })
+}
+
+fn amount() -> impl Strategy<Value = Amount> {
+    (number(), currency()).prop_map(|(num, currency)| Amount { num, currency })
+}
+
+fn tags() -> impl Strategy<Value = HashSet<String>> {
+    hash_set("[A-Za-z0-9_./-]{1,8}", 0..3)
+}
+
+fn meta_value() -> impl Strategy<Value = MetaValue> {
+    prop_oneof![
+        text().prop_map(MetaValue::Text),
+        account().prop_map(MetaValue::Account),
+        date().prop_map(MetaValue::Date),
+        currency().prop_map(MetaValue::Currency),
+        "[A-Za-z0-9_./-]{1,8}".prop_map(MetaValue::Tag),
+        any::<bool>().prop_map(MetaValue::Bool),
+        amount().prop_map(MetaValue::Amount),
+        number().prop_map(MetaValue::Number),
+    ]
+}
+
+fn meta() -> impl Strategy<Value = Meta> {
+    hash_map("[a-z][a-zA-Z0-9_-]{1,8}", meta_value(), 0..3)
+}
+
+/// Arguments of a `custom` directive. Numbers are unsigned, as `1 -2` is a single expression, and
+/// never followed by a bool, as `1 TRUE` is an amount.
+fn custom_args() -> impl Strategy<Value = Vec<MetaValue>> {
+    let arg = prop_oneof![
+        text().prop_map(MetaValue::Text),
+        account().prop_map(MetaValue::Account),
+        date().prop_map(MetaValue::Date),
+        any::<bool>().prop_map(MetaValue::Bool),
+        (number(), currency())
+            .prop_map(|(num, currency)| MetaValue::Amount(Amount { num, currency })),
+        number().prop_map(MetaValue::Number),
+    ];
+    vec(arg, 0..4)
+}
+
+fn flag() -> impl Strategy<Value = Flag> {
+    prop::sample::select(vec!["*", "!", "P", "S", "T", "C", "U", "R", "M"]).prop_map(Flag::from)
+}
+
+fn incomplete_amount() -> impl Strategy<Value = IncompleteAmount> {
+    (option::of(number()), option::of(currency()))
+        .prop_map(|(num, currency)| IncompleteAmount { num, currency })
+}
+
+fn cost_spec() -> impl Strategy<Value = CostSpec> {
+    (
+        option::of(number()),
+        option::of(number()),
+        option::of(currency()),
+        option::of(date()),
+        option::of(text()),
+        any::<bool>(),
+    )
+        .prop_filter(
+            "a cost with both numbers needs a currency",
+            |(per, total, currency, ..)| per.is_none() || total.is_none() || currency.is_some(),
+        )
+        .prop_map(
+            |(number_per, number_total, currency, date, label, merge_cost)| CostSpec {
+                number_per,
+                number_total,
+                currency,
+                date,
+                label,
+                merge_cost,
+            },
+        )
+}
+
+fn posting() -> impl Strategy<Value = Posting> {
+    let non_empty = |amount: &IncompleteAmount| amount.num.is_some() || amount.currency.is_some();
+    (
+        option::of(flag()),
+        account(),
+        incomplete_amount(),
+        option::of(cost_spec()),
+        option::of(prop_oneof![
+            incomplete_amount()
+                .prop_filter("empty price", non_empty)
+                .prop_map(PriceSpec::PerUnit),
+            incomplete_amount()
+                .prop_filter("empty price", non_empty)
+                .prop_map(PriceSpec::Total),
+        ]),
+        meta(),
+    )
+        .prop_filter(
+            "a cost or price needs units",
+            move |(_, _, units, cost, price, _)| {
+                non_empty(units) || (cost.is_none() && price.is_none())
+            },
+        )
+        .prop_map(|(flag, account, units, cost, price, meta)| {
+            Posting::builder()
+                .flag(flag)
+                .account(account)
+                .units(units)
+                .cost(cost)
+                .price(price)
+                .meta(meta)
+                .build()
+        })
+}
+
+fn transaction() -> impl Strategy<Value = Directive> {
+    (
+        date(),
+        flag(),
+        option::of(text()),
+        text(),
+        tags(),
+        tags(),
+        vec(posting(), 0..4),
+        meta(),
+    )
+        .prop_map(
+            |(date, flag, payee, narration, tags, links, postings, meta)| {
+                Directive::Transaction(
+                    Transaction::builder()
+                        .date(date)
+                        .flag(flag)
+                        .payee(payee)
+                        .narration(narration)
+                        .tags(tags)
+                        .links(links)
+                        .postings(postings)
+                        .meta(meta)
+                        .build(),
+                )
+            },
+        )
+}
+
+fn directive() -> impl Strategy<Value = Directive> {
+    prop_oneof![
+        (
+            date(),
+            account(),
+            vec(currency(), 0..3),
+            option::of(prop::sample::select(vec![
+                Booking::Strict,
+                Booking::StrictWithSize,
+                Booking::None,
+                Booking::Average,
+                Booking::Fifo,
+                Booking::Lifo,
+            ])),
+            meta()
+        )
+            .prop_map(|(date, account, currencies, booking, meta)| {
+                Directive::Open(
+                    Open::builder()
+                        .date(date)
+                        .account(account)
+                        .currencies(currencies)
+                        .booking(booking)
+                        .meta(meta)
+                        .build(),
+                )
+            }),
+        (date(), account(), meta()).prop_map(|(date, account, meta)| {
+            Directive::Close(
+                Close::builder()
+                    .date(date)
+                    .account(account)
+                    .meta(meta)
+                    .build(),
+            )
+        }),
+        (
+            date(),
+            account(),
+            amount(),
+            option::of(unsigned_number()),
+            meta()
+        )
+            .prop_map(|(date, account, amount, tolerance, meta)| {
+                Directive::Balance(
+                    Balance::builder()
+                        .date(date)
+                        .account(account)
+                        .amount(amount)
+                        .tolerance(tolerance)
+                        .meta(meta)
+                        .build(),
+                )
+            }),
+        ("opt_[a-z_]{1,8}", text()).prop_map(|(name, val)| {
+            Directive::Option(BcOption::builder().name(name).val(val).build())
+        }),
+        (date(), currency(), meta()).prop_map(|(date, name, meta)| {
+            Directive::Commodity(
+                Commodity::builder()
+                    .date(date)
+                    .name(name)
+                    .meta(meta)
+                    .build(),
+            )
+        }),
+        (date(), text(), custom_args(), meta()).prop_map(|(date, name, args, meta)| {
+            Directive::Custom(
+                Custom::builder()
+                    .date(date)
+                    .name(name)
+                    .args(args)
+                    .meta(meta)
+                    .build(),
+            )
+        }),
+        (date(), account(), text(), tags(), tags(), meta()).prop_map(
+            |(date, account, path, tags, links, meta)| {
+                Directive::Document(
+                    Document::builder()
+                        .date(date)
+                        .account(account)
+                        .path(path)
+                        .tags(tags)
+                        .links(links)
+                        .meta(meta)
+                        .build(),
+                )
+            }
+        ),
+        (date(), text(), text(), meta()).prop_map(|(date, name, description, meta)| {
+            Directive::Event(
+                Event::builder()
+                    .date(date)
+                    .name(name)
+                    .description(description)
+                    .meta(meta)
+                    .build(),
+            )
+        }),
+        text()
+            .prop_map(|filename| Directive::Include(Include::builder().filename(filename).build())),
+        (date(), account(), text(), meta()).prop_map(|(date, account, comment, meta)| {
+            Directive::Note(
+                Note::builder()
+                    .date(date)
+                    .account(account)
+                    .comment(comment)
+                    .meta(meta)
+                    .build(),
+            )
+        }),
+        (date(), account(), account(), meta()).prop_map(|(date, to, from, meta)| {
+            Directive::Pad(
+                Pad::builder()
+                    .date(date)
+                    .pad_to_account(to)
+                    .pad_from_account(from)
+                    .meta(meta)
+                    .build(),
+            )
+        }),
+        (text(), option::of(text())).prop_map(|(module, config)| {
+            Directive::Plugin(Plugin::builder().module(module).config(config).build())
+        }),
+        (date(), currency(), amount(), meta()).prop_map(|(date, currency, amount, meta)| {
+            Directive::Price(
+                Price::builder()
+                    .date(date)
+                    .currency(currency)
+                    .amount(amount)
+                    .meta(meta)
+                    .build(),
+            )
+        }),
+        (date(), text(), text(), meta()).prop_map(|(date, name, query_string, meta)| {
+            Directive::Query(
+                Query::builder()
+                    .date(date)
+                    .name(name)
+                    .query_string(query_string)
+                    .meta(meta)
+                    .build(),
+            )
+        }),
+        transaction(),
+    ]
+}
+
+/// Ledgers as returned by the parser, optionally renaming the root of asset accounts.
+fn ledger() -> impl Strategy<Value = Ledger> {
+    (any::<bool>(), vec(directive(), 0..6)).prop_map(|(rename, mut directives)| {
+        if rename {
+            let option = BcOption::builder()
+                .name("name_assets".to_string())
+                .val("Activa".to_string())
+                .build();
+            directives.insert(0, Directive::Option(option));
+        }
+        Ledger { directives }
+    })
+}
+
+/// Clear the source and location of the directives, which rendering does not preserve.
+fn without_locations(mut ledger: Ledger) -> Ledger {
+    macro_rules! clear {
+        ($d:expr) => {{
+            $d.source = None;
+            $d.location = None;
+        }};
+    }
+    for directive in &mut ledger.directives {
+        match directive {
+            Directive::Open(d) => clear!(d),
+            Directive::Close(d) => clear!(d),
+            Directive::Balance(d) => clear!(d),
+            Directive::Option(d) => clear!(d),
+            Directive::Commodity(d) => clear!(d),
+            Directive::Custom(d) => clear!(d),
+            Directive::Document(d) => clear!(d),
+            Directive::Event(d) => clear!(d),
+            Directive::Include(d) => clear!(d),
+            Directive::Note(d) => clear!(d),
+            Directive::Pad(d) => clear!(d),
+            Directive::Plugin(d) => clear!(d),
+            Directive::Price(d) => clear!(d),
+            Directive::Query(d) => clear!(d),
+            Directive::Transaction(d) => {
+                clear!(d);
+                for posting in &mut d.postings {
+                    posting.location = None;
+                }
+            }
+            Directive::Unsupported => {}
+        }
+    }
+    ledger
+}
+
+proptest! {
+    #[test]
+    fn rendered_ledgers_parse_back(ledger in ledger()) {
+        let mut rendered = Vec::new();
+        render(&mut rendered, &ledger).unwrap();
+        let rendered = String::from_utf8(rendered).unwrap();
+        let parsed = parse(&rendered).map_err(|e| TestCaseError::fail(format!("{}\n{}", e, rendered)))?;
+        prop_assert_eq!(without_locations(parsed), ledger, "{}", rendered);
+    }
+}
@@ -1,4 +1,5 @@
 use beancount_core::render::render;
+use beancount_core::Directive;
 use beancount_parser::parse;
 use indoc::indoc;
 
@@ -104,3 +105,62 @@ fn test_transaction() -> anyhow::Result<()> {
     "#})?;
     Ok(())
 }
+
+#[test]
+fn test_escaped_strings() -> anyhow::Result<()> {
+    test_conversion(indoc! {r#"
+        2020-10-01 * "Joe \"The Plumber\"" "C:\\temp \n" #trip ^invoice
+          note: "a \"quoted\" note"
+          Assets:Trading    1 HOOL {500.00 USD, "lot \"A\""}
+          Assets:Cash
+    "#})?;
+    Ok(())
+}
+
+#[test]
+fn test_org_mode_headings() -> anyhow::Result<()> {
+    let source = indoc! {r#"
+        * Accounts
+        2020-01-01 open Assets:Cash
+
+        ** Spending
+        2020-10-01 * "Coffee"
+          Expenses:Coffee    3.50 USD
+          Assets:Cash
+    "#};
+    test_conversion(source)?;
+
+    let mut rendered = Vec::new();
+    render(&mut rendered, &parse(source).unwrap())?;
+    let rendered = String::from_utf8(rendered).unwrap();
+    assert!(rendered.starts_with("2020-01-01 open Assets:Cash\n"));
+    assert!(!rendered.contains("Accounts"));
+    assert!(!rendered.contains("Spending"));
+    Ok(())
+}
+
+#[test]
+fn test_custom_args() -> anyhow::Result<()> {
+    let source = "2014-07-09 custom \"budget\" 1 (-2) (-3.50) USD 4 FALSE (-5)\n";
+    test_conversion(source)?;
+
+    let ledger = parse(source).unwrap();
+    let mut rendered = Vec::new();
+    render(&mut rendered, &ledger)?;
+    let reparsed = parse(std::str::from_utf8(&rendered)?).unwrap();
+    match (&ledger.directives[0], &reparsed.directives[0]) {
+        (Directive::Custom(custom), Directive::Custom(reparsed)) => {
+            assert_eq!(custom.args.len(), 6);
+            assert_eq!(reparsed.args, custom.args);
+        }
+        other => panic!("unexpected directives {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_display_org_mode_headings() {
+    let ledger = parse("* Heading\n2020-01-01 open Assets:Cash\n").unwrap();
+    assert_eq!(ledger.to_string(), "2020-01-01 open Assets:Cash\n\n");
+    assert_eq!(format!("{:#}", ledger), ledger.to_string());
+}
@@ -0,0 +1,122 @@
+use beancount_core::Ledger;
+use beancount_parser::parse;
+use indoc::indoc;
+use serde_json::json;
+
+const LEDGER: &str = indoc! {r#"
+    option "title" "Example"
+    plugin "beancount.plugins.auto_accounts"
+    include "other.beancount"
+
+    2014-01-01 open Assets:Broker HOOL,USD "FIFO"
+      description: "Broker account"
+    2014-01-01 commodity HOOL
+    2014-05-05 * "Broker" "Buy" #invest ^trade-1
+      Assets:Broker      10 HOOL {502.12 # 9.95 USD, 2014-05-05, "lot"} @ 510 USD
+        confirmed: TRUE
+      ! Assets:Cash   -5021.20 USD
+      Expenses:Fees
+    2014-06-01 balance Assets:Broker 10 ~ 0.01 HOOL
+    2014-06-01 pad Assets:Cash Equity:Opening-Balances
+    2014-06-01 note Assets:Cash "Called the \"bank\""
+    2014-06-01 document Assets:Cash "statement.pdf"
+    2014-06-01 event "location" "Paris"
+    2014-06-01 query "cash" "SELECT account"
+    2014-06-01 price HOOL 520.00 USD
+    2014-06-01 custom "budget" Expenses:Food "monthly" 400.00 USD TRUE 2014-07-01
+    2014-12-31 close Assets:Cash
+    "#};
+
+#[test]
+fn json_round_trip() {
+    let ledger = parse(LEDGER).unwrap();
+    let json = serde_json::to_string(&ledger).unwrap();
+    assert_eq!(serde_json::from_str::<Ledger>(&json).unwrap(), ledger);
+}
+
+#[test]
+fn json_shape() {
+    let ledger = parse(LEDGER).unwrap();
+    let value = serde_json::to_value(&ledger).unwrap();
+    let directives = value["directives"].as_array().unwrap();
+    let types: Vec<_> = directives
+        .iter()
+        .map(|directive| directive["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        types,
+        [
+            "option",
+            "plugin",
+            "include",
+            "open",
+            "commodity",
+            "transaction",
+            "balance",
+            "pad",
+            "note",
+            "document",
+            "event",
+            "query",
+            "price",
+            "custom",
+            "close"
+        ]
+    );
+
+    assert_eq!(directives[3]["currencies"], json!(["HOOL", "USD"]));
+    assert_eq!(directives[3]["booking"], "FIFO");
+    assert_eq!(
+        directives[3]["meta"]["description"],
+        json!({"type": "text", "value": "Broker account"})
+    );
+
+    let txn = &directives[5];
+    assert_eq!(txn["date"], "2014-05-05");
+    assert_eq!(txn["flag"], "*");
+    assert_eq!(txn["tags"], json!(["invest"]));
+    assert_eq!(txn["links"], json!(["trade-1"]));
+    assert_eq!(txn["location"]["line"], 8);
+    let posting = &txn["postings"][0];
+    assert_eq!(posting["account"], "Assets:Broker");
+    assert_eq!(posting["units"], json!({"num": "10", "currency": "HOOL"}));
+    assert_eq!(
+        posting["cost"],
+        json!({
+            "number_per": "502.12",
+            "number_total": "9.95",
+            "currency": "USD",
+            "date": "2014-05-05",
+            "label": "lot",
+            "merge_cost": false
+        })
+    );
+    assert_eq!(
+        posting["price"],
+        json!({"type": "per_unit", "num": "510", "currency": "USD"})
+    );
+    assert_eq!(
+        posting["meta"]["confirmed"],
+        json!({"type": "bool", "value": true})
+    );
+    assert_eq!(txn["postings"][1]["flag"], "!");
+    assert_eq!(
+        txn["postings"][2]["units"],
+        json!({"num": null, "currency": null})
+    );
+
+    assert_eq!(directives[6]["tolerance"], "0.01");
+    assert_eq!(
+        directives[13]["args"][2],
+        json!({"type": "amount", "value": {"num": "400.00", "currency": "USD"}})
+    );
+}
+
+#[test]
+fn numbers_must_be_strings() {
+    let amount = json!({"num": 0.1, "currency": "USD"});
+    assert!(serde_json::from_value::<beancount_core::Amount>(amount).is_err());
+    let amount = json!({"num": "0.1", "currency": "USD"});
+    assert!(serde_json::from_value::<beancount_core::Amount>(amount).is_ok());
+    assert!(serde_json::from_value::<beancount_core::Date>(json!("2014-02-30")).is_err());
+}
@@ -0,0 +1,282 @@
+use beancount_core::booking::book;
+use beancount_core::metadata::MetaValue;
+use beancount_core::plugins::{PluginError, PluginRegistry};
+use beancount_core::Directive;
+use beancount_parser::parse;
+use indoc::indoc;
+
+/// Parses and books `source`, then runs the plugins it names.
+fn run(source: &str) -> (Vec<Directive>, Vec<PluginError>) {
+    let (directives, errors) = book(parse(source).unwrap().directives, Default::default());
+    assert!(errors.is_empty(), "{:?}", errors);
+    PluginRegistry::with_standard_plugins().run(directives)
+}
+
+fn messages(errors: &[PluginError]) -> Vec<String> {
+    errors.iter().map(|e| e.message.clone()).collect()
+}
+
+#[test]
+fn implicit_prices() {
+    let source = indoc! {r#"
+        plugin "beancount.plugins.implicit_prices"
+
+        2021-01-10 * "Buy"
+          Assets:Broker     10 HOOL {100.00 USD}
+          Assets:Cash
+
+        2021-01-10 * "Buy more"
+          Assets:Broker      5 HOOL {100.00 USD}
+          Assets:Cash
+
+        2021-01-20 * "Sell"
+          Assets:Broker     -5 HOOL {100.00 USD} @ 110.00 USD
+          Assets:Cash      550.00 USD
+          Income:Gains
+
+        2021-01-21 * "Sell at cost"
+          Assets:Broker     -5 HOOL {100.00 USD}
+          Assets:Cash
+    "#};
+    let (directives, errors) = run(source);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let prices: Vec<_> = directives
+        .iter()
+        .filter_map(|d| match d {
+            Directive::Price(price) => Some((
+                price.date.to_string(),
+                price.amount.num.to_string(),
+                price.meta["__implicit_prices__"].clone(),
+            )),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        prices,
+        [
+            (
+                "2021-01-10".to_string(),
+                "100.00".to_string(),
+                MetaValue::Text("from_cost".into())
+            ),
+            (
+                "2021-01-20".to_string(),
+                "110.00".to_string(),
+                MetaValue::Text("from_price".into())
+            ),
+        ]
+    );
+    assert!(matches!(directives[2], Directive::Price(_)));
+}
+
+#[test]
+fn auto_accounts() {
+    let source = indoc! {r#"
+        plugin "beancount.plugins.auto_accounts"
+
+        2021-01-01 open Assets:Cash
+
+        2021-01-10 * "Lunch"
+          Expenses:Food      12.00 USD
+          Assets:Cash
+
+        2021-01-05 balance Liabilities:Card   0 USD
+    "#};
+    let (directives, errors) = run(source);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let opens: Vec<_> = directives
+        .iter()
+        .filter_map(|d| match d {
+            Directive::Open(open) => Some(format!("{} {}", open.date, open.account)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        opens,
+        [
+            "2021-01-01 Assets:Cash",
+            "2021-01-05 Liabilities:Card",
+            "2021-01-10 Expenses:Food"
+        ]
+    );
+}
+
+#[test]
+fn leafonly() {
+    let source = indoc! {r#"
+        plugin "beancount.plugins.leafonly"
+
+        2021-01-01 open Expenses:Food
+        2021-01-01 open Expenses:Food:Restaurant
+
+        2021-01-10 * "Lunch"
+          Expenses:Food               12.00 USD
+          Expenses:Food:Restaurant     8.00 USD
+          Assets:Cash
+    "#};
+    let (_, errors) = run(source);
+    assert_eq!(
+        messages(&errors),
+        ["Non-leaf account 'Expenses:Food' has postings on it"]
+    );
+    assert!(matches!(errors[0].directive, Some(Directive::Open(_))));
+    assert_eq!(errors[0].module, "beancount.plugins.leafonly");
+}
+
+#[test]
+fn noduplicates() {
+    let source = indoc! {r#"
+        plugin "beancount.plugins.noduplicates"
+
+        2021-01-10 * "Lunch"
+          Expenses:Food      12.00 USD
+          Assets:Cash
+
+        2021-01-10 * "Lunch"
+          id: "different metadata"
+          Expenses:Food      12.00 USD
+          Assets:Cash
+
+        2021-01-10 * "Lunch"
+          Expenses:Food      13.00 USD
+          Assets:Cash
+
+        2021-01-10 price HOOL  100 USD
+        2021-01-10 price HOOL  100 USD
+    "#};
+    let (_, errors) = run(source);
+    assert_eq!(
+        messages(&errors),
+        [
+            "Duplicate entry on 2021-01-10",
+            "Duplicate entry on 2021-01-10"
+        ]
+    );
+    assert!(matches!(
+        errors[0].directive,
+        Some(Directive::Transaction(_))
+    ));
+    assert!(matches!(errors[1].directive, Some(Directive::Price(_))));
+}
+
+#[test]
+fn check_commodity() {
+    let source = indoc! {r#"
+        plugin "beancount.plugins.check_commodity"
+
+        2021-01-01 commodity USD
+        2021-01-01 open Assets:Broker HOOL
+
+        2021-01-10 * "Buy"
+          Assets:Broker      1 HOOL {100.00 USD}
+          Assets:Cash
+
+        2021-01-11 price EUR  1.20 USD
+        2021-01-12 price EUR  1.21 USD
+    "#};
+    let (_, errors) = run(source);
+    assert_eq!(
+        messages(&errors),
+        [
+            "Missing Commodity directive for 'HOOL'",
+            "Missing Commodity directive for 'EUR'"
+        ]
+    );
+}
+
+#[test]
+fn onecommodity() {
+    let source = indoc! {r#"
+        plugin "beancount.plugins.onecommodity"
+
+        2021-01-01 open Assets:Cash
+        2021-01-01 open Assets:Wallet USD,EUR
+        2021-01-01 open Assets:Other
+          onecommodity: FALSE
+
+        2021-01-10 * "Deposits"
+          Assets:Cash       10.00 USD
+          Assets:Wallet     10.00 USD
+          Assets:Other      10.00 USD
+          Equity:Opening
+
+        2021-01-11 * "Deposits"
+          Assets:Cash       10.00 EUR
+          Assets:Wallet     10.00 EUR
+          Assets:Other      10.00 EUR
+          Equity:Opening
+    "#};
+    let (_, errors) = run(source);
+    assert_eq!(
+        messages(&errors),
+        [
+            "More than one currency in account 'Assets:Cash': USD,EUR",
+            "More than one currency in account 'Equity:Opening': USD,EUR"
+        ]
+    );
+}
+
+#[test]
+fn unique_prices() {
+    let source = indoc! {r#"
+        plugin "beancount.plugins.unique_prices"
+
+        2021-01-10 price HOOL  100 USD
+        2021-01-10 price HOOL  100 USD
+        2021-01-10 price HOOL  101 USD
+        2021-01-10 price HOOL   90 EUR
+        2021-01-11 price HOOL  102 USD
+    "#};
+    let (_, errors) = run(source);
+    assert_eq!(
+        messages(&errors),
+        ["Disagreeing price entries for HOOL in USD on 2021-01-10: 100 vs. 101"]
+    );
+}
+
+#[test]
+fn coherent_cost() {
+    let source = indoc! {r#"
+        plugin "beancount.plugins.coherent_cost"
+
+        2021-01-10 * "Buy"
+          Assets:Broker      10 HOOL {100.00 USD}
+          Assets:Cash
+
+        2021-01-11 * "Transfer"
+          Assets:Broker     -10 HOOL {100.00 USD}
+          Assets:Other       10 HOOL @ 100.00 USD
+    "#};
+    let (_, errors) = run(source);
+    assert_eq!(
+        messages(&errors),
+        ["Currency 'HOOL' is used both with and without cost"]
+    );
+}
+
+#[test]
+fn sellgains() {
+    let source = indoc! {r#"
+        plugin "beancount.plugins.sellgains"
+
+        2021-01-10 * "Buy"
+          Assets:Broker      10 HOOL {100.00 USD}
+          Assets:Cash
+
+        2021-01-20 * "Sell"
+          Assets:Broker      -5 HOOL {100.00 USD} @ 110.00 USD
+          Assets:Cash      549.95 USD
+          Expenses:Fees      0.05 USD
+          Income:Gains
+
+        2021-01-21 * "Sell with wrong gains"
+          Assets:Broker      -5 HOOL {100.00 USD} @ 110.00 USD
+          Assets:Cash      500.00 USD
+          Income:Gains
+    "#};
+    let (_, errors) = run(source);
+    assert_eq!(
+        messages(&errors),
+        ["Invalid price vs. proceeds/gains: 550.00 USD vs. 500.00 USD"]
+    );
+}
@@ -0,0 +1,246 @@
+use beancount_core::interpolate::{infer_tolerances, ToleranceOptions};
+use beancount_core::ops::validation::{
+    validate_accounts, validate_transaction_balances, ValidationError,
+};
+use beancount_core::{Directive, Transaction};
+use beancount_parser::parse;
+use indoc::indoc;
+use rust_decimal::Decimal;
+
+fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+fn check_balances(source: &str) -> Vec<ValidationError> {
+    let directives = parse(source).unwrap().directives;
+    let options = ToleranceOptions::from_directives(&directives);
+    validate_transaction_balances(&directives, &options)
+}
+
+fn first_transaction(source: &str) -> Transaction {
+    parse(source)
+        .unwrap()
+        .directives
+        .into_iter()
+        .find_map(|d| match d {
+            Directive::Transaction(txn) => Some(txn),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn infers_tolerances_from_precision() {
+    let txn = first_transaction(indoc! {r#"
+        2020-01-01 * "Trade"
+          Assets:Cash        -10.015 USD
+          Assets:Brokerage     1.2 EUR
+          Assets:Brokerage     3 HOOL
+          Assets:Cash         -1.20 EUR
+    "#});
+    let tolerances = infer_tolerances(&txn.postings, &ToleranceOptions::default());
+    assert_eq!(tolerances.get("USD"), Some(&dec("0.0005")));
+    assert_eq!(tolerances.get("EUR"), Some(&dec("0.05")));
+    assert_eq!(tolerances.get("HOOL"), None);
+}
+
+#[test]
+fn balanced_within_tolerance() {
+    let errors = check_balances(indoc! {r#"
+        2020-01-01 * "Groceries"
+          Expenses:Food      12.344 USD
+          Assets:Cash       -12.34 USD
+    "#});
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    let errors = check_balances(indoc! {r#"
+        2020-01-01 * "Buy"
+          Assets:Brokerage    10 HOOL {500.00 USD}
+          Assets:Cash     -5000.00 USD
+    "#});
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn reports_imbalance() {
+    let errors = check_balances(indoc! {r#"
+        2020-01-01 * "Groceries"
+          Expenses:Food      12.35 USD
+          Assets:Cash       -12.34 USD
+
+        2020-01-02 * "Exchange"
+          Assets:Checking   -400.00 USD @ 1.09 CAD
+          Assets:Savings     436.00 CAD
+    "#});
+    assert_eq!(errors.len(), 1);
+    match &errors[0] {
+        ValidationError::Unbalanced {
+            transaction,
+            residual,
+        } => {
+            assert_eq!(transaction.narration, "Groceries");
+            assert_eq!(residual.len(), 1);
+            assert_eq!(residual[0].num, dec("0.01"));
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert_eq!(
+        errors[0].to_string(),
+        "transaction does not balance: 0.01 USD"
+    );
+}
+
+#[test]
+fn honours_tolerance_options() {
+    let source = indoc! {r#"
+        2020-01-01 * "Shares"
+          Assets:Brokerage    10 HOOL
+          Assets:Other        -9 HOOL
+    "#};
+    assert_eq!(check_balances(source).len(), 1);
+
+    let options = indoc! {r#"
+        option "inferred_tolerance_default" "HOOL:1"
+    "#};
+    assert!(check_balances(&(options.to_owned() + source)).is_empty());
+
+    let options = indoc! {r#"
+        option "inferred_tolerance_default" "*:1"
+    "#};
+    assert!(check_balances(&(options.to_owned() + source)).is_empty());
+
+    let source = indoc! {r#"
+        2020-01-01 * "Groceries"
+          Expenses:Food      12.35 USD
+          Assets:Cash       -12.34 USD
+    "#};
+    assert_eq!(check_balances(source).len(), 1);
+    let options = indoc! {r#"
+        option "inferred_tolerance_multiplier" "1.5"
+    "#};
+    assert!(check_balances(&(options.to_owned() + source)).is_empty());
+}
+
+#[test]
+fn reports_incomplete_transactions() {
+    let errors = check_balances(indoc! {r#"
+        2020-01-01 * "Groceries"
+          Expenses:Food      12.35 USD
+          Assets:Cash
+    "#});
+    assert!(matches!(errors[0], ValidationError::Incomplete { .. }));
+}
+
+fn check_accounts(source: &str) -> Vec<ValidationError> {
+    validate_accounts(&parse(source).unwrap().directives)
+}
+
+#[test]
+fn accounts_must_be_open() {
+    let errors = check_accounts(indoc! {r#"
+        2020-01-01 open Assets:Cash
+        2020-01-01 open Expenses:Food
+
+        2019-12-31 * "Too early"
+          Expenses:Food       5.00 USD
+          Assets:Cash
+
+        2020-01-01 * "Same day"
+          Expenses:Food       5.00 USD
+          Expenses:Food      -5.00 USD
+
+        2020-01-02 balance Assets:Unknown   0 USD
+        2020-01-02 note Assets:Unknown "comment"
+        2020-01-02 document Assets:Unknown "statement.pdf"
+        2020-01-02 pad Assets:Cash Equity:Opening
+
+        2020-02-01 close Assets:Cash
+        2020-02-01 balance Assets:Cash   0 USD
+        2020-02-02 balance Assets:Cash   0 USD
+    "#});
+    assert_eq!(errors.len(), 7, "{:?}", errors);
+    assert!(errors
+        .iter()
+        .all(|e| matches!(e, ValidationError::InactiveAccount { .. })));
+    assert_eq!(
+        errors[1].to_string(),
+        "account Assets:Cash is not open on 2019-12-31"
+    );
+    assert_eq!(
+        errors[4].to_string(),
+        "account Equity:Opening is not open on 2020-01-02"
+    );
+    assert_eq!(
+        errors[6].to_string(),
+        "account Assets:Cash is not open on 2020-02-02"
+    );
+}
+
+#[test]
+fn duplicate_open_and_close() {
+    let errors = check_accounts(indoc! {r#"
+        2020-01-01 open Assets:Cash
+        2020-03-01 open Assets:Cash
+        2020-04-01 close Assets:Cash
+        2020-05-01 close Assets:Cash
+    "#});
+    assert_eq!(errors.len(), 2);
+    match &errors[0] {
+        ValidationError::DuplicateOpen { open, previous } => {
+            assert_eq!(open.date.to_string(), "2020-03-01");
+            assert_eq!(previous.date.to_string(), "2020-01-01");
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert!(matches!(errors[1], ValidationError::InactiveAccount { .. }));
+}
+
+#[test]
+fn closed_accounts_must_be_empty() {
+    let errors = check_accounts(indoc! {r#"
+        2020-01-01 open Assets:Cash
+        2020-01-01 open Assets:Checking
+
+        2020-01-05 * "Withdraw"
+          Assets:Cash         20.00 USD
+          Assets:Checking    -20.00 USD
+
+        2020-01-06 * "Deposit"
+          Assets:Cash        -15.00 USD
+          Assets:Checking     15.00 USD
+
+        2020-02-01 close Assets:Cash
+    "#});
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].to_string(),
+        "account Assets:Cash is closed with a non-zero balance: 5.00 USD"
+    );
+}
+
+#[test]
+fn currency_constraints() {
+    let errors = check_accounts(indoc! {r#"
+        2020-01-01 open Assets:Cash USD,CAD
+        2020-01-01 open Assets:Other
+
+        2020-01-05 * "Exchange"
+          Assets:Cash         20.00 CAD
+          Assets:Cash        -10.00 EUR
+          Assets:Other
+    "#});
+    assert_eq!(errors.len(), 1);
+    match &errors[0] {
+        ValidationError::DisallowedCurrency {
+            posting, currency, ..
+        } => {
+            assert_eq!(*posting, 1);
+            assert_eq!(currency, "EUR");
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert_eq!(
+        errors[0].to_string(),
+        "currency EUR is not allowed in account Assets:Cash"
+    );
+}